@@ -0,0 +1,190 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    braced, token, Attribute, Error, FnArg, GenericArgument, Ident, ImplItem, ImplItemMethod,
+    ItemImpl, PathArguments, Result, ReturnType, Token, Type,
+};
+
+/// The arguments of `#[handler(Variant)]`, or `#[handler(Variant { a, b })]` for a variant with
+/// named fields
+struct HandlerArgs {
+    variant: Ident,
+    names: Option<Punctuated<Ident, Token![,]>>,
+}
+
+impl Parse for HandlerArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let variant = input.parse()?;
+        let names = if input.peek(token::Brace) {
+            let content;
+            braced!(content in input);
+            Some(content.parse_terminated(Ident::parse)?)
+        } else {
+            None
+        };
+        Ok(HandlerArgs { variant, names })
+    }
+}
+
+pub fn expand(attr: TokenStream, item: TokenStream) -> Result<TokenStream> {
+    let mut input: ItemImpl = syn::parse2(item)?;
+    let mut message: Option<Type> = if attr.is_empty() {
+        None
+    } else {
+        Some(syn::parse2(attr)?)
+    };
+
+    let mut arms = Vec::new();
+    let mut fallback = None;
+    for item in input.items.iter_mut() {
+        let method = match item {
+            ImplItem::Method(method) => method,
+            _ => continue,
+        };
+        if take_attribute(&mut method.attrs, "fallback").is_some() {
+            if message.is_none() {
+                message = Some(context_type(method)?);
+            }
+            let name = &method.sig.ident;
+            fallback = Some(quote! { _ => self.#name(context, message), });
+            continue;
+        }
+        let handler = match take_attribute(&mut method.attrs, "handler") {
+            Some(handler) => handler,
+            None => continue,
+        };
+        if message.is_none() {
+            message = Some(context_type(method)?);
+        }
+
+        let args = if handler.tokens.is_empty() {
+            HandlerArgs {
+                variant: Ident::new(
+                    &camel_case(&method.sig.ident.to_string()),
+                    Span::call_site(),
+                ),
+                names: None,
+            }
+        } else {
+            handler.parse_args::<HandlerArgs>()?
+        };
+        let variant = &args.variant;
+        let fields: Vec<Ident> = (2..method.sig.inputs.len())
+            .map(|i| format_ident!("field{}", i - 2))
+            .collect();
+        let pattern = match &args.names {
+            Some(names) if names.len() != fields.len() => {
+                return Err(Error::new(
+                    handler.span(),
+                    format!(
+                        "#[handler] names {} fields of {}, but the method takes {}",
+                        names.len(),
+                        variant,
+                        fields.len()
+                    ),
+                ))
+            }
+            Some(names) => {
+                let names = names.iter();
+                quote! { #variant { #(#names: #fields),* } }
+            }
+            None if fields.is_empty() => quote! { #variant { .. } },
+            None => quote! { #variant(#(#fields),*) },
+        };
+        let name = &method.sig.ident;
+        let call = match method.sig.output {
+            ReturnType::Default => quote! {
+                {
+                    self.#name(context, #(#fields),*);
+                    ::rust_isolate::IsolateFlow::Continue
+                }
+            },
+            ReturnType::Type(..) => quote! { self.#name(context, #(#fields),*) },
+        };
+        arms.push((pattern, call));
+    }
+
+    let message = match message {
+        Some(message) => message,
+        None => {
+            return Err(Error::new(
+                input.span(),
+                "isolate_handler needs at least one #[handler] method, or a message type",
+            ))
+        }
+    };
+    let arms = arms.into_iter().map(|(pattern, call)| {
+        quote! { #message::#pattern => #call }
+    });
+
+    let self_ty = &input.self_ty;
+    let (impl_generics, _, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        #input
+
+        impl #impl_generics ::rust_isolate::IsolateHandler<#message> for #self_ty #where_clause {
+            #[allow(unreachable_patterns)]
+            fn handle(
+                &mut self,
+                context: &::rust_isolate::IsolateContext<#message>,
+                message: #message,
+            ) -> ::rust_isolate::IsolateFlow {
+                match message {
+                    #(#arms,)*
+                    #fallback
+                }
+            }
+        }
+    })
+}
+
+/// Remove an attribute from a method, returning it if it was there
+fn take_attribute(attrs: &mut Vec<Attribute>, name: &str) -> Option<Attribute> {
+    let index = attrs.iter().position(|a| a.path.is_ident(name))?;
+    Some(attrs.remove(index))
+}
+
+/// Find the message type from the `&IsolateContext<Message>` argument of a handler method
+fn context_type(method: &ImplItemMethod) -> Result<Type> {
+    let error = || {
+        Error::new(
+            method.sig.span(),
+            "handler methods take &mut self, then &IsolateContext<Message>",
+        )
+    };
+    let context = match method.sig.inputs.iter().nth(1) {
+        Some(FnArg::Typed(arg)) => &arg.ty,
+        _ => return Err(error()),
+    };
+    let path = match context.as_ref() {
+        Type::Reference(reference) => match reference.elem.as_ref() {
+            Type::Path(path) => path,
+            _ => return Err(error()),
+        },
+        _ => return Err(error()),
+    };
+    let arguments = match path.path.segments.last().map(|s| &s.arguments) {
+        Some(PathArguments::AngleBracketed(arguments)) => arguments,
+        _ => return Err(error()),
+    };
+    match arguments.args.first() {
+        Some(GenericArgument::Type(message)) => Ok(message.clone()),
+        _ => Err(error()),
+    }
+}
+
+/// Convert a method name to the name of its variant; new_message becomes NewMessage
+fn camel_case(name: &str) -> String {
+    name.split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
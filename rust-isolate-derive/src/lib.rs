@@ -0,0 +1,22 @@
+extern crate proc_macro;
+
+mod isolate_handler;
+
+use proc_macro::TokenStream;
+
+/// Implement IsolateHandler for a type from the `#[handler]` methods of an impl block.
+///
+/// Each handler method takes `&mut self`, the `&IsolateContext` and then the fields of the
+/// message variant it handles, and returns an IsolateFlow or nothing. The variant is named after
+/// the method in CamelCase, or given as `#[handler(Variant)]`. The fields are matched as a tuple
+/// variant; for a variant with named fields, list them in the order the method takes them, as
+/// `#[handler(Variant { a, b })]`. A `#[fallback]` method taking the context and the message
+/// handles every other variant; without one, every variant needs a handler, and the compiler
+/// lists the variants that don't have one.
+/// The message type is taken from the context type, or given as `#[isolate_handler(Message)]`.
+#[proc_macro_attribute]
+pub fn isolate_handler(attr: TokenStream, item: TokenStream) -> TokenStream {
+    isolate_handler::expand(attr.into(), item.into())
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
@@ -1,14 +1,43 @@
-use crate::IsolateChannel;
-use crate::IsolateIdentity;
+use crate::IsolateContext;
+use crate::IsolateRuntimeError;
+use std::any::Any;
 
 /// Isolate the isolate worker that is run in its own thread to process tasks.
 pub trait Isolate<T: Send + 'static> {
+    /// Args is the set of spawn-time arguments handed to each new worker by `spawn_with`.
+    /// Isolates that don't need any per-worker configuration should use `()`.
+    type Args: Send + 'static;
+
     /// Spawn is invoked when a new connection is opened to the isolate.
     /// It should return a function that can be invoked in a remote thread.
-    /// The spawn function should handle incoming events on the channel until it closes.
+    /// The spawn function should handle incoming events on the context channel until it closes,
+    /// or until the worker is stopped.
     fn spawn(
         &self,
-        identity: IsolateIdentity,
-        channel: IsolateChannel<T>,
+        context: IsolateContext<T>,
+        args: Self::Args,
     ) -> Box<dyn FnMut() + Send + 'static>;
 }
+
+/// IsolateSpawn is the type erased form of an Isolate, so runtimes don't need to carry the
+/// Args type around; the args are checked against the isolate when a worker is spawned.
+pub(crate) trait IsolateSpawn<T: Send + 'static> {
+    fn spawn_any(
+        &self,
+        context: IsolateContext<T>,
+        args: Box<dyn Any + Send + 'static>,
+    ) -> Result<Box<dyn FnMut() + Send + 'static>, IsolateRuntimeError>;
+}
+
+impl<T: Send + 'static, I: Isolate<T>> IsolateSpawn<T> for I {
+    fn spawn_any(
+        &self,
+        context: IsolateContext<T>,
+        args: Box<dyn Any + Send + 'static>,
+    ) -> Result<Box<dyn FnMut() + Send + 'static>, IsolateRuntimeError> {
+        match args.downcast::<I::Args>() {
+            Ok(args) => Ok(self.spawn(context, *args)),
+            Err(_) => Err(IsolateRuntimeError::InvalidArgsType),
+        }
+    }
+}
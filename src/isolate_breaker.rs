@@ -0,0 +1,259 @@
+pub(crate) mod isolate_breaker_error;
+pub(crate) mod isolate_breaker_shared;
+pub(crate) mod isolate_breaker_state;
+pub(crate) mod isolate_breaker_target;
+
+use crate::isolate_breaker::isolate_breaker_shared::IsolateBreakerShared;
+use crate::isolate_breaker::isolate_breaker_target::IsolateBreakerTarget;
+use crate::IsolateBreakerError;
+use crate::IsolateBreakerState;
+use crate::IsolateChannel;
+use crate::IsolateCorrelationId;
+use crate::IsolateEnvelope;
+use crate::IsolateIdentity;
+use crate::IsolateRuntimeRef;
+use crossbeam::{bounded, unbounded, Receiver, Select, Sender};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Picks out the replies that count as failures
+type IsolateBreakerFailure<T> = Arc<dyn Fn(&T) -> bool + Send + Sync>;
+
+/// The asks waiting for a reply, by the correlation id of their request
+type IsolateBreakerWaiting<T> = Arc<Mutex<HashMap<IsolateCorrelationId, Sender<T>>>>;
+
+/// IsolateCircuitBreaker guards requests to a worker that may be overloaded or crashing.
+/// Failed sends, timed out asks and failure replies are counted; after `threshold` failures in a
+/// row the breaker opens and requests fail immediately with `IsolateBreakerError::Open`. Once
+/// `reset_after` has passed the breaker half opens and lets one trial request through, closing
+/// again if it succeeds. A trial `send` succeeds once the message is queued for the worker, as it
+/// can't tell whether the worker handled it; a trial `ask` waits for the reply.
+/// A breaker guarding a worker of a runtime tells the time by the runtime's clock, so under the
+/// test scheduler it resets on virtual time.
+/// Clones share the same state, so peers can share a breaker.
+/// To `ask`, the worker must run over `IsolateEnvelope`s and send its replies out of its channel
+/// with the correlation id of the request, as `envelope_reply` does; each ask takes the reply to
+/// its own request, and late replies to asks that timed out are dropped.
+pub struct IsolateCircuitBreaker<T: Send + 'static> {
+    target: IsolateBreakerTarget<T>,
+    threshold: u32,
+    reset_after: Duration,
+    failure: Option<IsolateBreakerFailure<T>>,
+    shared: Arc<Mutex<IsolateBreakerShared>>,
+    waiting: IsolateBreakerWaiting<T>,
+}
+
+impl<T: Send + 'static> IsolateCircuitBreaker<T> {
+    /// Guard requests sent on a worker's channel; by default the breaker opens after 5
+    /// failures, and half opens after 30 seconds
+    pub fn new(channel: IsolateChannel<T>) -> IsolateCircuitBreaker<T> {
+        IsolateCircuitBreaker::from(IsolateBreakerTarget::Channel(channel))
+    }
+
+    /// Guard requests sent to a worker of a runtime, looking it up by identity for each one;
+    /// a worker that isn't bound to the runtime counts as a failure
+    pub fn for_worker(
+        runtime: IsolateRuntimeRef<T>,
+        identity: IsolateIdentity,
+    ) -> IsolateCircuitBreaker<T> {
+        IsolateCircuitBreaker::from(IsolateBreakerTarget::Worker(runtime, identity))
+    }
+
+    fn from(target: IsolateBreakerTarget<T>) -> IsolateCircuitBreaker<T> {
+        IsolateCircuitBreaker {
+            target,
+            threshold: 5,
+            reset_after: Duration::from_secs(30),
+            failure: None,
+            shared: Arc::new(Mutex::new(IsolateBreakerShared::new())),
+            waiting: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Set how many failures in a row open the breaker
+    pub fn with_threshold(mut self, threshold: u32) -> IsolateCircuitBreaker<T> {
+        self.threshold = threshold.max(1);
+        self
+    }
+
+    /// Set how long the breaker stays open before it lets a trial request through
+    pub fn with_reset_after(mut self, reset_after: Duration) -> IsolateCircuitBreaker<T> {
+        self.reset_after = reset_after;
+        self
+    }
+
+    /// Count the replies a function picks out as failures; they are still returned by `ask`
+    pub fn with_failure(
+        mut self,
+        failure: impl Fn(&T) -> bool + Send + Sync + 'static,
+    ) -> IsolateCircuitBreaker<T> {
+        self.failure = Some(Arc::new(failure));
+        self
+    }
+
+    /// Send a message without waiting for a reply, failing immediately if the breaker is open.
+    /// A message that is queued counts as a success, so as the trial of a half open breaker it
+    /// closes the breaker without the worker having handled it.
+    pub fn send(&self, message: T) -> Result<(), IsolateBreakerError> {
+        let channel = self.begin()?;
+        match channel.send(message) {
+            Ok(()) => {
+                self.record(true);
+                Ok(())
+            }
+            Err(err) => Err(self.failed(err.into())),
+        }
+    }
+
+    /// Return the state of the breaker
+    pub fn state(&self) -> IsolateBreakerState {
+        let now = self.target.now();
+        match self.shared.lock() {
+            Ok(mut shared) => shared.state(self.reset_after, now),
+            Err(_) => IsolateBreakerState::Open,
+        }
+    }
+
+    /// Subscribe to the breaker's state changes; the subscription ends when the receiver is
+    /// dropped.
+    pub fn subscribe(&self) -> Receiver<IsolateBreakerState> {
+        let (sender, receiver) = unbounded();
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.subscribe(sender);
+        }
+        receiver
+    }
+
+    /// Close the breaker, forgetting any failures
+    pub fn reset(&self) -> Result<(), IsolateBreakerError> {
+        match self.shared.lock() {
+            Ok(mut shared) => {
+                shared.reset();
+                Ok(())
+            }
+            Err(_) => Err(IsolateBreakerError::InternalSyncError),
+        }
+    }
+
+    /// Check the breaker lets a request through, and find the channel to send it on
+    fn begin(&self) -> Result<IsolateChannel<T>, IsolateBreakerError> {
+        let now = self.target.now();
+        match self.shared.lock() {
+            Ok(mut shared) => {
+                if !shared.permit(self.reset_after, now) {
+                    return Err(IsolateBreakerError::Open);
+                }
+            }
+            Err(_) => return Err(IsolateBreakerError::InternalSyncError),
+        }
+        self.target.channel().map_err(|err| self.failed(err))
+    }
+
+    /// Count a failed request, passing on its error
+    fn failed(&self, err: IsolateBreakerError) -> IsolateBreakerError {
+        self.record(false);
+        err
+    }
+
+    fn record(&self, success: bool) {
+        let now = self.target.now();
+        if let Ok(mut shared) = self.shared.lock() {
+            if success {
+                shared.succeeded();
+            } else {
+                shared.failed(self.threshold, now);
+            }
+        }
+    }
+}
+
+impl<T: Send + 'static> IsolateCircuitBreaker<IsolateEnvelope<T>> {
+    /// Send a request and wait for the reply to it, failing immediately if the breaker is open
+    pub fn ask(
+        &self,
+        request: impl Into<IsolateEnvelope<T>>,
+        timeout: Duration,
+    ) -> Result<IsolateEnvelope<T>, IsolateBreakerError> {
+        let channel = self.begin()?;
+        let request = request.into();
+        let correlation = request.correlation;
+        let (sender, receiver) = bounded(1);
+        match self.waiting.lock() {
+            Ok(mut waiting) => waiting.insert(correlation, sender),
+            Err(_) => return Err(IsolateBreakerError::InternalSyncError),
+        };
+        let result = match channel.send(request) {
+            Ok(()) => self.await_reply(&channel, correlation, &receiver, timeout),
+            Err(err) => Err(err.into()),
+        };
+        if let Ok(mut waiting) = self.waiting.lock() {
+            waiting.remove(&correlation);
+        }
+
+        match result {
+            Ok(reply) => {
+                match &self.failure {
+                    Some(failure) if failure(&reply) => self.record(false),
+                    _ => self.record(true),
+                }
+                Ok(reply)
+            }
+            Err(err) => Err(self.failed(err)),
+        }
+    }
+
+    /// Wait for the reply to a request. Asks sharing the channel read it in turn, handing on
+    /// replies to other asks, and dropping replies nobody is waiting for any more.
+    fn await_reply(
+        &self,
+        channel: &IsolateChannel<IsolateEnvelope<T>>,
+        correlation: IsolateCorrelationId,
+        own: &Receiver<IsolateEnvelope<T>>,
+        timeout: Duration,
+    ) -> Result<IsolateEnvelope<T>, IsolateBreakerError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let mut select = Select::new();
+            let handed = select.recv(own);
+            select.recv(&channel.receiver);
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let operation = select
+                .select_timeout(remaining)
+                .map_err(|_| IsolateBreakerError::Timeout)?;
+            if operation.index() == handed {
+                return operation
+                    .recv(own)
+                    .map_err(|_| IsolateBreakerError::InternalSyncError);
+            }
+            let reply = operation
+                .recv(&channel.receiver)
+                .map_err(|_| IsolateBreakerError::Disconnected)?;
+            if reply.correlation == correlation {
+                return Ok(reply);
+            }
+            let waiting = self
+                .waiting
+                .lock()
+                .map_err(|_| IsolateBreakerError::InternalSyncError)?;
+            if let Some(sender) = waiting.get(&reply.correlation) {
+                let _ = sender.try_send(reply);
+            }
+        }
+    }
+}
+
+impl<T: Send + 'static> Clone for IsolateCircuitBreaker<T> {
+    fn clone(&self) -> IsolateCircuitBreaker<T> {
+        IsolateCircuitBreaker {
+            target: self.target.clone(),
+            threshold: self.threshold,
+            reset_after: self.reset_after,
+            failure: self.failure.clone(),
+            shared: self.shared.clone(),
+            waiting: self.waiting.clone(),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use crate::IsolateChannelError;
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum IsolateBreakerError {
+    /// The breaker is open, and the request was not sent.
+    Open,
+
+    /// No reply arrived in time.
+    Timeout,
+
+    /// The worker's channel has closed.
+    Disconnected,
+
+    /// The worker isn't bound to the runtime.
+    NoMatchingIdentity,
+
+    InternalSyncError,
+
+    /// The request couldn't be sent.
+    ChannelError(IsolateChannelError),
+}
+
+impl Error for IsolateBreakerError {}
+
+impl Display for IsolateBreakerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl From<IsolateChannelError> for IsolateBreakerError {
+    fn from(err: IsolateChannelError) -> Self {
+        IsolateBreakerError::ChannelError(err)
+    }
+}
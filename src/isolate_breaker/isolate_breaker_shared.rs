@@ -0,0 +1,121 @@
+use crate::IsolateBreakerState;
+use crossbeam::Sender;
+use std::time::Duration;
+use std::time::Instant;
+
+/// The state shared by every clone of a circuit breaker
+pub(crate) struct IsolateBreakerShared {
+    state: IsolateBreakerState,
+    failures: u32,
+    opened_at: Instant,
+    trial: bool,
+    subscribers: Vec<Sender<IsolateBreakerState>>,
+}
+
+impl IsolateBreakerShared {
+    pub fn new() -> IsolateBreakerShared {
+        IsolateBreakerShared {
+            state: IsolateBreakerState::Closed,
+            failures: 0,
+            opened_at: Instant::now(),
+            trial: false,
+            subscribers: Vec::new(),
+        }
+    }
+
+    pub fn subscribe(&mut self, subscriber: Sender<IsolateBreakerState>) {
+        self.subscribers.push(subscriber);
+    }
+
+    /// Return the state as of now, half opening the breaker if it has been open long enough
+    pub fn state(&mut self, reset_after: Duration, now: Instant) -> IsolateBreakerState {
+        let elapsed = now.saturating_duration_since(self.opened_at);
+        if self.state == IsolateBreakerState::Open && elapsed >= reset_after {
+            self.trial = false;
+            self.change(IsolateBreakerState::HalfOpen);
+        }
+        self.state
+    }
+
+    /// Return true if a request may be sent; while half open only one trial is let through
+    pub fn permit(&mut self, reset_after: Duration, now: Instant) -> bool {
+        match self.state(reset_after, now) {
+            IsolateBreakerState::Closed => true,
+            IsolateBreakerState::Open => false,
+            IsolateBreakerState::HalfOpen => !std::mem::replace(&mut self.trial, true),
+        }
+    }
+
+    pub fn succeeded(&mut self) {
+        self.failures = 0;
+        self.trial = false;
+        self.change(IsolateBreakerState::Closed);
+    }
+
+    pub fn failed(&mut self, threshold: u32, now: Instant) {
+        self.failures += 1;
+        self.trial = false;
+        if self.state == IsolateBreakerState::HalfOpen || self.failures >= threshold {
+            self.opened_at = now;
+            self.change(IsolateBreakerState::Open);
+        }
+    }
+
+    /// Close the breaker, forgetting any failures
+    pub fn reset(&mut self) {
+        self.succeeded();
+    }
+
+    /// Move to a state, telling the subscribers if it changed
+    fn change(&mut self, state: IsolateBreakerState) {
+        if self.state != state {
+            self.state = state;
+            self.subscribers.retain(|s| s.send(state).is_ok());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IsolateBreakerShared;
+    use crate::IsolateBreakerState;
+    use crossbeam::unbounded;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    #[test]
+    pub fn test_open_half_open_close() {
+        let mut shared = IsolateBreakerShared::new();
+        let (sender, receiver) = unbounded();
+        shared.subscribe(sender);
+        let reset_after = Duration::from_millis(10);
+        let mut now = Instant::now();
+
+        shared.failed(2, now);
+        assert!(shared.permit(reset_after, now));
+        shared.failed(2, now);
+        assert!(!shared.permit(reset_after, now));
+        assert_eq!(receiver.try_recv(), Ok(IsolateBreakerState::Open));
+
+        now += reset_after;
+        assert!(shared.permit(reset_after, now));
+        assert!(!shared.permit(reset_after, now));
+        assert_eq!(receiver.try_recv(), Ok(IsolateBreakerState::HalfOpen));
+        shared.failed(2, now);
+        assert_eq!(shared.state(reset_after, now), IsolateBreakerState::Open);
+
+        now += reset_after;
+        assert!(shared.permit(reset_after, now));
+        shared.succeeded();
+        assert_eq!(shared.state(reset_after, now), IsolateBreakerState::Closed);
+        let changes: Vec<_> = receiver.try_iter().collect();
+        assert_eq!(
+            changes,
+            vec![
+                IsolateBreakerState::Open,
+                IsolateBreakerState::HalfOpen,
+                IsolateBreakerState::Closed
+            ]
+        );
+    }
+}
@@ -0,0 +1,11 @@
+/// The state of a circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IsolateBreakerState {
+    /// Requests are passed on, and failures are counted
+    Closed,
+    /// Requests fail immediately, until the reset timer runs out
+    Open,
+    /// One trial request is passed on; if it succeeds the breaker closes, otherwise it opens
+    /// again
+    HalfOpen,
+}
@@ -0,0 +1,47 @@
+use crate::IsolateBreakerError;
+use crate::IsolateChannel;
+use crate::IsolateIdentity;
+use crate::IsolateRuntimeRef;
+use std::time::Instant;
+
+/// What a circuit breaker sends its requests to
+pub(crate) enum IsolateBreakerTarget<T: Send + 'static> {
+    /// A worker's channel, as returned when it was spawned
+    Channel(IsolateChannel<T>),
+    /// A worker looked up by identity for each request, so a restarted worker is picked up
+    Worker(IsolateRuntimeRef<T>, IsolateIdentity),
+}
+
+impl<T: Send + 'static> IsolateBreakerTarget<T> {
+    /// Return the channel to send a request on
+    pub fn channel(&self) -> Result<IsolateChannel<T>, IsolateBreakerError> {
+        match self {
+            IsolateBreakerTarget::Channel(channel) => Ok(channel.clone()),
+            IsolateBreakerTarget::Worker(runtime, identity) => runtime
+                .find(identity)
+                .ok_or(IsolateBreakerError::NoMatchingIdentity),
+        }
+    }
+
+    /// Return the current time for a worker of a runtime by the runtime's clock, so a breaker
+    /// under the test scheduler resets on virtual time
+    pub fn now(&self) -> Instant {
+        match self {
+            IsolateBreakerTarget::Channel(_) => Instant::now(),
+            IsolateBreakerTarget::Worker(runtime, _) => runtime.clock().now(),
+        }
+    }
+}
+
+impl<T: Send + 'static> Clone for IsolateBreakerTarget<T> {
+    fn clone(&self) -> IsolateBreakerTarget<T> {
+        match self {
+            IsolateBreakerTarget::Channel(channel) => {
+                IsolateBreakerTarget::Channel(channel.clone())
+            }
+            IsolateBreakerTarget::Worker(runtime, identity) => {
+                IsolateBreakerTarget::Worker(runtime.clone(), *identity)
+            }
+        }
+    }
+}
@@ -1,4 +1,25 @@
+pub(crate) mod isolate_channel_error;
+#[cfg(feature = "tracing")]
+pub(crate) mod isolate_channel_trace;
+pub(crate) mod isolate_priority;
+pub(crate) mod isolate_priority_lanes;
+
+#[cfg(feature = "tracing")]
+use crate::isolate_channel::isolate_channel_trace::IsolateChannelTrace;
+use crate::isolate_channel::isolate_priority_lanes::IsolatePriorityLanes;
+use crate::isolate_dead_letters::isolate_dead_letter_route::IsolateDeadLetterRoute;
+use crate::isolate_mailbox::isolate_mailbox_binding::IsolateMailboxSender;
+use crate::isolate_runtime::isolate_worker_state::IsolateWorkerState;
+use crate::isolate_throttle::IsolateAdmission;
+use crate::isolate_throttle::IsolateThrottle;
+use crate::IsolateChannelError;
+use crate::IsolateDeadLetterReason;
+use crate::IsolateEnvelope;
+use crate::IsolateIdentity;
+use crate::IsolatePriority;
+use crate::IsolateRateLimit;
 use crossbeam::{unbounded, Receiver, Sender};
+use std::sync::Arc;
 
 /// IsolateChannel wraps a multi-producer multi-consumer channel that can be safely passed between
 /// threads; it is safe to clone and share this object, but realize it basically acts as a RC on
@@ -6,6 +27,13 @@ use crossbeam::{unbounded, Receiver, Sender};
 pub struct IsolateChannel<T: Send + 'static> {
     pub sender: Sender<T>,
     pub receiver: Receiver<T>,
+    dead_letters: Option<(IsolateIdentity, Arc<IsolateDeadLetterRoute>)>,
+    lanes: Option<IsolatePriorityLanes<T>>,
+    mailbox: Option<IsolateMailboxSender<T>>,
+    throttles: Vec<Arc<IsolateThrottle>>,
+    worker: Option<Arc<IsolateWorkerState>>,
+    #[cfg(feature = "tracing")]
+    trace: Option<IsolateChannelTrace>,
 }
 
 impl<T: Send + 'static> IsolateChannel<T> {
@@ -14,32 +42,283 @@ impl<T: Send + 'static> IsolateChannel<T> {
         let (runner_s, runner_r) = unbounded();
         let (worker_s, worker_r) = unbounded();
         (
-            IsolateChannel {
-                sender: worker_s,
-                receiver: runner_r,
-            },
-            IsolateChannel {
-                sender: runner_s,
-                receiver: worker_r,
-            },
+            IsolateChannel::from(worker_s, runner_r),
+            IsolateChannel::from(runner_s, worker_r),
         )
     }
 
+    /// Create a channel from an existing sender and receiver
+    pub(crate) fn from(sender: Sender<T>, receiver: Receiver<T>) -> IsolateChannel<T> {
+        IsolateChannel {
+            sender,
+            receiver,
+            dead_letters: None,
+            lanes: None,
+            mailbox: None,
+            throttles: Vec::new(),
+            worker: None,
+            #[cfg(feature = "tracing")]
+            trace: None,
+        }
+    }
+
+    /// Route messages that can't be sent on this channel to a dead letter sink, as undelivered
+    /// messages for the worker with the given identity.
+    pub(crate) fn with_dead_letters(
+        mut self,
+        identity: IsolateIdentity,
+        route: Arc<IsolateDeadLetterRoute>,
+    ) -> IsolateChannel<T> {
+        self.dead_letters = Some((identity, route));
+        self
+    }
+
+    /// Give the worker this channel belongs to queues for urgent messages
+    pub(crate) fn with_lanes(mut self, lanes: IsolatePriorityLanes<T>) -> IsolateChannel<T> {
+        self.lanes = Some(lanes);
+        self
+    }
+
+    /// Return the queues for urgent messages, if the worker has them
+    pub(crate) fn lanes(&self) -> Option<&IsolatePriorityLanes<T>> {
+        self.lanes.as_ref()
+    }
+
+    /// Write messages sent on this channel to the durable mailbox of the worker it leads to
+    pub(crate) fn with_mailbox(mut self, mailbox: IsolateMailboxSender<T>) -> IsolateChannel<T> {
+        self.mailbox = Some(mailbox);
+        self
+    }
+
+    /// Count the messages throttled on this channel against the worker it leads to
+    pub(crate) fn with_worker(mut self, worker: Arc<IsolateWorkerState>) -> IsolateChannel<T> {
+        self.worker = Some(worker);
+        self
+    }
+
+    /// Return a copy of this channel that also limits the rate of messages sent through it, and
+    /// its clones, on top of any limits this channel already has
+    pub fn throttled(&self, limit: IsolateRateLimit) -> IsolateChannel<T> {
+        let mut channel = self.clone();
+        let throttle = Arc::new(IsolateThrottle::new(limit));
+        channel.throttles.push(throttle);
+        channel
+    }
+
+    /// Return a channel that wakes a sender waiting for the worker this channel leads to to
+    /// take a message, if the channel leads to a worker
+    pub(crate) fn taken(&self) -> Option<&Receiver<()>> {
+        self.worker.as_ref().map(|worker| worker.taken())
+    }
+
+    /// Return the number of messages waiting to be received, including urgent and durable ones
+    pub(crate) fn queued(&self) -> usize {
+        self.sender.len()
+            + self.lanes.as_ref().map_or(0, |lanes| lanes.len())
+            + self.mailbox.as_ref().map_or(0, |mailbox| mailbox.len())
+    }
+
+    /// Trace messages sent on this channel, and received by the worker it belongs to
+    #[cfg(feature = "tracing")]
+    pub(crate) fn with_trace(mut self, trace: IsolateChannelTrace) -> IsolateChannel<T> {
+        self.trace = Some(trace);
+        self
+    }
+
+    /// Return a span for handling a message just received from this channel
+    #[cfg(feature = "tracing")]
+    pub(crate) fn received_span(&self) -> Option<tracing::Span> {
+        self.trace.as_ref().map(|trace| trace.received())
+    }
+
+    /// Send a message; unlike sending directly on the sender, if the other end has closed the
+    /// message is passed on to the dead letter sink of the registry, if there is one.
+    /// With the tracing feature, the message carries the current trace to the worker, and with
+    /// a durable mailbox the message is written to it first.
+    pub fn send(&self, message: T) -> Result<(), IsolateChannelError> {
+        match self.send_or_return(message)? {
+            Some(message) => self.undeliverable(message),
+            None => Ok(()),
+        }
+    }
+
+    /// Send a message like `send`, but hand it back instead of passing it to the dead letter
+    /// sink if the other end has closed, so it can be sent somewhere else
+    pub(crate) fn send_or_return(&self, message: T) -> Result<Option<T>, IsolateChannelError> {
+        self.offer(message, true)
+    }
+
+    /// Send a message like `send_or_return`, but without waiting on a rate limit that delays
+    /// messages; one that would have to wait is rejected as over the limit. Used where a single
+    /// sender delivers to many workers, such as topics, routers and timers, so one slow worker
+    /// doesn't hold up the rest.
+    pub(crate) fn try_send_or_return(&self, message: T) -> Result<Option<T>, IsolateChannelError> {
+        self.offer(message, false)
+    }
+
+    /// Send a message like `send`, without waiting on a rate limit like `try_send_or_return`
+    pub(crate) fn try_send(&self, message: T) -> Result<(), IsolateChannelError> {
+        match self.try_send_or_return(message)? {
+            Some(message) => self.undeliverable(message),
+            None => Ok(()),
+        }
+    }
+
+    fn offer(&self, message: T, wait: bool) -> Result<Option<T>, IsolateChannelError> {
+        // A message to a worker that has gone is handed back before it is held to a limit
+        if self.has_exited() {
+            return Ok(Some(message));
+        }
+        let message = self.throttle(message, wait)?;
+        #[cfg(feature = "tracing")]
+        {
+            if let Some(trace) = self.trace.as_ref() {
+                let mut returned = None;
+                let result = trace.send(self.sender.len(), || match self.try_deliver(message)? {
+                    Some(message) => {
+                        returned = Some(message);
+                        Err(IsolateChannelError::Disconnected)
+                    }
+                    None => Ok(()),
+                });
+                return match returned {
+                    Some(message) => Ok(Some(message)),
+                    None => result.map(|_| None),
+                };
+            }
+        }
+        self.try_deliver(message)
+    }
+
+    /// Send a message ahead of any waiting messages of lower priority. Channels that don't lead
+    /// to a worker with priority queues, such as those of workers run under the test
+    /// scheduler, send every message as Normal, as do channels to workers that haven't received
+    /// through their context yet, since a worker reading its channel directly never sees the
+    /// priority queues. Urgent messages aren't kept in a durable mailbox, and System messages
+    /// aren't rate limited.
+    pub fn send_priority(
+        &self,
+        message: T,
+        priority: IsolatePriority,
+    ) -> Result<(), IsolateChannelError> {
+        let reads_lanes = match self.worker.as_ref() {
+            Some(worker) => worker.reads_lanes(),
+            None => true,
+        };
+        match self.lanes.as_ref().and_then(|lanes| lanes.sender(priority)) {
+            Some(lane) if reads_lanes && priority == IsolatePriority::System => {
+                self.deliver_on(lane, message)
+            }
+            Some(_) if reads_lanes && self.has_exited() => self.undeliverable(message),
+            Some(lane) if reads_lanes => {
+                let message = self.throttle(message, true)?;
+                self.deliver_on(lane, message)
+            }
+            _ if priority == IsolatePriority::System => self.deliver_on(&self.sender, message),
+            _ => self.send(message),
+        }
+    }
+
+    /// Return true if the worker this channel leads to has exited
+    fn has_exited(&self) -> bool {
+        self.worker
+            .as_ref()
+            .map_or(false, |worker| worker.has_exited())
+    }
+
+    /// Hold a message to the rate limits of this channel, handing it back if it may be sent
+    fn throttle(&self, message: T, wait: bool) -> Result<T, IsolateChannelError> {
+        for throttle in self.throttles.iter() {
+            let admission = throttle.admit(wait)?;
+            if admission != IsolateAdmission::Admitted {
+                if let Some(worker) = self.worker.as_ref() {
+                    worker.recorder().throttled();
+                }
+            }
+            if admission == IsolateAdmission::Rejected {
+                if let Some((identity, route)) = self.dead_letters.as_ref() {
+                    route.post(*identity, IsolateDeadLetterReason::Throttled, message);
+                }
+                return Err(IsolateChannelError::Throttled);
+            }
+        }
+        Ok(message)
+    }
+
+    /// Deliver a message to the mailbox or inbox, handing it back if the other end has closed
+    fn try_deliver(&self, message: T) -> Result<Option<T>, IsolateChannelError> {
+        match self.mailbox.as_ref() {
+            Some(mailbox) => mailbox
+                .send(message)
+                .map_err(IsolateChannelError::MailboxError),
+            None => Ok(self.sender.send(message).err().map(|err| err.into_inner())),
+        }
+    }
+
+    fn deliver_on(&self, sender: &Sender<T>, message: T) -> Result<(), IsolateChannelError> {
+        match sender.send(message) {
+            Ok(_) => Ok(()),
+            Err(err) => self.undeliverable(err.into_inner()),
+        }
+    }
+
+    /// Pass on a message for a worker that has exited to the dead letter sink, if there is one
+    pub(crate) fn undeliverable(&self, message: T) -> Result<(), IsolateChannelError> {
+        match self.dead_letters.as_ref() {
+            Some((identity, route)) => {
+                route.post(*identity, IsolateDeadLetterReason::WorkerExited, message);
+                Err(IsolateChannelError::DeadLetter)
+            }
+            None => Err(IsolateChannelError::Disconnected),
+        }
+    }
+}
+
+impl<T: Send + 'static> IsolateChannel<IsolateEnvelope<T>> {
+    /// Send a plain message to a worker that receives envelopes; it arrives from the worker
+    /// sending it, or with no sender if it is sent from outside any worker
+    pub fn send_message(&self, message: T) -> Result<(), IsolateChannelError> {
+        self.send(IsolateEnvelope::new(message))
+    }
+}
+
+impl<T: Send + 'static> Clone for IsolateChannel<T> {
     /// Clone the references in this instance
-    pub fn clone(&self) -> IsolateChannel<T> {
-        return IsolateChannel {
+    fn clone(&self) -> IsolateChannel<T> {
+        IsolateChannel {
             sender: self.sender.clone(),
             receiver: self.receiver.clone(),
-        };
+            dead_letters: self.dead_letters.clone(),
+            lanes: self.lanes.clone(),
+            mailbox: self.mailbox.clone(),
+            throttles: self.throttles.clone(),
+            worker: self.worker.clone(),
+            #[cfg(feature = "tracing")]
+            trace: self.trace.clone(),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::IsolateChannel;
+    use crate::IsolateChannelError;
 
     #[test]
     pub fn test_new_channel() {
         let _ = IsolateChannel::<String>::new();
     }
+
+    #[test]
+    pub fn test_send_disconnected() {
+        let (a, b) = IsolateChannel::<String>::new();
+        a.send("Hello".to_string()).unwrap();
+        assert_eq!(b.receiver.recv().unwrap(), "Hello");
+
+        drop(b);
+        match a.send("World".to_string()) {
+            Err(IsolateChannelError::Disconnected) => {}
+            _ => unreachable!(),
+        }
+    }
 }
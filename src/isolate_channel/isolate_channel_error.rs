@@ -0,0 +1,31 @@
+use crate::IsolateMailboxError;
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum IsolateChannelError {
+    /// The other end of the channel has closed, and the message was dropped.
+    Disconnected,
+
+    /// The message couldn't be delivered, and was passed to the dead letter sink.
+    DeadLetter,
+
+    /// The message was over a rate limit, and was passed to the dead letter sink if there is
+    /// one.
+    Throttled,
+
+    /// A lock guarding the state of the channel was poisoned, and the message wasn't sent.
+    InternalSyncError,
+
+    /// The message couldn't be written to the durable mailbox, and wasn't sent.
+    MailboxError(IsolateMailboxError),
+}
+
+impl Error for IsolateChannelError {}
+
+impl Display for IsolateChannelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
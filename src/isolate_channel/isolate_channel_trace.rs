@@ -0,0 +1,96 @@
+use crate::IsolateChannelError;
+use crate::IsolateIdentity;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Mutex;
+use tracing::Span;
+
+/// The spans of messages sent to a worker, in the order they were sent; the worker takes the
+/// span of each message it receives as the parent of its own, so a request keeps its trace as
+/// it moves between workers.
+type IsolateTraceLane = Arc<Mutex<VecDeque<Span>>>;
+
+/// Tracing for one end of a worker channel, tagged with the runtime and worker it belongs to
+#[derive(Clone)]
+pub struct IsolateChannelTrace {
+    runtime: String,
+    identity: IsolateIdentity,
+    lane: IsolateTraceLane,
+    inbound: bool,
+}
+
+impl IsolateChannelTrace {
+    /// Create the tracing for the runtime end and the worker end of a worker channel
+    pub fn pair(
+        runtime: &str,
+        identity: IsolateIdentity,
+    ) -> (IsolateChannelTrace, IsolateChannelTrace) {
+        let lane: IsolateTraceLane = Arc::new(Mutex::new(VecDeque::new()));
+        let trace = IsolateChannelTrace {
+            runtime: runtime.to_string(),
+            identity,
+            lane,
+            inbound: false,
+        };
+        let worker = IsolateChannelTrace {
+            inbound: true,
+            ..trace.clone()
+        };
+        (trace, worker)
+    }
+
+    /// Send a message in a span of its own. Sends to the worker leave the span for the worker to
+    /// pick up; `queued` is how many messages were waiting, so spans of messages that were
+    /// received without the worker context can be dropped.
+    pub fn send(
+        &self,
+        queued: usize,
+        send: impl FnOnce() -> Result<(), IsolateChannelError>,
+    ) -> Result<(), IsolateChannelError> {
+        let span = tracing::info_span!(
+            "isolate.send",
+            runtime = %self.runtime,
+            worker = %self.identity,
+            inbound = !self.inbound
+        );
+        if self.inbound {
+            return span.in_scope(send);
+        }
+
+        // Hold the lane while sending so spans stay in the same order as their messages
+        let mut lane = match self.lane.lock() {
+            Ok(lane) => lane,
+            Err(_) => return span.in_scope(send),
+        };
+        while lane.len() > queued {
+            lane.pop_front();
+        }
+        let result = span.in_scope(send);
+        if result.is_ok() {
+            lane.push_back(span);
+        }
+        result
+    }
+
+    /// Return a span for handling a message the worker has just received, following on from
+    /// the span it was sent in if it was sent through the library
+    pub fn received(&self) -> Span {
+        let parent = match self.lane.lock() {
+            Ok(mut lane) => lane.pop_front(),
+            Err(_) => None,
+        };
+        match parent {
+            Some(parent) => tracing::info_span!(
+                parent: &parent,
+                "isolate.receive",
+                runtime = %self.runtime,
+                worker = %self.identity
+            ),
+            None => tracing::info_span!(
+                "isolate.receive",
+                runtime = %self.runtime,
+                worker = %self.identity
+            ),
+        }
+    }
+}
@@ -0,0 +1,9 @@
+/// How urgently a message should be handled; a worker takes every waiting System message
+/// before any High message, and every High message before any Normal one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IsolatePriority {
+    /// Control signals from the library, such as down notifications for monitors and links
+    System,
+    High,
+    Normal,
+}
@@ -0,0 +1,91 @@
+use crate::IsolatePriority;
+use crossbeam::{unbounded, Receiver, Sender, TryRecvError};
+
+/// The queues a worker checks before its normal inbox; the runtime end of a worker channel holds
+/// the senders and the worker end holds the receivers, so sends fail once the worker exits.
+pub enum IsolatePriorityLanes<T> {
+    Senders {
+        system: Sender<T>,
+        high: Sender<T>,
+    },
+    Receivers {
+        system: Receiver<T>,
+        high: Receiver<T>,
+    },
+}
+
+impl<T> IsolatePriorityLanes<T> {
+    /// Create the lanes for the runtime end and the worker end of a worker channel
+    pub fn pair() -> (IsolatePriorityLanes<T>, IsolatePriorityLanes<T>) {
+        let (system_s, system_r) = unbounded();
+        let (high_s, high_r) = unbounded();
+        (
+            IsolatePriorityLanes::Senders {
+                system: system_s,
+                high: high_s,
+            },
+            IsolatePriorityLanes::Receivers {
+                system: system_r,
+                high: high_r,
+            },
+        )
+    }
+
+    /// Return the sender for a priority, if this is the runtime end and it has a lane for it
+    pub fn sender(&self, priority: IsolatePriority) -> Option<&Sender<T>> {
+        match (self, priority) {
+            (IsolatePriorityLanes::Senders { system, .. }, IsolatePriority::System) => Some(system),
+            (IsolatePriorityLanes::Senders { high, .. }, IsolatePriority::High) => Some(high),
+            _ => None,
+        }
+    }
+
+    /// Return the receivers, most urgent first, if this is the worker end
+    pub fn receivers(&self) -> Option<[&Receiver<T>; 2]> {
+        match self {
+            IsolatePriorityLanes::Receivers { system, high } => Some([system, high]),
+            IsolatePriorityLanes::Senders { .. } => None,
+        }
+    }
+
+    /// Take the most urgent waiting message, if this is the worker end
+    pub fn try_recv(&self) -> Option<T> {
+        self.receivers()?
+            .iter()
+            .find_map(|lane| match lane.try_recv() {
+                Ok(message) => Some(message),
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => None,
+            })
+    }
+
+    /// Take every waiting message, most urgent first, if this is the worker end
+    pub fn drain(&self) -> Vec<T> {
+        match self.receivers() {
+            Some(lanes) => lanes.iter().flat_map(|lane| lane.try_iter()).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Return the number of messages waiting in the lanes
+    pub fn len(&self) -> usize {
+        match self {
+            IsolatePriorityLanes::Senders { system, high } => system.len() + high.len(),
+            IsolatePriorityLanes::Receivers { system, high } => system.len() + high.len(),
+        }
+    }
+}
+
+impl<T> Clone for IsolatePriorityLanes<T> {
+    fn clone(&self) -> Self {
+        match self {
+            IsolatePriorityLanes::Senders { system, high } => IsolatePriorityLanes::Senders {
+                system: system.clone(),
+                high: high.clone(),
+            },
+            IsolatePriorityLanes::Receivers { system, high } => IsolatePriorityLanes::Receivers {
+                system: system.clone(),
+                high: high.clone(),
+            },
+        }
+    }
+}
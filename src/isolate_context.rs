@@ -0,0 +1,646 @@
+pub(crate) mod isolate_context_error;
+#[cfg(feature = "tracing")]
+pub(crate) mod isolate_context_trace;
+pub(crate) mod isolate_select;
+
+#[cfg(feature = "tracing")]
+use crate::isolate_context::isolate_context_trace::IsolateContextTrace;
+use crate::isolate_context::isolate_select::IsolateSelect;
+use crate::isolate_context::isolate_select::IsolateSelectArm;
+use crate::isolate_context::isolate_select::IsolateSelected;
+use crate::isolate_mailbox::isolate_mailbox_binding::IsolateMailboxReceipt;
+use crate::isolate_registry::isolate_registry_shared::IsolateRegistryShared;
+use crate::isolate_runtime::isolate_runtime_shared::IsolateRuntimeShared;
+use crate::isolate_runtime::isolate_worker_state::IsolateWorkerState;
+use crate::IsolateCancellationToken;
+use crate::IsolateChannel;
+use crate::IsolateChannelError;
+use crate::IsolateContextError;
+use crate::IsolateDeadLetterReason;
+use crate::IsolateEnvelope;
+use crate::IsolateExitReason;
+use crate::IsolateIdentity;
+use crate::IsolateRegistryRef;
+use crate::IsolateRuntimeRef;
+use crate::IsolateTimerHandle;
+use crate::IsolateTree;
+use crossbeam::Select;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::Weak;
+use std::time::Duration;
+use std::time::Instant;
+
+/// The registry a runtime is bound to, and the name it is bound as
+pub type IsolateContextRegistry = (String, Weak<Mutex<IsolateRegistryShared>>);
+
+/// IsolateContext is handed to each worker when it is spawned; it is everything the worker
+/// knows about itself and the runtime it runs in.
+/// The context only holds weak references to the runtime and registry, so a running worker
+/// doesn't keep them open.
+pub struct IsolateContext<T: Send + 'static> {
+    identity: IsolateIdentity,
+    channel: IsolateChannel<T>,
+    state: Arc<IsolateWorkerState>,
+    runtime: Weak<Mutex<IsolateRuntimeShared<T>>>,
+    registry: Option<IsolateContextRegistry>,
+    tree: IsolateTree,
+    stash: Mutex<VecDeque<(T, Option<u64>)>>,
+    mailbox: Option<Arc<IsolateMailboxReceipt<T>>>,
+    coalescing: Option<Duration>,
+    #[cfg(feature = "tracing")]
+    trace: IsolateContextTrace,
+}
+
+impl<T: Send + 'static> IsolateContext<T> {
+    pub(crate) fn new(
+        identity: IsolateIdentity,
+        channel: IsolateChannel<T>,
+        state: Arc<IsolateWorkerState>,
+        runtime: Weak<Mutex<IsolateRuntimeShared<T>>>,
+        registry: Option<IsolateContextRegistry>,
+        tree: IsolateTree,
+    ) -> IsolateContext<T> {
+        IsolateContext {
+            identity,
+            channel,
+            state,
+            runtime,
+            registry,
+            tree,
+            stash: Mutex::new(VecDeque::new()),
+            mailbox: None,
+            coalescing: None,
+            #[cfg(feature = "tracing")]
+            trace: IsolateContextTrace::new(),
+        }
+    }
+
+    /// Acknowledge messages from the worker's durable mailbox as they are handled
+    pub(crate) fn with_mailbox(
+        mut self,
+        receipt: Arc<IsolateMailboxReceipt<T>>,
+    ) -> IsolateContext<T> {
+        self.mailbox = Some(receipt);
+        self
+    }
+
+    /// Wait out a window for more messages when receiving a batch
+    pub(crate) fn with_coalescing(mut self, window: Duration) -> IsolateContext<T> {
+        self.coalescing = Some(window);
+        self
+    }
+
+    /// Return the identity of this worker
+    pub fn identity(&self) -> IsolateIdentity {
+        self.identity
+    }
+
+    /// Return the channel of this worker
+    pub fn channel(&self) -> &IsolateChannel<T> {
+        &self.channel
+    }
+
+    /// Send a message out of this worker, to whoever holds the other end of its channel
+    pub fn reply(&self, message: T) -> Result<(), IsolateChannelError> {
+        self.channel.send(message)?;
+        self.state.recorder().sent();
+        Ok(())
+    }
+
+    /// Wrap a message in an envelope from this worker
+    pub fn envelope<U>(&self, message: U) -> IsolateEnvelope<U> {
+        IsolateEnvelope::new(message).with_sender(self.identity)
+    }
+
+    /// Wrap a reply to a request in an envelope from this worker, with the correlation id of
+    /// the request
+    pub fn envelope_reply<R, U>(
+        &self,
+        request: &IsolateEnvelope<R>,
+        message: U,
+    ) -> IsolateEnvelope<U> {
+        request.reply(message).with_sender(self.identity)
+    }
+
+    /// Wait for the next message for this worker.
+    /// Unlike receiving from the channel directly, this returns early if the worker is stopped.
+    pub fn recv(&self) -> Result<T, IsolateContextError> {
+        let message = self.select_next(None)?;
+        self.state.received();
+        Ok(message)
+    }
+
+    /// Wait for the next message for this worker, for up to a timeout
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, IsolateContextError> {
+        let message = self.select_next(Some(timeout))?;
+        self.state.received();
+        Ok(message)
+    }
+
+    /// Wait for the next message for this worker, for up to a timeout, then take up to `max`
+    /// messages in all that are already waiting; if the runtime coalesces messages, also wait
+    /// out the rest of its window after the first for more to arrive.
+    /// Messages from a durable mailbox are acknowledged together, on the next receive.
+    pub fn recv_batch(&self, max: usize, timeout: Duration) -> Result<Vec<T>, IsolateContextError> {
+        let mut batch = vec![self.select_next(Some(timeout))?];
+        let deadline = self.coalescing.map(|window| Instant::now() + window);
+        while batch.len() < max {
+            if let Some(message) = self.unstash(|_| true) {
+                batch.push(message);
+                continue;
+            }
+            let wait = deadline
+                .map(|d| d.saturating_duration_since(Instant::now()))
+                .unwrap_or_default();
+            // Stopping or disconnecting ends the batch early; the next receive reports it
+            match self.receive::<()>(Some(wait), &[]) {
+                Ok(IsolateSelected::Message(message)) => batch.push(message),
+                _ => break,
+            }
+        }
+        batch.iter().for_each(|_| self.state.received());
+        Ok(batch)
+    }
+
+    /// Wait for the next message that matches, deferring the others; deferred messages are
+    /// received, in order, before anything new.
+    pub fn recv_matching(&self, matches: impl Fn(&T) -> bool) -> Result<T, IsolateContextError> {
+        self.select_matching(matches, None)
+    }
+
+    /// Wait for the next message that matches, for up to a timeout
+    pub fn recv_matching_timeout(
+        &self,
+        matches: impl Fn(&T) -> bool,
+        timeout: Duration,
+    ) -> Result<T, IsolateContextError> {
+        self.select_matching(matches, Some(Instant::now() + timeout))
+    }
+
+    /// Return the number of messages deferred by selective receives
+    pub fn stashed(&self) -> usize {
+        self.stash.lock().map(|stash| stash.len()).unwrap_or(0)
+    }
+
+    /// Wait on this worker's messages together with other channels
+    pub fn select<R>(&self) -> IsolateSelect<'_, T, R> {
+        IsolateSelect::new(self)
+    }
+
+    /// Return the name of the runtime this worker runs in, if it is bound to a registry
+    pub fn runtime_name(&self) -> Option<&str> {
+        self.registry.as_ref().map(|(name, _)| name.as_str())
+    }
+
+    /// Return the registry of the runtime this worker runs in, if it is bound to one
+    pub fn registry(&self) -> Option<IsolateRegistryRef> {
+        let (_, registry) = self.registry.as_ref()?;
+        registry.upgrade().map(IsolateRegistryRef::new)
+    }
+
+    /// Return the runtime this worker runs in, unless it has been dropped
+    pub fn runtime(&self) -> Result<IsolateRuntimeRef<T>, IsolateContextError> {
+        match self.runtime.upgrade() {
+            Some(shared) => Ok(IsolateRuntimeRef::new(shared)),
+            None => Err(IsolateContextError::RuntimeHalted),
+        }
+    }
+
+    /// Return the tree of workers this worker is part of
+    pub fn tree(&self) -> &IsolateTree {
+        &self.tree
+    }
+
+    /// Spawn a child of this worker in the same runtime; the child is stopped when this worker
+    /// stops or exits
+    pub fn spawn(&self) -> Result<IsolateChannel<T>, IsolateContextError> {
+        self.spawn_with(())
+    }
+
+    /// Spawn a child of this worker in the same runtime, with a set of spawn-time args
+    pub fn spawn_with<A: Send + 'static>(
+        &self,
+        args: A,
+    ) -> Result<IsolateChannel<T>, IsolateContextError> {
+        let runtime = self.runtime()?;
+        Ok(runtime.spawn_child(args, Some(self.identity))?.1)
+    }
+
+    /// Spawn a child of this worker in another runtime of the registry, found by name
+    pub fn spawn_in<U: Send + 'static, A: Send + 'static>(
+        &self,
+        runtime: &str,
+        args: A,
+    ) -> Result<IsolateChannel<U>, IsolateContextError> {
+        let registry = self.registry().ok_or(IsolateContextError::NoRegistry)?;
+        let runtime = registry.find::<U>(runtime)?;
+        Ok(runtime.spawn_child(args, Some(self.identity))?.1)
+    }
+
+    /// Send a message to this worker after a delay
+    pub fn send_after(
+        &self,
+        delay: Duration,
+        message: T,
+    ) -> Result<IsolateTimerHandle, IsolateContextError> {
+        Ok(self.runtime()?.send_after(&self.identity, delay, message)?)
+    }
+
+    /// Send a copy of a message to this worker every period, until the timer is cancelled or
+    /// the worker exits
+    pub fn send_interval(
+        &self,
+        period: Duration,
+        message: T,
+    ) -> Result<IsolateTimerHandle, IsolateContextError>
+    where
+        T: Clone,
+    {
+        Ok(self
+            .runtime()?
+            .send_interval(&self.identity, period, message)?)
+    }
+
+    /// Record how long this worker took to handle a message, for its latency histogram
+    pub(crate) fn observe(&self, duration: Duration) {
+        self.state.recorder().observe(duration);
+    }
+
+    /// Ask this worker and its children to stop; recv returns Stopped from now on
+    pub fn stop(&self) {
+        self.state.stop();
+        self.tree.stop(&self.identity);
+    }
+
+    /// Pass a message this worker won't handle to the dead letter sink of the registry, returning
+    /// false if there is no sink and the message was dropped
+    pub fn reject(&self, message: T) -> bool {
+        self.dead_letter(IsolateDeadLetterReason::Rejected, message)
+    }
+
+    /// Pass a message this worker received to the dead letter sink of the registry for a reason
+    pub(crate) fn dead_letter(&self, reason: IsolateDeadLetterReason, message: T) -> bool {
+        let runtime = match self.runtime.upgrade() {
+            Some(runtime) => runtime,
+            None => return false,
+        };
+        let inner = match runtime.lock() {
+            Ok(inner) => inner,
+            Err(_) => return false,
+        };
+        inner.dead_letter(self.identity, reason, message)
+    }
+
+    /// Report the state this worker is in, for a state machine
+    pub(crate) fn enter_state<S: Clone + Send + 'static>(&self, state: S) {
+        self.state.set_machine_state(Box::new(state));
+    }
+
+    /// Tag this worker, so it can be found by tag in its runtime; a tag replaces any value it had
+    pub fn set_tag(&self, key: &str, value: &str) {
+        self.state.set_tag(key, value);
+    }
+
+    /// Remove a tag from this worker, returning the value it had
+    pub fn remove_tag(&self, key: &str) -> Option<String> {
+        self.state.remove_tag(key)
+    }
+
+    /// Return the value of a tag on this worker
+    pub fn tag(&self, key: &str) -> Option<String> {
+        self.state.tag(key)
+    }
+
+    /// Return true if this worker has been asked to stop
+    pub fn is_stopped(&self) -> bool {
+        self.state.is_stopped()
+    }
+
+    /// Return this worker's cancellation token, to check in long running work or hand to
+    /// threads the worker starts
+    pub fn cancellation(&self) -> IsolateCancellationToken {
+        self.state.cancellation()
+    }
+
+    /// Set the reason this worker reports to monitors and links when it exits.
+    /// A panic is always reported as a panic.
+    pub fn set_exit_reason(&self, reason: IsolateExitReason) {
+        self.state.set_reason(reason);
+    }
+
+    /// Wait for the next message, taking messages deferred by a selective receive first
+    fn select_next(&self, timeout: Option<Duration>) -> Result<T, IsolateContextError> {
+        self.handled();
+        if let Some(message) = self.unstash(|_| true) {
+            return Ok(message);
+        }
+        match self.receive::<()>(timeout, &[])? {
+            IsolateSelected::Message(message) => Ok(message),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Wait on the worker's messages and other channels, for an IsolateSelect
+    pub(crate) fn select_with<R>(
+        &self,
+        timeout: Option<Duration>,
+        arms: &[Box<dyn IsolateSelectArm<R> + '_>],
+    ) -> Result<IsolateSelected<T, R>, IsolateContextError> {
+        self.handled();
+        if let Some(message) = self.unstash(|_| true) {
+            self.state.received();
+            return Ok(IsolateSelected::Message(message));
+        }
+        let selected = self.receive(timeout, arms)?;
+        if let IsolateSelected::Message(_) = selected {
+            self.state.received();
+        }
+        Ok(selected)
+    }
+
+    fn select_matching(
+        &self,
+        matches: impl Fn(&T) -> bool,
+        deadline: Option<Instant>,
+    ) -> Result<T, IsolateContextError> {
+        self.handled();
+        if let Some(message) = self.unstash(&matches) {
+            self.state.received();
+            return Ok(message);
+        }
+        loop {
+            let timeout = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+            if let IsolateSelected::Message(message) = self.receive::<()>(timeout, &[])? {
+                if matches(&message) {
+                    self.state.received();
+                    return Ok(message);
+                }
+                let sequence = self.mailbox.as_ref().and_then(|receipt| receipt.take());
+                if let Ok(mut stash) = self.stash.lock() {
+                    stash.push_back((message, sequence));
+                }
+            }
+        }
+    }
+
+    /// Take the first deferred message that matches
+    fn unstash(&self, matches: impl Fn(&T) -> bool) -> Option<T> {
+        let mut stash = self.stash.lock().ok()?;
+        let index = stash.iter().position(|(message, _)| matches(message))?;
+        let (message, sequence) = stash.remove(index)?;
+        if let Some(receipt) = self.mailbox.as_ref() {
+            receipt.handling(sequence);
+        }
+        Some(message)
+    }
+
+    /// Acknowledge the message the worker was handling, now it has asked for another
+    fn handled(&self) {
+        if let Some(receipt) = self.mailbox.as_ref() {
+            receipt.complete();
+        }
+    }
+
+    /// Wait for the next message from the urgent queues, the inbox, the durable mailbox or other
+    /// channels, or for the worker to be stopped
+    fn receive<R>(
+        &self,
+        timeout: Option<Duration>,
+        arms: &[Box<dyn IsolateSelectArm<R> + '_>],
+    ) -> Result<IsolateSelected<T, R>, IsolateContextError> {
+        #[cfg(feature = "tracing")]
+        self.trace.exit();
+        if self.is_stopped() {
+            return Err(IsolateContextError::Stopped);
+        }
+        self.state.receiving();
+        let lanes = self.channel.lanes();
+        if let Some(message) = lanes.and_then(|lanes| lanes.try_recv()) {
+            return Ok(IsolateSelected::Message(message));
+        }
+        let mut select = Select::new();
+        let inbox = select.recv(&self.channel.receiver);
+        let stopped = select.recv(self.state.stopped());
+        let urgent: Vec<_> = match lanes.and_then(|lanes| lanes.receivers()) {
+            Some(receivers) => receivers
+                .iter()
+                .map(|lane| (select.recv(lane), *lane))
+                .collect(),
+            None => Vec::new(),
+        };
+        let durable = self
+            .mailbox
+            .as_ref()
+            .map(|receipt| (select.recv(receipt.lane()), receipt));
+        let others: Vec<_> = arms.iter().map(|arm| arm.register(&mut select)).collect();
+        let operation = match timeout {
+            Some(timeout) => match select.select_timeout(timeout) {
+                Ok(operation) => operation,
+                Err(_) => return Err(IsolateContextError::Timeout),
+            },
+            None => select.select(),
+        };
+
+        let index = operation.index();
+        if index == inbox {
+            // Urgent and durable messages sent before the inbox closed are still handled
+            return match operation.recv(&self.channel.receiver) {
+                Ok(message) => Ok(IsolateSelected::Message(self.inbox_message(message))),
+                Err(_) => self
+                    .closed()
+                    .map(IsolateSelected::Message)
+                    .ok_or(IsolateContextError::Disconnected),
+            };
+        }
+        if index == stopped {
+            let _ = operation.recv(self.state.stopped());
+            return Err(IsolateContextError::Stopped);
+        }
+        if let Some((_, lane)) = urgent.iter().find(|(i, _)| *i == index) {
+            // The urgent queues close with the inbox, which may still have messages waiting
+            return match operation.recv(lane) {
+                Ok(message) => Ok(IsolateSelected::Message(message)),
+                Err(_) => self
+                    .closed()
+                    .map(IsolateSelected::Message)
+                    .ok_or(IsolateContextError::Disconnected),
+            };
+        }
+        if let Some((_, receipt)) = durable.filter(|(i, _)| *i == index) {
+            return match operation.recv(receipt.lane()) {
+                Ok((message, sequence)) => {
+                    let message = receipt.arrived(message, sequence);
+                    Ok(IsolateSelected::Message(self.inbox_message(message)))
+                }
+                Err(_) => self
+                    .closed()
+                    .map(IsolateSelected::Message)
+                    .ok_or(IsolateContextError::Disconnected),
+            };
+        }
+        match others.iter().position(|i| *i == index) {
+            Some(arm) => Ok(match arms[arm].complete(operation) {
+                Some(result) => IsolateSelected::Channel(result),
+                None => IsolateSelected::Closed(arm),
+            }),
+            None => unreachable!(),
+        }
+    }
+
+    /// Take a message still waiting anywhere once one of the worker's queues has closed
+    fn closed(&self) -> Option<T> {
+        if let Some(message) = self.channel.lanes().and_then(|lanes| lanes.try_recv()) {
+            return Some(message);
+        }
+        if let Ok(message) = self.channel.receiver.try_recv() {
+            return Some(self.inbox_message(message));
+        }
+        let message = self.mailbox.as_ref()?.try_recv()?;
+        Some(self.inbox_message(message))
+    }
+
+    /// Pick up the trace of a message taken from the inbox or the durable mailbox
+    fn inbox_message(&self, message: T) -> T {
+        #[cfg(feature = "tracing")]
+        {
+            if let Some(span) = self.channel.received_span() {
+                self.trace.enter(span);
+            }
+        }
+        message
+    }
+}
+
+impl<T: Send + 'static> IsolateContext<IsolateEnvelope<T>> {
+    /// Answer a request. The reply goes to the worker that sent the request if it is in the
+    /// same runtime as this one, and out of this worker's channel otherwise.
+    pub fn respond(
+        &self,
+        request: &IsolateEnvelope<T>,
+        message: T,
+    ) -> Result<(), IsolateContextError> {
+        let reply = self.envelope_reply(request, message);
+        let sender = request
+            .sender
+            .and_then(|sender| self.runtime().ok()?.find(&sender));
+        match sender {
+            Some(channel) => channel.send(reply)?,
+            None => self.reply(reply)?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Isolate;
+    use crate::IsolateContext;
+    use crate::IsolateContextError;
+    use crate::IsolateDown;
+    use crate::IsolateExitReason;
+    use crate::IsolateRegistry;
+    use crate::IsolateRuntimeWait;
+
+    #[derive(Debug)]
+    enum TestEvent {
+        Name,
+        Fail,
+        Spawn,
+        Output(String),
+        Down(IsolateDown),
+    }
+
+    impl From<IsolateDown> for TestEvent {
+        fn from(down: IsolateDown) -> Self {
+            TestEvent::Down(down)
+        }
+    }
+
+    struct TestIsolate {}
+
+    impl Isolate<TestEvent> for TestIsolate {
+        type Args = ();
+
+        fn spawn(&self, context: IsolateContext<TestEvent>, _: ()) -> Box<dyn FnMut() + Send> {
+            Box::new(move || loop {
+                match context.recv() {
+                    Ok(TestEvent::Name) => {
+                        let name = context.runtime_name().unwrap().to_string();
+                        context.reply(TestEvent::Output(name)).unwrap();
+                    }
+                    Ok(TestEvent::Fail) => {
+                        context.set_exit_reason(IsolateExitReason::Failed("Failed".to_string()));
+                        break;
+                    }
+                    Ok(TestEvent::Spawn) => {
+                        let channel = context.spawn_in::<TestEvent, ()>("Test", ()).unwrap();
+                        channel.sender.send(TestEvent::Name).unwrap();
+                        let output = channel.receiver.recv().unwrap();
+                        context.reply(output).unwrap();
+                    }
+                    Ok(event) => context.reply(event).unwrap(),
+                    Err(IsolateContextError::Stopped) => {
+                        context
+                            .reply(TestEvent::Output("Stopped".to_string()))
+                            .unwrap();
+                        break;
+                    }
+                    Err(_) => break,
+                }
+            })
+        }
+    }
+
+    fn output(event: TestEvent) -> String {
+        match event {
+            TestEvent::Output(output) => output,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    pub fn test_runtime_name_and_spawn() {
+        let mut registry = IsolateRegistry::new();
+        let mut runtime = registry.bind("Test", TestIsolate {}).unwrap();
+
+        let channel = runtime.spawn().unwrap();
+        channel.sender.send(TestEvent::Name).unwrap();
+        assert_eq!(output(channel.receiver.recv().unwrap()), "Test");
+        channel.sender.send(TestEvent::Spawn).unwrap();
+        assert_eq!(output(channel.receiver.recv().unwrap()), "Test");
+
+        drop(channel);
+        registry.wait();
+    }
+
+    #[test]
+    pub fn test_stop_and_exit_reason() {
+        let mut registry = IsolateRegistry::new();
+        let runtime = registry.bind("Test", TestIsolate {}).unwrap();
+
+        let (watcher, watcher_channel) = runtime.spawn_worker(()).unwrap();
+        let (worker, worker_channel) = runtime.spawn_worker(()).unwrap();
+        runtime.monitor(&watcher, &runtime, &worker).unwrap();
+
+        // A worker waiting on its context wakes up when it is stopped
+        runtime.stop(&watcher).unwrap();
+        assert_eq!(output(watcher_channel.receiver.recv().unwrap()), "Stopped");
+
+        let (watcher, watcher_channel) = runtime.spawn_worker(()).unwrap();
+        runtime.monitor(&watcher, &runtime, &worker).unwrap();
+        worker_channel.sender.send(TestEvent::Fail).unwrap();
+        match watcher_channel.receiver.recv().unwrap() {
+            TestEvent::Down(down) => {
+                assert_eq!(down.identity, worker);
+                assert_eq!(down.reason, IsolateExitReason::Failed("Failed".to_string()));
+            }
+            _ => unreachable!(),
+        }
+
+        drop(watcher_channel);
+        drop(worker_channel);
+        runtime.wait();
+    }
+}
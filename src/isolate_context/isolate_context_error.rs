@@ -0,0 +1,44 @@
+use crate::IsolateChannelError;
+use crate::IsolateRegistryError;
+use crate::IsolateRuntimeError;
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum IsolateContextError {
+    Disconnected,
+    Stopped,
+    Timeout,
+    RuntimeHalted,
+    NoRegistry,
+    RegistryError(IsolateRegistryError),
+    RuntimeError(IsolateRuntimeError),
+    ChannelError(IsolateChannelError),
+}
+
+impl Error for IsolateContextError {}
+
+impl Display for IsolateContextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl From<IsolateRegistryError> for IsolateContextError {
+    fn from(err: IsolateRegistryError) -> Self {
+        IsolateContextError::RegistryError(err)
+    }
+}
+
+impl From<IsolateChannelError> for IsolateContextError {
+    fn from(err: IsolateChannelError) -> Self {
+        IsolateContextError::ChannelError(err)
+    }
+}
+
+impl From<IsolateRuntimeError> for IsolateContextError {
+    fn from(err: IsolateRuntimeError) -> Self {
+        IsolateContextError::RuntimeError(err)
+    }
+}
@@ -0,0 +1,42 @@
+use std::sync::Mutex;
+use tracing::Span;
+
+/// The span of the message a worker is handling; it stays entered on the worker thread until
+/// the worker waits for its next message, so anything the worker sends is part of it.
+pub struct IsolateContextTrace {
+    current: Mutex<Option<Span>>,
+}
+
+impl IsolateContextTrace {
+    pub fn new() -> IsolateContextTrace {
+        IsolateContextTrace {
+            current: Mutex::new(None),
+        }
+    }
+
+    /// Enter the span of a new message
+    pub fn enter(&self, span: Span) {
+        self.exit();
+        span.with_subscriber(|(id, dispatch)| dispatch.enter(id));
+        if let Ok(mut current) = self.current.lock() {
+            *current = Some(span);
+        }
+    }
+
+    /// Leave the span of the message being handled, if there is one
+    pub fn exit(&self) {
+        let span = match self.current.lock() {
+            Ok(mut current) => current.take(),
+            Err(_) => None,
+        };
+        if let Some(span) = span {
+            span.with_subscriber(|(id, dispatch)| dispatch.exit(id));
+        }
+    }
+}
+
+impl Drop for IsolateContextTrace {
+    fn drop(&mut self) {
+        self.exit();
+    }
+}
@@ -0,0 +1,80 @@
+use crate::IsolateContext;
+use crate::IsolateContextError;
+use crossbeam::{Receiver, Select, SelectedOperation};
+use std::time::Duration;
+
+/// What a worker woke up for when waiting on its context and other channels together
+#[derive(Debug)]
+pub enum IsolateSelected<T, R> {
+    /// A message for the worker itself
+    Message(T),
+    /// A message from one of the other channels
+    Channel(R),
+    /// One of the other channels closed; this is its index, in the order they were added
+    Closed(usize),
+}
+
+/// One of the other channels a worker waits on, with how to turn its messages into a result
+pub trait IsolateSelectArm<R> {
+    /// Add the channel to a select, returning its index
+    fn register<'a>(&'a self, select: &mut Select<'a>) -> usize;
+
+    /// Take the message the select found, or None if the channel has closed
+    fn complete(&self, operation: SelectedOperation<'_>) -> Option<R>;
+}
+
+struct IsolateSelectReceiver<'a, U, R> {
+    receiver: &'a Receiver<U>,
+    map: Box<dyn Fn(U) -> R + 'a>,
+}
+
+impl<'a, U, R> IsolateSelectArm<R> for IsolateSelectReceiver<'a, U, R> {
+    fn register<'b>(&'b self, select: &mut Select<'b>) -> usize {
+        select.recv(self.receiver)
+    }
+
+    fn complete(&self, operation: SelectedOperation<'_>) -> Option<R> {
+        operation.recv(self.receiver).ok().map(&self.map)
+    }
+}
+
+/// IsolateSelect waits on a worker's own messages and stop signal together with other channels
+/// the worker holds, such as the channel of another worker it talks to.
+pub struct IsolateSelect<'a, T: Send + 'static, R> {
+    context: &'a IsolateContext<T>,
+    arms: Vec<Box<dyn IsolateSelectArm<R> + 'a>>,
+    timeout: Option<Duration>,
+}
+
+impl<'a, T: Send + 'static, R> IsolateSelect<'a, T, R> {
+    pub(crate) fn new(context: &'a IsolateContext<T>) -> IsolateSelect<'a, T, R> {
+        IsolateSelect {
+            context,
+            arms: Vec::new(),
+            timeout: None,
+        }
+    }
+
+    /// Also wait on another channel, turning its messages into results with map
+    pub fn recv<U>(mut self, receiver: &'a Receiver<U>, map: impl Fn(U) -> R + 'a) -> Self
+    where
+        R: 'a,
+    {
+        self.arms.push(Box::new(IsolateSelectReceiver {
+            receiver,
+            map: Box::new(map),
+        }));
+        self
+    }
+
+    /// Give up waiting after a timeout
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Wait for the first message from any of the channels; a stopped worker gets Stopped
+    pub fn wait(self) -> Result<IsolateSelected<T, R>, IsolateContextError> {
+        self.context.select_with(self.timeout, &self.arms)
+    }
+}
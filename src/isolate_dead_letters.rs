@@ -0,0 +1,103 @@
+pub(crate) mod isolate_dead_letter;
+pub(crate) mod isolate_dead_letter_route;
+
+use crate::IsolateDeadLetter;
+use crossbeam::{unbounded, Receiver, Sender};
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// IsolateDeadLetters is the sink for messages that couldn't be delivered to the workers of the
+/// runtimes in a registry. Every subscriber gets a reference to each dead letter.
+pub struct IsolateDeadLetters {
+    subscribers: Arc<Mutex<Vec<Sender<Arc<IsolateDeadLetter>>>>>,
+}
+
+impl IsolateDeadLetters {
+    pub fn new() -> IsolateDeadLetters {
+        IsolateDeadLetters {
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Subscribe to dead letters; the subscription ends when the receiver is dropped.
+    pub fn subscribe(&self) -> Receiver<Arc<IsolateDeadLetter>> {
+        let (sender, receiver) = unbounded();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(sender);
+        }
+        receiver
+    }
+
+    /// Post a dead letter to every subscriber.
+    /// If there are no subscribers the letter is dropped.
+    pub fn post(&self, letter: IsolateDeadLetter) {
+        let letter = Arc::new(letter);
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain(|s| s.send(letter.clone()).is_ok());
+        }
+    }
+}
+
+impl Clone for IsolateDeadLetters {
+    fn clone(&self) -> IsolateDeadLetters {
+        IsolateDeadLetters {
+            subscribers: self.subscribers.clone(),
+        }
+    }
+}
+
+impl Default for IsolateDeadLetters {
+    fn default() -> Self {
+        IsolateDeadLetters::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IsolateDeadLetters;
+    use crate::IsolateDeadLetter;
+    use crate::IsolateDeadLetterReason;
+    use crate::IsolateIdentity;
+
+    #[test]
+    pub fn test_post_to_subscribers() {
+        let sink = IsolateDeadLetters::new();
+        let s1 = sink.subscribe();
+        let s2 = sink.subscribe();
+
+        let identity = IsolateIdentity::new();
+        sink.post(IsolateDeadLetter::new(
+            identity,
+            "Test",
+            IsolateDeadLetterReason::WorkerExited,
+            "Hello".to_string(),
+        ));
+
+        let l1 = s1.recv().unwrap();
+        let l2 = s2.recv().unwrap();
+        assert_eq!(l1.identity, identity);
+        assert_eq!(l1.runtime, "Test");
+        assert_eq!(l1.reason, IsolateDeadLetterReason::WorkerExited);
+
+        // Only one subscriber gets to take the message
+        assert!(l1.take::<u32>().is_none());
+        assert!(l1.is::<String>());
+        assert_eq!(l1.take::<String>().unwrap(), "Hello");
+        assert!(l2.take::<String>().is_none());
+    }
+
+    #[test]
+    pub fn test_dropped_subscriber() {
+        let sink = IsolateDeadLetters::new();
+        {
+            let _ = sink.subscribe();
+        }
+        sink.post(IsolateDeadLetter::new(
+            IsolateIdentity::new(),
+            "Test",
+            IsolateDeadLetterReason::Unprocessed,
+            1,
+        ));
+        assert!(sink.subscribers.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,72 @@
+use crate::IsolateIdentity;
+use std::any::Any;
+use std::sync::Mutex;
+
+/// The reason a message could not be delivered
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IsolateDeadLetterReason {
+    /// The message was sent to a worker that has already exited.
+    WorkerExited,
+
+    /// The message was sent to an identity that isn't bound to the runtime.
+    NoMatchingIdentity,
+
+    /// The message was still waiting in the queue of a worker when it exited.
+    Unprocessed,
+
+    /// The worker wouldn't handle the message in the state it was in.
+    Rejected,
+
+    /// The message was over a rate limit on the way to the worker.
+    Throttled,
+}
+
+/// IsolateDeadLetter is a message that couldn't be delivered to a worker.
+/// The message itself can be taken by exactly one subscriber, eg. to redeliver it; the rest of
+/// the details are available to every subscriber.
+#[derive(Debug)]
+pub struct IsolateDeadLetter {
+    pub identity: IsolateIdentity,
+    pub runtime: String,
+    pub reason: IsolateDeadLetterReason,
+    message: Mutex<Option<Box<dyn Any + Send + 'static>>>,
+}
+
+impl IsolateDeadLetter {
+    pub fn new<T: Send + 'static>(
+        identity: IsolateIdentity,
+        runtime: &str,
+        reason: IsolateDeadLetterReason,
+        message: T,
+    ) -> IsolateDeadLetter {
+        IsolateDeadLetter {
+            identity,
+            runtime: runtime.to_string(),
+            reason,
+            message: Mutex::new(Some(Box::new(message))),
+        }
+    }
+
+    /// Check if the message is still available, and is of type T
+    pub fn is<T: Send + 'static>(&self) -> bool {
+        match self.message.lock() {
+            Ok(message) => match message.as_ref() {
+                Some(m) => m.is::<T>(),
+                None => false,
+            },
+            Err(_) => false,
+        }
+    }
+
+    /// Take the message, if it is of type T and hasn't already been taken
+    pub fn take<T: Send + 'static>(&self) -> Option<T> {
+        let mut message = self.message.lock().ok()?;
+        match message.take()?.downcast::<T>() {
+            Ok(m) => Some(*m),
+            Err(m) => {
+                *message = Some(m);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use crate::IsolateDeadLetter;
+use crate::IsolateDeadLetterReason;
+use crate::IsolateDeadLetters;
+use crate::IsolateIdentity;
+use std::sync::Arc;
+
+/// IsolateDeadLetterRoute is the dead letter sink for a single named runtime
+pub struct IsolateDeadLetterRoute {
+    runtime: String,
+    sink: IsolateDeadLetters,
+}
+
+impl IsolateDeadLetterRoute {
+    pub fn new(runtime: &str, sink: IsolateDeadLetters) -> Arc<IsolateDeadLetterRoute> {
+        Arc::new(IsolateDeadLetterRoute {
+            runtime: runtime.to_string(),
+            sink,
+        })
+    }
+
+    /// Post an undelivered message for a worker in this runtime
+    pub fn post<T: Send + 'static>(
+        &self,
+        identity: IsolateIdentity,
+        reason: IsolateDeadLetterReason,
+        message: T,
+    ) {
+        self.sink.post(IsolateDeadLetter::new(
+            identity,
+            &self.runtime,
+            reason,
+            message,
+        ));
+    }
+}
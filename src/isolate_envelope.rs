@@ -0,0 +1,119 @@
+pub(crate) mod isolate_correlation_id;
+
+use crate::isolate_runtime::isolate_current_worker;
+use crate::IsolateCorrelationId;
+use crate::IsolateIdentity;
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// IsolateEnvelope wraps a message with who sent it, the request it belongs to, when it was
+/// sent and any headers; run a runtime over `IsolateEnvelope<T>` to receive them.
+/// Plain messages can still be sent, wrapped with `IsolateEnvelope::from`; they arrive from the
+/// worker that wrapped them, or with no sender if they were wrapped outside any worker.
+#[derive(Debug, Clone)]
+pub struct IsolateEnvelope<T> {
+    pub message: T,
+    /// The worker that sent the message, if it was sent by a worker
+    pub sender: Option<IsolateIdentity>,
+    /// Shared by a request and every reply to it
+    pub correlation: IsolateCorrelationId,
+    pub sent_at: SystemTime,
+    pub headers: HashMap<String, String>,
+}
+
+impl<T> IsolateEnvelope<T> {
+    /// Wrap a message as a new request, from the worker running on this thread if there is one
+    pub fn new(message: T) -> IsolateEnvelope<T> {
+        IsolateEnvelope {
+            message,
+            sender: isolate_current_worker::current(),
+            correlation: IsolateCorrelationId::new(),
+            sent_at: SystemTime::now(),
+            headers: HashMap::new(),
+        }
+    }
+
+    /// Set the worker the message is from
+    pub fn with_sender(mut self, sender: IsolateIdentity) -> IsolateEnvelope<T> {
+        self.sender = Some(sender);
+        self
+    }
+
+    /// Set the request the message belongs to
+    pub fn with_correlation(mut self, correlation: IsolateCorrelationId) -> IsolateEnvelope<T> {
+        self.correlation = correlation;
+        self
+    }
+
+    /// Add a header
+    pub fn with_header(mut self, key: &str, value: &str) -> IsolateEnvelope<T> {
+        self.headers.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Return the value of a header
+    pub fn header(&self, key: &str) -> Option<&str> {
+        self.headers.get(key).map(|value| value.as_str())
+    }
+
+    /// Wrap a reply to this message; it keeps the correlation id, and is from the worker running
+    /// on this thread, but has no headers until they are set.
+    pub fn reply<U>(&self, message: U) -> IsolateEnvelope<U> {
+        IsolateEnvelope::new(message).with_correlation(self.correlation)
+    }
+
+    /// Change the message, keeping the rest of the envelope
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> IsolateEnvelope<U> {
+        IsolateEnvelope {
+            message: f(self.message),
+            sender: self.sender,
+            correlation: self.correlation,
+            sent_at: self.sent_at,
+            headers: self.headers,
+        }
+    }
+
+    /// Unwrap the message
+    pub fn into_message(self) -> T {
+        self.message
+    }
+}
+
+impl<T> From<T> for IsolateEnvelope<T> {
+    fn from(message: T) -> Self {
+        IsolateEnvelope::new(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::isolate_runtime::isolate_current_worker;
+    use crate::IsolateEnvelope;
+    use crate::IsolateIdentity;
+    use std::thread;
+
+    #[test]
+    pub fn test_reply_keeps_correlation() {
+        let sender = IsolateIdentity::new();
+        let request = IsolateEnvelope::new("ping")
+            .with_sender(sender)
+            .with_header("user", "42");
+        assert_eq!(request.header("user"), Some("42"));
+
+        let reply = request.reply(1);
+        assert_eq!(reply.correlation, request.correlation);
+        assert_eq!(reply.sender, None);
+        assert_eq!(reply.header("user"), None);
+        assert_eq!(reply.map(|n| n + 1).into_message(), 2);
+    }
+
+    #[test]
+    pub fn test_stamp_current_worker() {
+        let worker = IsolateIdentity::new();
+        let stamped = thread::spawn(move || {
+            isolate_current_worker::enter(worker);
+            IsolateEnvelope::from("ping").sender
+        });
+        assert_eq!(stamped.join().unwrap(), Some(worker));
+    }
+}
@@ -0,0 +1,27 @@
+use std::fmt;
+use std::fmt::Display;
+use uuid::Uuid;
+
+/// IsolateCorrelationId ties a reply to the request it answers
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct IsolateCorrelationId {
+    id: Uuid,
+}
+
+impl IsolateCorrelationId {
+    pub fn new() -> IsolateCorrelationId {
+        IsolateCorrelationId { id: Uuid::new_v4() }
+    }
+}
+
+impl Default for IsolateCorrelationId {
+    fn default() -> Self {
+        IsolateCorrelationId::new()
+    }
+}
+
+impl Display for IsolateCorrelationId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
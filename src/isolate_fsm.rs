@@ -0,0 +1,163 @@
+pub(crate) mod isolate_fsm_state;
+pub(crate) mod isolate_transition;
+pub(crate) mod isolate_unhandled;
+
+use crate::Isolate;
+use crate::IsolateContext;
+use crate::IsolateContextError;
+use crate::IsolateDeadLetterReason;
+use crate::IsolateExitReason;
+use crate::IsolateFsmState;
+use crate::IsolateTransition;
+use crate::IsolateUnhandled;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::mem;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// IsolateFsm implements Isolate for a state machine declared as a set of states, each with its
+/// own message handler, and the transitions allowed between them. Each worker starts in the
+/// state and with the data built from its spawn-time args, and reports the state it is in to
+/// `IsolateRuntimeRef::current_state`. A worker that moves to a state that wasn't declared, or
+/// along a transition that wasn't declared when any were, fails. Messages deferred when the
+/// worker exits are passed to the dead letter sink of the registry as unprocessed.
+pub struct IsolateFsm<A, S, D, T: Send + 'static> {
+    factory: Arc<dyn Fn(A) -> (S, D) + Send + Sync + 'static>,
+    states: HashMap<S, Arc<IsolateFsmState<S, D, T>>>,
+    transitions: HashSet<(S, S)>,
+}
+
+impl<A, S, D, T> IsolateFsm<A, S, D, T>
+where
+    A: Send + 'static,
+    S: Clone + Eq + Hash + Debug + Send + Sync + 'static,
+    T: Send + 'static,
+{
+    /// Create a state machine that builds the first state and data of each worker from its
+    /// spawn-time args
+    pub fn new(factory: impl Fn(A) -> (S, D) + Send + Sync + 'static) -> IsolateFsm<A, S, D, T> {
+        IsolateFsm {
+            factory: Arc::new(factory),
+            states: HashMap::new(),
+            transitions: HashSet::new(),
+        }
+    }
+
+    /// Declare a state and how the machine behaves in it
+    pub fn state(
+        mut self,
+        state: S,
+        behaviour: IsolateFsmState<S, D, T>,
+    ) -> IsolateFsm<A, S, D, T> {
+        self.states.insert(state, Arc::new(behaviour));
+        self
+    }
+
+    /// Allow the machine to move from one state to another; if no transitions are declared,
+    /// any move between declared states is allowed
+    pub fn transition(mut self, from: S, to: S) -> IsolateFsm<A, S, D, T> {
+        self.transitions.insert((from, to));
+        self
+    }
+}
+
+impl<A, S, D, T> Isolate<T> for IsolateFsm<A, S, D, T>
+where
+    A: Send + 'static,
+    S: Clone + Eq + Hash + Debug + Send + Sync + 'static,
+    D: Send + 'static,
+    T: Send + 'static,
+{
+    type Args = A;
+
+    fn spawn(&self, context: IsolateContext<T>, args: A) -> Box<dyn FnMut() + Send + 'static> {
+        let mut machine = Some((self.factory)(args));
+        let states = self.states.clone();
+        let transitions = self.transitions.clone();
+        Box::new(move || {
+            let (mut current, mut data) = match machine.take() {
+                Some(machine) => machine,
+                None => return,
+            };
+            let mut state = match states.get(&current) {
+                Some(state) => state.clone(),
+                None => {
+                    let reason = format!("Undeclared state {:?}", current);
+                    context.set_exit_reason(IsolateExitReason::Failed(reason));
+                    return;
+                }
+            };
+            context.enter_state(current.clone());
+            let mut deadline = state.timeout_after().map(|after| Instant::now() + after);
+            let mut deferred = VecDeque::new();
+            let mut retry = VecDeque::new();
+
+            loop {
+                let message = match retry.pop_front() {
+                    Some(message) => Ok(message),
+                    None => match deadline {
+                        Some(deadline) => {
+                            context.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                        }
+                        None => context.recv(),
+                    },
+                };
+                let transition = match message {
+                    Ok(message) => {
+                        let started = Instant::now();
+                        let transition = state.handle(&context, &mut data, message);
+                        context.observe(started.elapsed());
+                        transition
+                    }
+                    Err(IsolateContextError::Timeout) => {
+                        deadline = None;
+                        state.handle_timeout(&context, &mut data)
+                    }
+                    Err(_) => break,
+                };
+
+                match transition {
+                    IsolateTransition::Stay => {}
+                    IsolateTransition::Goto(next) => {
+                        let allowed = transitions.is_empty()
+                            || transitions.contains(&(current.clone(), next.clone()));
+                        let next_state = match states.get(&next) {
+                            Some(next_state) if allowed => next_state.clone(),
+                            _ => {
+                                let reason =
+                                    format!("Invalid transition {:?} to {:?}", current, next);
+                                context.set_exit_reason(IsolateExitReason::Failed(reason));
+                                break;
+                            }
+                        };
+                        state = next_state;
+                        current = next;
+                        context.enter_state(current.clone());
+                        deadline = state.timeout_after().map(|after| Instant::now() + after);
+
+                        // Deferred messages get another chance, ahead of any still to retry
+                        deferred.append(&mut retry);
+                        mem::swap(&mut deferred, &mut retry);
+                    }
+                    IsolateTransition::Unhandled(message) => match state.unhandled_policy() {
+                        IsolateUnhandled::Defer => deferred.push_back(message),
+                        IsolateUnhandled::Reject => {
+                            context.reject(message);
+                        }
+                    },
+                    IsolateTransition::Halt => break,
+                }
+            }
+
+            // Messages still put aside when the machine stops are left unprocessed, like those
+            // waiting in the worker's queue
+            for message in deferred.into_iter().chain(retry) {
+                context.dead_letter(IsolateDeadLetterReason::Unprocessed, message);
+            }
+        })
+    }
+}
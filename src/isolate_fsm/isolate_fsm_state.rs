@@ -0,0 +1,80 @@
+use crate::IsolateContext;
+use crate::IsolateTransition;
+use crate::IsolateUnhandled;
+use std::time::Duration;
+
+type IsolateFsmHandler<S, D, T> =
+    Box<dyn Fn(&IsolateContext<T>, &mut D, T) -> IsolateTransition<S, T> + Send + Sync + 'static>;
+
+type IsolateFsmTimeout<S, D, T> =
+    Box<dyn Fn(&IsolateContext<T>, &mut D) -> IsolateTransition<S, T> + Send + Sync + 'static>;
+
+/// IsolateFsmState declares how a state machine behaves in one of its states: the handler for
+/// messages that arrive in it, what happens to messages the handler gives back, and an optional
+/// timeout that fires if the machine is still in the state after a while.
+pub struct IsolateFsmState<S, D, T: Send + 'static> {
+    handler: IsolateFsmHandler<S, D, T>,
+    unhandled: IsolateUnhandled,
+    timeout: Option<(Duration, IsolateFsmTimeout<S, D, T>)>,
+}
+
+impl<S, D, T: Send + 'static> IsolateFsmState<S, D, T> {
+    /// Create a state with a handler for its messages; by default, messages the handler gives
+    /// back are rejected
+    pub fn new(
+        handler: impl Fn(&IsolateContext<T>, &mut D, T) -> IsolateTransition<S, T>
+            + Send
+            + Sync
+            + 'static,
+    ) -> IsolateFsmState<S, D, T> {
+        IsolateFsmState {
+            handler: Box::new(handler),
+            unhandled: IsolateUnhandled::Reject,
+            timeout: None,
+        }
+    }
+
+    /// Set what happens to messages the handler gives back in this state
+    pub fn unhandled(mut self, unhandled: IsolateUnhandled) -> IsolateFsmState<S, D, T> {
+        self.unhandled = unhandled;
+        self
+    }
+
+    /// Fire a timeout if the machine is still in this state a given time after entering it
+    pub fn timeout(
+        mut self,
+        after: Duration,
+        fired: impl Fn(&IsolateContext<T>, &mut D) -> IsolateTransition<S, T> + Send + Sync + 'static,
+    ) -> IsolateFsmState<S, D, T> {
+        self.timeout = Some((after, Box::new(fired)));
+        self
+    }
+
+    pub(crate) fn handle(
+        &self,
+        context: &IsolateContext<T>,
+        data: &mut D,
+        message: T,
+    ) -> IsolateTransition<S, T> {
+        (self.handler)(context, data, message)
+    }
+
+    pub(crate) fn handle_timeout(
+        &self,
+        context: &IsolateContext<T>,
+        data: &mut D,
+    ) -> IsolateTransition<S, T> {
+        match self.timeout.as_ref() {
+            Some((_, fired)) => fired(context, data),
+            None => IsolateTransition::Stay,
+        }
+    }
+
+    pub(crate) fn timeout_after(&self) -> Option<Duration> {
+        self.timeout.as_ref().map(|(after, _)| *after)
+    }
+
+    pub(crate) fn unhandled_policy(&self) -> IsolateUnhandled {
+        self.unhandled
+    }
+}
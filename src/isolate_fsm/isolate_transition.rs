@@ -0,0 +1,12 @@
+/// What a state machine does after handling a message or a state timeout
+#[derive(Debug, PartialEq)]
+pub enum IsolateTransition<S, T> {
+    /// Stay in the current state; its timeout keeps running
+    Stay,
+    /// Move to a state, starting its timeout; deferred messages are handled again first
+    Goto(S),
+    /// Hand the message back as not handled in this state, to be deferred or rejected
+    Unhandled(T),
+    /// Stop the worker
+    Halt,
+}
@@ -0,0 +1,8 @@
+/// What a state does with messages its handler doesn't handle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IsolateUnhandled {
+    /// Keep the message until the machine moves to another state, then handle it again
+    Defer,
+    /// Pass the message to the dead letter sink of the registry, or drop it if there is none
+    Reject,
+}
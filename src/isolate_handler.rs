@@ -0,0 +1,64 @@
+pub(crate) mod isolate_driver;
+pub(crate) mod isolate_flow;
+
+use crate::IsolateContext;
+use crate::IsolateFlow;
+
+/// IsolateHandler handles the messages for a worker one at a time, so workers don't each need
+/// to implement their own message loop; run it with an IsolateDriver.
+/// The `isolate_handler` attribute can implement this from a set of `#[handler]` methods.
+pub trait IsolateHandler<T: Send + 'static> {
+    /// Handle a message for this worker
+    fn handle(&mut self, context: &IsolateContext<T>, message: T) -> IsolateFlow;
+
+    /// Invoked before the first message is handled
+    fn started(&mut self, _context: &IsolateContext<T>) {}
+
+    /// Invoked once the worker stops handling messages
+    fn stopped(&mut self, _context: &IsolateContext<T>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IsolateHandler;
+    use crate::IsolateContext;
+    use crate::IsolateDriver;
+    use crate::IsolateFlow;
+    use crate::IsolateRuntime;
+    use crate::IsolateRuntimeWait;
+
+    struct SumHandler {
+        total: u32,
+    }
+
+    impl IsolateHandler<u32> for SumHandler {
+        fn handle(&mut self, context: &IsolateContext<u32>, message: u32) -> IsolateFlow {
+            if message == 0 {
+                return IsolateFlow::Halt;
+            }
+            self.total += message;
+            context.reply(self.total).unwrap();
+            IsolateFlow::Continue
+        }
+
+        fn stopped(&mut self, context: &IsolateContext<u32>) {
+            let _ = context.reply(u32::MAX);
+        }
+    }
+
+    #[test]
+    pub fn test_driver() {
+        let mut runtime =
+            IsolateRuntime::new(IsolateDriver::new(|start| SumHandler { total: start }));
+
+        let channel = runtime.spawn_with(10u32).unwrap();
+        channel.sender.send(1).unwrap();
+        channel.sender.send(2).unwrap();
+        channel.sender.send(0).unwrap();
+        assert_eq!(channel.receiver.recv().unwrap(), 11);
+        assert_eq!(channel.receiver.recv().unwrap(), 13);
+        assert_eq!(channel.receiver.recv().unwrap(), u32::MAX);
+
+        runtime.wait();
+    }
+}
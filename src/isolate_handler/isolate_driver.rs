@@ -0,0 +1,57 @@
+use crate::Isolate;
+use crate::IsolateContext;
+use crate::IsolateFlow;
+use crate::IsolateHandler;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// IsolateDriver implements Isolate for a message handler; each worker gets its own handler,
+/// created from the spawn-time args, and runs the message loop until the handler halts, the
+/// worker is stopped or the channel closes.
+pub struct IsolateDriver<A, H> {
+    factory: Arc<dyn Fn(A) -> H + Send + Sync + 'static>,
+    args: PhantomData<fn(A)>,
+}
+
+impl<A: Send + 'static, H> IsolateDriver<A, H> {
+    /// Create a driver that builds a handler for each worker from its spawn-time args
+    pub fn new(factory: impl Fn(A) -> H + Send + Sync + 'static) -> IsolateDriver<A, H> {
+        IsolateDriver {
+            factory: Arc::new(factory),
+            args: PhantomData,
+        }
+    }
+}
+
+impl<H: Clone + Send + Sync + 'static> IsolateDriver<(), H> {
+    /// Create a driver that gives each worker a copy of a handler
+    pub fn from_clone(handler: H) -> IsolateDriver<(), H> {
+        IsolateDriver::new(move |_| handler.clone())
+    }
+}
+
+impl<T, A, H> Isolate<T> for IsolateDriver<A, H>
+where
+    T: Send + 'static,
+    A: Send + 'static,
+    H: IsolateHandler<T> + Send + 'static,
+{
+    type Args = A;
+
+    fn spawn(&self, context: IsolateContext<T>, args: A) -> Box<dyn FnMut() + Send + 'static> {
+        let mut handler = (self.factory)(args);
+        Box::new(move || {
+            handler.started(&context);
+            while let Ok(message) = context.recv() {
+                let started = Instant::now();
+                let flow = handler.handle(&context, message);
+                context.observe(started.elapsed());
+                if flow == IsolateFlow::Halt {
+                    break;
+                }
+            }
+            handler.stopped(&context);
+        })
+    }
+}
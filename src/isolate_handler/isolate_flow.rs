@@ -0,0 +1,8 @@
+/// What a worker does after handling a message
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IsolateFlow {
+    /// Wait for the next message
+    Continue,
+    /// Stop handling messages and exit
+    Halt,
+}
@@ -0,0 +1,131 @@
+pub(crate) mod isolate_mailbox_binding;
+pub(crate) mod isolate_mailbox_error;
+pub(crate) mod isolate_mailbox_log;
+pub(crate) mod isolate_mailbox_sync;
+
+use crate::isolate_mailbox::isolate_mailbox_log::IsolateMailboxLog;
+use crate::IsolateIdentity;
+use crate::IsolateMailboxError;
+use crate::IsolateMailboxSync;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// IsolateDurableMailbox keeps the messages sent to the workers of a runtime on disk until they
+/// are handled, in a directory of its own for each worker identity. A message is acknowledged
+/// when the worker asks for its next message, or exits without panicking; whatever a worker
+/// didn't acknowledge is delivered again to the next worker spawned with its identity. A
+/// worker's directory is deleted when it exits with every message acknowledged.
+///
+/// Delivery is at least once: a message handled just before a crash may not have been
+/// acknowledged yet, and is delivered again.
+#[derive(Clone)]
+pub struct IsolateDurableMailbox {
+    directory: PathBuf,
+    sync: IsolateMailboxSync,
+    segment_size: u64,
+    logs: Arc<Mutex<HashMap<IsolateIdentity, Arc<IsolateMailboxLog>>>>,
+}
+
+impl IsolateDurableMailbox {
+    /// Create a mailbox in a directory, creating the directory if it doesn't exist. By default
+    /// every write is synced, and segments are started every 4MB.
+    pub fn new(
+        directory: impl Into<PathBuf>,
+    ) -> Result<IsolateDurableMailbox, IsolateMailboxError> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+        Ok(IsolateDurableMailbox {
+            directory,
+            sync: IsolateMailboxSync::Always,
+            segment_size: 4 * 1024 * 1024,
+            logs: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Set how often the mailbox is synced to disk
+    pub fn with_sync(mut self, sync: IsolateMailboxSync) -> IsolateDurableMailbox {
+        self.sync = sync;
+        self
+    }
+
+    /// Set the size at which a worker's mailbox starts a new segment file
+    pub fn with_segment_size(mut self, bytes: u64) -> IsolateDurableMailbox {
+        self.segment_size = bytes;
+        self
+    }
+
+    /// Return the identities of workers with messages they haven't acknowledged, such as those
+    /// running before a restart; spawn them again with `spawn_as` to handle the messages.
+    pub fn pending(&self) -> Result<Vec<IsolateIdentity>, IsolateMailboxError> {
+        let mut pending = Vec::new();
+        for entry in fs::read_dir(&self.directory)? {
+            let entry = entry?;
+            let identity = match IsolateIdentity::try_from(&entry.file_name().to_string_lossy()) {
+                Ok(identity) => identity,
+                Err(_) => continue,
+            };
+            // The mailboxes of workers that aren't running are opened just to look, and closed
+            // again, which deletes them if they are done with
+            let logs = self
+                .logs
+                .lock()
+                .map_err(|_| IsolateMailboxError::InternalSyncError)?;
+            let waiting = match logs.get(&identity) {
+                Some(log) => !log.pending()?.is_empty(),
+                None => {
+                    let log = self.open(&identity)?;
+                    let waiting = !log.pending()?.is_empty();
+                    log.close()?;
+                    waiting
+                }
+            };
+            if waiting {
+                pending.push(identity);
+            }
+        }
+        pending.sort_by_key(|identity| identity.to_string());
+        Ok(pending)
+    }
+
+    /// Return the mailbox of a running worker, opening it the first time
+    pub(crate) fn log(
+        &self,
+        identity: &IsolateIdentity,
+    ) -> Result<Arc<IsolateMailboxLog>, IsolateMailboxError> {
+        let mut logs = self
+            .logs
+            .lock()
+            .map_err(|_| IsolateMailboxError::InternalSyncError)?;
+        if let Some(log) = logs.get(identity) {
+            return Ok(log.clone());
+        }
+        let log = Arc::new(self.open(identity)?);
+        logs.insert(*identity, log.clone());
+        Ok(log)
+    }
+
+    /// Close the mailbox of a worker that has exited, so it is opened afresh for the next
+    /// worker with its identity
+    pub(crate) fn close(&self, identity: &IsolateIdentity) -> Result<(), IsolateMailboxError> {
+        let log = self
+            .logs
+            .lock()
+            .map_err(|_| IsolateMailboxError::InternalSyncError)?
+            .remove(identity);
+        match log {
+            Some(log) => log.close(),
+            None => Ok(()),
+        }
+    }
+
+    fn open(&self, identity: &IsolateIdentity) -> Result<IsolateMailboxLog, IsolateMailboxError> {
+        IsolateMailboxLog::open(
+            self.directory.join(identity.to_string()),
+            self.sync,
+            self.segment_size,
+        )
+    }
+}
@@ -0,0 +1,162 @@
+use crate::isolate_mailbox::isolate_mailbox_log::IsolateMailboxLog;
+use crate::IsolateDurableMailbox;
+use crate::IsolateIdentity;
+use crate::IsolateMailboxError;
+use crate::IsolateRecord;
+use crossbeam::{unbounded, Receiver, Sender};
+use std::mem;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// The durable mailbox of a runtime, with how to write and read its messages
+pub struct IsolateMailboxBinding<T> {
+    mailbox: IsolateDurableMailbox,
+    encode: fn(&T) -> Vec<u8>,
+    decode: fn(&[u8]) -> Option<T>,
+}
+
+impl<T: Send + 'static> IsolateMailboxBinding<T> {
+    pub fn new(mailbox: IsolateDurableMailbox) -> IsolateMailboxBinding<T>
+    where
+        T: IsolateRecord,
+    {
+        IsolateMailboxBinding {
+            mailbox,
+            encode: T::to_record,
+            decode: T::from_record,
+        }
+    }
+
+    /// Open the mailbox of a worker, putting the messages it hasn't acknowledged back in its
+    /// lane. Messages that can't be read any more are acknowledged and dropped.
+    pub fn open(
+        &self,
+        identity: &IsolateIdentity,
+    ) -> Result<(IsolateMailboxSender<T>, IsolateMailboxReceipt<T>), IsolateMailboxError> {
+        let log = self.mailbox.log(identity)?;
+        let (lane_s, lane_r) = unbounded();
+        for (sequence, record) in log.pending()? {
+            match (self.decode)(&record) {
+                Some(message) => {
+                    let _ = lane_s.send((message, sequence));
+                }
+                None => log.ack(sequence)?,
+            }
+        }
+        let sender = IsolateMailboxSender {
+            log: log.clone(),
+            lane: lane_s,
+            encode: self.encode,
+        };
+        let receipt = IsolateMailboxReceipt {
+            mailbox: self.mailbox.clone(),
+            identity: *identity,
+            log,
+            lane: lane_r,
+            handling: Mutex::new(Vec::new()),
+        };
+        Ok((sender, receipt))
+    }
+}
+
+/// The runtime end of a worker's durable mailbox; messages are written to the mailbox before
+/// they are sent on a lane of their own, together with their number in the mailbox
+pub struct IsolateMailboxSender<T> {
+    log: Arc<IsolateMailboxLog>,
+    lane: Sender<(T, u64)>,
+    encode: fn(&T) -> Vec<u8>,
+}
+
+impl<T> IsolateMailboxSender<T> {
+    /// Write a message to the mailbox, then deliver it. If the worker has exited the message is
+    /// acknowledged, since it will never be handled, and handed back.
+    pub fn send(&self, message: T) -> Result<Option<T>, IsolateMailboxError> {
+        let sequence = match self.log.append(&(self.encode)(&message))? {
+            Some(sequence) => sequence,
+            None => return Ok(Some(message)),
+        };
+        match self.lane.send((message, sequence)) {
+            Ok(_) => Ok(None),
+            Err(err) => {
+                self.log.ack(sequence)?;
+                Ok(Some(err.into_inner().0))
+            }
+        }
+    }
+
+    /// Return the number of durable messages waiting to be received
+    pub fn len(&self) -> usize {
+        self.lane.len()
+    }
+}
+
+impl<T> Clone for IsolateMailboxSender<T> {
+    fn clone(&self) -> IsolateMailboxSender<T> {
+        IsolateMailboxSender {
+            log: self.log.clone(),
+            lane: self.lane.clone(),
+            encode: self.encode,
+        }
+    }
+}
+
+/// The worker end of a durable mailbox; it keeps track of the messages being handled, so they
+/// can be acknowledged once the worker is done with them. Messages left in the lane when the
+/// worker exits stay in the mailbox, for the next worker spawned with its identity.
+pub struct IsolateMailboxReceipt<T> {
+    mailbox: IsolateDurableMailbox,
+    identity: IsolateIdentity,
+    log: Arc<IsolateMailboxLog>,
+    lane: Receiver<(T, u64)>,
+    handling: Mutex<Vec<u64>>,
+}
+
+impl<T> IsolateMailboxReceipt<T> {
+    /// Return the lane durable messages arrive on, with their numbers
+    pub fn lane(&self) -> &Receiver<(T, u64)> {
+        &self.lane
+    }
+
+    /// Take a durable message that is waiting, marking it as being handled
+    pub fn try_recv(&self) -> Option<T> {
+        let (message, sequence) = self.lane.try_recv().ok()?;
+        Some(self.arrived(message, sequence))
+    }
+
+    /// Mark a message just received from the lane as being handled, and hand it on
+    pub fn arrived(&self, message: T, sequence: u64) -> T {
+        self.handling(Some(sequence));
+        message
+    }
+
+    /// Add the number of a message being handled, if it is a durable one; a worker handles
+    /// more than one at once when it receives a batch
+    pub fn handling(&self, sequence: Option<u64>) {
+        if let (Ok(mut handling), Some(sequence)) = (self.handling.lock(), sequence) {
+            handling.push(sequence);
+        }
+    }
+
+    /// Take the number of the message received last, when it is put aside for later
+    pub fn take(&self) -> Option<u64> {
+        self.handling.lock().ok()?.pop()
+    }
+
+    /// Acknowledge the messages being handled, if there are any. A failed acknowledgement only
+    /// means the message is delivered again after a restart.
+    pub fn complete(&self) {
+        let handling = match self.handling.lock() {
+            Ok(mut handling) => mem::take(&mut *handling),
+            Err(_) => return,
+        };
+        for sequence in handling {
+            let _ = self.log.ack(sequence);
+        }
+    }
+
+    /// Close the mailbox when the worker exits; messages sent to the worker from then on are
+    /// handed back rather than kept for the next worker with its identity
+    pub fn close(&self) {
+        let _ = self.mailbox.close(&self.identity);
+    }
+}
@@ -0,0 +1,24 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+use std::io;
+
+#[derive(Debug)]
+pub enum IsolateMailboxError {
+    InternalSyncError,
+    IoError(io::Error),
+}
+
+impl Error for IsolateMailboxError {}
+
+impl Display for IsolateMailboxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl From<io::Error> for IsolateMailboxError {
+    fn from(err: io::Error) -> Self {
+        IsolateMailboxError::IoError(err)
+    }
+}
@@ -0,0 +1,313 @@
+use crate::isolate_persistent::isolate_file_journal::read_records;
+use crate::isolate_persistent::isolate_file_journal::write_record;
+use crate::IsolateJournalEvents;
+use crate::IsolateMailboxError;
+use crate::IsolateMailboxSync;
+use std::collections::BTreeSet;
+use std::collections::VecDeque;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+struct IsolateMailboxLogState {
+    /// The number of the first message in each segment, oldest first
+    segments: VecDeque<u64>,
+    /// The newest segment, once it has been opened for writing, and its length
+    segment: Option<(File, u64)>,
+    next: u64,
+    /// Every message up to this one has been acknowledged
+    acked: u64,
+    /// Messages acknowledged out of order, after `acked`
+    acked_ahead: BTreeSet<u64>,
+    ack_file: File,
+    unsynced: usize,
+    /// Set once the worker is gone; nothing more is written to a closed mailbox
+    closed: bool,
+}
+
+/// IsolateMailboxLog is the durable mailbox of one worker; messages are appended to numbered
+/// segment files, and the number of the last message acknowledged in order is kept beside
+/// them, followed by the numbers of those acknowledged out of order. Segments are deleted once
+/// every message in them is acknowledged.
+pub struct IsolateMailboxLog {
+    directory: PathBuf,
+    sync: IsolateMailboxSync,
+    segment_size: u64,
+    state: Mutex<IsolateMailboxLogState>,
+}
+
+impl IsolateMailboxLog {
+    /// Open the mailbox in a directory, cutting off a message left partly written at the end
+    pub fn open(
+        directory: PathBuf,
+        sync: IsolateMailboxSync,
+        segment_size: u64,
+    ) -> Result<IsolateMailboxLog, IsolateMailboxError> {
+        fs::create_dir_all(&directory)?;
+        let mut ack_file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(directory.join("ack"))?;
+        let mut acks = Vec::new();
+        ack_file.read_to_end(&mut acks)?;
+        let mut acks = acks.chunks_exact(8).map(|chunk| {
+            let mut sequence = [0; 8];
+            sequence.copy_from_slice(chunk);
+            u64::from_le_bytes(sequence)
+        });
+        let acked = acks.next().unwrap_or(0);
+        let acked_ahead: BTreeSet<u64> = acks.filter(|sequence| *sequence > acked).collect();
+
+        let mut segments: Vec<u64> = Vec::new();
+        for entry in fs::read_dir(&directory)? {
+            let name = entry?.file_name();
+            let name = name.to_string_lossy();
+            if let Some(first) = name.strip_suffix(".segment") {
+                if let Ok(first) = first.parse() {
+                    segments.push(first);
+                }
+            }
+        }
+        segments.sort_unstable();
+
+        let mut next = acked + 1;
+        if let Some(last) = segments.last() {
+            let path = segment_path(&directory, *last);
+            let (records, length) = read_records(&fs::read(&path)?);
+            let file = OpenOptions::new().write(true).open(&path)?;
+            if file.metadata()?.len() > length as u64 {
+                file.set_len(length as u64)?;
+            }
+            next = next.max(records.last().map_or(*last, |(sequence, _)| sequence + 1));
+        }
+
+        let log = IsolateMailboxLog {
+            directory,
+            sync,
+            segment_size,
+            state: Mutex::new(IsolateMailboxLogState {
+                segments: segments.into_iter().collect(),
+                segment: None,
+                next,
+                acked,
+                acked_ahead,
+                ack_file,
+                unsynced: 0,
+                closed: false,
+            }),
+        };
+        if let Ok(mut state) = log.state.lock() {
+            log.compact(&mut state)?;
+        }
+        Ok(log)
+    }
+
+    /// Append a message, returning its number, or nothing if the mailbox is closed
+    pub fn append(&self, message: &[u8]) -> Result<Option<u64>, IsolateMailboxError> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| IsolateMailboxError::InternalSyncError)?;
+        if state.closed {
+            return Ok(None);
+        }
+        let sequence = state.next;
+        let full = match state.segment.as_ref() {
+            Some((_, length)) => *length >= self.segment_size,
+            None => true,
+        };
+        if full {
+            // Carry on with the newest segment if there's room, and start another if not
+            let reopen = match (state.segment.as_ref(), state.segments.back()) {
+                (None, Some(last)) => {
+                    let path = segment_path(&self.directory, *last);
+                    let length = fs::metadata(&path)?.len();
+                    if length < self.segment_size {
+                        Some((OpenOptions::new().append(true).open(path)?, length))
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            };
+            state.segment = match reopen {
+                Some(segment) => Some(segment),
+                None => {
+                    let path = segment_path(&self.directory, sequence);
+                    let file = OpenOptions::new().create(true).append(true).open(path)?;
+                    state.segments.push_back(sequence);
+                    Some((file, 0))
+                }
+            };
+        }
+
+        let record = write_record(sequence, message);
+        if let Some((file, length)) = state.segment.as_mut() {
+            file.write_all(&record)?;
+            *length += record.len() as u64;
+        }
+        state.next += 1;
+        self.written(&mut state)?;
+        Ok(Some(sequence))
+    }
+
+    /// Acknowledge a message, once it has been handled
+    pub fn ack(&self, sequence: u64) -> Result<(), IsolateMailboxError> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| IsolateMailboxError::InternalSyncError)?;
+        if state.closed || sequence <= state.acked || !state.acked_ahead.insert(sequence) {
+            return Ok(());
+        }
+        let mut acked = state.acked;
+        while state.acked_ahead.remove(&(acked + 1)) {
+            acked += 1;
+        }
+        state.acked = acked;
+        let mut acks = acked.to_le_bytes().to_vec();
+        for sequence in state.acked_ahead.iter() {
+            acks.extend_from_slice(&sequence.to_le_bytes());
+        }
+        state.ack_file.seek(SeekFrom::Start(0))?;
+        state.ack_file.write_all(&acks)?;
+        state.ack_file.set_len(acks.len() as u64)?;
+        self.written(&mut state)?;
+        self.compact(&mut state)
+    }
+
+    /// Close the mailbox once its worker is gone, deleting it if every message in it has been
+    /// acknowledged. The files of a closed mailbox can be opened again by the next worker.
+    pub fn close(&self) -> Result<(), IsolateMailboxError> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| IsolateMailboxError::InternalSyncError)?;
+        if state.closed {
+            return Ok(());
+        }
+        state.closed = true;
+        if let Some((file, _)) = state.segment.take() {
+            file.sync_data()?;
+        }
+        state.ack_file.sync_data()?;
+        if state.acked + 1 == state.next && state.acked_ahead.is_empty() {
+            fs::remove_dir_all(&self.directory)?;
+        }
+        Ok(())
+    }
+
+    /// Return the messages that haven't been acknowledged, in order
+    pub fn pending(&self) -> Result<IsolateJournalEvents, IsolateMailboxError> {
+        let state = self
+            .state
+            .lock()
+            .map_err(|_| IsolateMailboxError::InternalSyncError)?;
+        let mut pending = Vec::new();
+        for first in state.segments.iter() {
+            let (records, _) = read_records(&fs::read(segment_path(&self.directory, *first))?);
+            pending.extend(records.into_iter().filter(|(sequence, _)| {
+                *sequence > state.acked && !state.acked_ahead.contains(sequence)
+            }));
+        }
+        Ok(pending)
+    }
+
+    /// Sync the files if the sync policy calls for it
+    fn written(&self, state: &mut IsolateMailboxLogState) -> Result<(), IsolateMailboxError> {
+        state.unsynced += 1;
+        let due = match self.sync {
+            IsolateMailboxSync::Always => true,
+            IsolateMailboxSync::Every(writes) => state.unsynced >= writes,
+            IsolateMailboxSync::Never => false,
+        };
+        if due {
+            if let Some((file, _)) = state.segment.as_ref() {
+                file.sync_data()?;
+            }
+            state.ack_file.sync_data()?;
+            state.unsynced = 0;
+        }
+        Ok(())
+    }
+
+    /// Delete the segments that only hold acknowledged messages, keeping the newest
+    fn compact(&self, state: &mut IsolateMailboxLogState) -> Result<(), IsolateMailboxError> {
+        while state.segments.len() > 1 && state.segments[1] <= state.acked + 1 {
+            if let Some(first) = state.segments.pop_front() {
+                fs::remove_file(segment_path(&self.directory, first))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn segment_path(directory: &Path, first: u64) -> PathBuf {
+    directory.join(format!("{:020}.segment", first))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IsolateMailboxLog;
+    use crate::IsolateIdentity;
+    use crate::IsolateMailboxSync;
+    use std::fs;
+
+    #[test]
+    pub fn test_ack_out_of_order() {
+        let directory =
+            std::env::temp_dir().join(format!("isolate-mailbox-{}", IsolateIdentity::new()));
+        let log = IsolateMailboxLog::open(directory.clone(), IsolateMailboxSync::Never, 1).unwrap();
+        for message in &[b"a", b"b", b"c"] {
+            log.append(*message).unwrap();
+        }
+        log.ack(2).unwrap();
+        log.ack(1).unwrap();
+        assert_eq!(log.pending().unwrap(), vec![(3, b"c".to_vec())]);
+
+        // Acknowledged segments are gone, and numbering carries on after reopening
+        let log = IsolateMailboxLog::open(directory.clone(), IsolateMailboxSync::Never, 1).unwrap();
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 2);
+        assert_eq!(log.append(b"d").unwrap(), Some(4));
+        log.ack(3).unwrap();
+        assert_eq!(log.pending().unwrap(), vec![(4, b"d".to_vec())]);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    pub fn test_ack_ahead_survives_reopening() {
+        let directory =
+            std::env::temp_dir().join(format!("isolate-mailbox-{}", IsolateIdentity::new()));
+        let log =
+            IsolateMailboxLog::open(directory.clone(), IsolateMailboxSync::Never, 16).unwrap();
+        for message in &[b"a", b"b", b"c"] {
+            log.append(*message).unwrap();
+        }
+        log.ack(2).unwrap();
+        log.close().unwrap();
+
+        let log =
+            IsolateMailboxLog::open(directory.clone(), IsolateMailboxSync::Never, 16).unwrap();
+        assert_eq!(
+            log.pending().unwrap(),
+            vec![(1, b"a".to_vec()), (3, b"c".to_vec())]
+        );
+
+        // A closed mailbox takes no more messages, and is deleted once everything is handled
+        log.ack(3).unwrap();
+        log.ack(1).unwrap();
+        log.close().unwrap();
+        assert_eq!(log.append(b"d").unwrap(), None);
+        assert!(!directory.exists());
+    }
+}
@@ -0,0 +1,11 @@
+/// How often a durable mailbox syncs its files to disk
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IsolateMailboxSync {
+    /// Sync every message and acknowledgement before going on; nothing is lost in a crash
+    Always,
+    /// Sync after every so many writes; a crash can lose the messages since the last sync
+    Every(usize),
+    /// Leave syncing to the operating system; messages survive the process crashing, but not
+    /// the machine
+    Never,
+}
@@ -0,0 +1,220 @@
+pub(crate) mod isolate_histogram;
+#[cfg(feature = "prometheus")]
+pub(crate) mod isolate_metrics_error;
+#[cfg(feature = "prometheus")]
+pub(crate) mod isolate_metrics_exporter;
+pub(crate) mod isolate_metrics_recorder;
+pub(crate) mod isolate_metrics_source;
+pub(crate) mod isolate_runtime_metrics;
+pub(crate) mod isolate_worker_metrics;
+
+use crate::IsolateHistogram;
+use crate::IsolateRuntimeMetrics;
+use crate::IsolateWorkerMetrics;
+use std::fmt::Write;
+
+/// IsolateMetrics is a snapshot of the metrics of every runtime in a registry
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsolateMetrics {
+    /// The runtimes, ordered by name
+    pub runtimes: Vec<IsolateRuntimeMetrics>,
+}
+
+impl IsolateMetrics {
+    /// Find the metrics of a runtime by name
+    pub fn runtime(&self, name: &str) -> Option<&IsolateRuntimeMetrics> {
+        self.runtimes
+            .iter()
+            .find(|r| r.name.as_deref() == Some(name))
+    }
+
+    /// Render the metrics in the Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let mut output = String::new();
+        let runtimes = || {
+            self.runtimes
+                .iter()
+                .map(|r| (format!("runtime=\"{}\"", label(r)), r))
+        };
+        let metric = "isolate_runtime_spawned_total";
+        write_values(
+            &mut output,
+            metric,
+            "counter",
+            runtimes().map(|(l, r)| (l, r.spawned)),
+        );
+        let metric = "isolate_runtime_exited_total";
+        write_values(
+            &mut output,
+            metric,
+            "counter",
+            runtimes().map(|(l, r)| (l, r.exited)),
+        );
+        let metric = "isolate_runtime_sent_total";
+        write_values(
+            &mut output,
+            metric,
+            "counter",
+            runtimes().map(|(l, r)| (l, r.sent)),
+        );
+        let metric = "isolate_runtime_received_total";
+        write_values(
+            &mut output,
+            metric,
+            "counter",
+            runtimes().map(|(l, r)| (l, r.received)),
+        );
+        let metric = "isolate_runtime_throttled_total";
+        write_values(
+            &mut output,
+            metric,
+            "counter",
+            runtimes().map(|(l, r)| (l, r.throttled)),
+        );
+
+        let metric = "isolate_worker_sent_total";
+        write_values(
+            &mut output,
+            metric,
+            "counter",
+            self.workers().map(|(l, w)| (l, w.sent)),
+        );
+        let metric = "isolate_worker_received_total";
+        let values = self.workers().map(|(l, w)| (l, w.received));
+        write_values(&mut output, metric, "counter", values);
+        let metric = "isolate_worker_throttled_total";
+        let values = self.workers().map(|(l, w)| (l, w.throttled));
+        write_values(&mut output, metric, "counter", values);
+        let metric = "isolate_worker_mailbox_length";
+        let values = self.workers().map(|(l, w)| (l, w.mailbox as u64));
+        write_values(&mut output, metric, "gauge", values);
+
+        let metric = "isolate_handler_latency_seconds";
+        let _ = writeln!(output, "# TYPE {} histogram", metric);
+        for (labels, worker) in self.workers() {
+            write_histogram(&mut output, metric, &labels, &worker.latency);
+        }
+        output
+    }
+
+    /// Return each worker with the labels that identify it
+    fn workers(&self) -> impl Iterator<Item = (String, &IsolateWorkerMetrics)> {
+        self.runtimes.iter().flat_map(|runtime| {
+            runtime.workers.iter().map(move |worker| {
+                let labels = format!(
+                    "runtime=\"{}\",worker=\"{}\"",
+                    label(runtime),
+                    worker.identity
+                );
+                (labels, worker)
+            })
+        })
+    }
+}
+
+/// Return the runtime name as a label value, escaped
+fn label(runtime: &IsolateRuntimeMetrics) -> String {
+    runtime
+        .name
+        .as_deref()
+        .unwrap_or("")
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn write_values(
+    output: &mut String,
+    metric: &str,
+    kind: &str,
+    values: impl Iterator<Item = (String, u64)>,
+) {
+    let _ = writeln!(output, "# TYPE {} {}", metric, kind);
+    for (labels, value) in values {
+        let _ = writeln!(output, "{}{{{}}} {}", metric, labels, value);
+    }
+}
+
+fn write_histogram(output: &mut String, metric: &str, labels: &str, histogram: &IsolateHistogram) {
+    for (bound, count) in histogram.buckets() {
+        let _ = writeln!(
+            output,
+            "{}_bucket{{{},le=\"{}\"}} {}",
+            metric, labels, bound, count
+        );
+    }
+    let _ = writeln!(
+        output,
+        "{}_bucket{{{},le=\"+Inf\"}} {}",
+        metric,
+        labels,
+        histogram.count()
+    );
+    let _ = writeln!(
+        output,
+        "{}_sum{{{}}} {}",
+        metric,
+        labels,
+        histogram.sum().as_secs_f64()
+    );
+    let _ = writeln!(
+        output,
+        "{}_count{{{}}} {}",
+        metric,
+        labels,
+        histogram.count()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::IsolateHistogram;
+    use crate::IsolateIdentity;
+    use crate::IsolateMetrics;
+    use crate::IsolateRuntimeMetrics;
+    use crate::IsolateWorkerMetrics;
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_histogram_buckets() {
+        let mut histogram = IsolateHistogram::new();
+        histogram.observe(Duration::from_micros(50));
+        histogram.observe(Duration::from_millis(3));
+        histogram.observe(Duration::from_secs(10));
+        let buckets = histogram.buckets();
+        assert_eq!(buckets[0], (0.0001, 1));
+        assert_eq!(buckets[3], (0.005, 2));
+        assert_eq!(buckets[9], (5.0, 2));
+        assert_eq!(histogram.count(), 3);
+    }
+
+    #[test]
+    pub fn test_prometheus_format() {
+        let identity = IsolateIdentity::new();
+        let metrics = IsolateMetrics {
+            runtimes: vec![IsolateRuntimeMetrics {
+                name: Some("Peer \"1\"".to_string()),
+                spawned: 2,
+                exited: 1,
+                sent: 3,
+                received: 4,
+                throttled: 6,
+                workers: vec![IsolateWorkerMetrics {
+                    identity,
+                    sent: 1,
+                    received: 2,
+                    throttled: 6,
+                    mailbox: 5,
+                    latency: IsolateHistogram::new(),
+                }],
+            }],
+        };
+        let output = metrics.to_prometheus();
+        assert!(output.contains("isolate_runtime_spawned_total{runtime=\"Peer \\\"1\\\"\"} 2\n"));
+        assert!(output.contains(&format!(
+            "isolate_worker_mailbox_length{{runtime=\"Peer \\\"1\\\"\",worker=\"{}\"}} 5\n",
+            identity
+        )));
+        assert!(output.contains("le=\"+Inf\"} 0\n"));
+    }
+}
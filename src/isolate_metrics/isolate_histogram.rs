@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+/// The upper bounds, in seconds, of the latency histogram buckets
+const BOUNDS: [f64; 10] = [
+    0.000_1, 0.000_5, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0,
+];
+
+/// A histogram of durations in fixed buckets, from a tenth of a millisecond to five seconds
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsolateHistogram {
+    counts: [u64; BOUNDS.len()],
+    count: u64,
+    sum: Duration,
+}
+
+impl IsolateHistogram {
+    pub fn new() -> IsolateHistogram {
+        IsolateHistogram {
+            counts: [0; BOUNDS.len()],
+            count: 0,
+            sum: Duration::from_secs(0),
+        }
+    }
+
+    /// Record a duration
+    pub fn observe(&mut self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        if let Some(index) = BOUNDS.iter().position(|bound| seconds <= *bound) {
+            self.counts[index] += 1;
+        }
+        self.count += 1;
+        self.sum += duration;
+    }
+
+    /// Return the number of durations recorded
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Return the total of the durations recorded
+    pub fn sum(&self) -> Duration {
+        self.sum
+    }
+
+    /// Return the upper bound of each bucket in seconds, with the number of durations at or
+    /// below it; durations above the last bound are only included in the count.
+    pub fn buckets(&self) -> Vec<(f64, u64)> {
+        BOUNDS
+            .iter()
+            .zip(self.counts.iter())
+            .scan(0, |total, (bound, count)| {
+                *total += count;
+                Some((*bound, *total))
+            })
+            .collect()
+    }
+}
+
+impl Default for IsolateHistogram {
+    fn default() -> Self {
+        IsolateHistogram::new()
+    }
+}
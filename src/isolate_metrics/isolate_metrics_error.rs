@@ -0,0 +1,23 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+use std::io;
+
+#[derive(Debug)]
+pub enum IsolateMetricsError {
+    IoError(io::Error),
+}
+
+impl Error for IsolateMetricsError {}
+
+impl Display for IsolateMetricsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl From<io::Error> for IsolateMetricsError {
+    fn from(err: io::Error) -> Self {
+        IsolateMetricsError::IoError(err)
+    }
+}
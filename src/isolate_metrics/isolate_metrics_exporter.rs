@@ -0,0 +1,159 @@
+use crate::IsolateMetricsError;
+use crate::IsolateRegistryRef;
+use crossbeam::{bounded, Receiver};
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::net::ToSocketAddrs;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+
+/// How long a client has in all to send its request, and how long it has to take each part of
+/// the response
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The most a client may send before the end of its request headers
+const MAX_REQUEST: usize = 8192;
+
+/// How many clients are answered at once; clients that connect while every thread is busy and
+/// as many again are waiting are turned away
+const CLIENT_THREADS: usize = 4;
+
+/// How often the server checks whether it has been stopped while there are no new clients
+const ACCEPT_POLL: Duration = Duration::from_millis(50);
+
+/// IsolateMetricsExporter serves the metrics of a registry in the Prometheus text format over
+/// HTTP, at `/metrics`, answering clients on a small pool of threads. The server stops when
+/// the exporter is dropped; clients being answered then are still given their time to finish.
+pub struct IsolateMetricsExporter {
+    address: SocketAddr,
+    stopped: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl IsolateMetricsExporter {
+    /// Start serving the metrics of a registry on an address; use port 0 for any free port
+    pub fn serve(
+        registry: IsolateRegistryRef,
+        address: impl ToSocketAddrs,
+    ) -> Result<IsolateMetricsExporter, IsolateMetricsError> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+        let (clients, waiting) = bounded(CLIENT_THREADS);
+        for _ in 0..CLIENT_THREADS {
+            let registry = registry.clone();
+            let waiting: Receiver<TcpStream> = waiting.clone();
+            thread::spawn(move || {
+                for stream in waiting.iter() {
+                    let _ = IsolateMetricsExporter::respond(&registry, stream);
+                }
+            });
+        }
+        let stopped = Arc::new(AtomicBool::new(false));
+        let server_stopped = stopped.clone();
+        let handle = thread::spawn(move || {
+            while !server_stopped.load(Ordering::SeqCst) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        if stream.set_nonblocking(false).is_ok() {
+                            let _ = clients.try_send(stream);
+                        }
+                    }
+                    Err(_) => thread::sleep(ACCEPT_POLL),
+                }
+            }
+        });
+        Ok(IsolateMetricsExporter {
+            address,
+            stopped,
+            handle: Some(handle),
+        })
+    }
+
+    /// Return the address the metrics are served on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Answer a single request, reading only as far as the end of the request headers
+    fn respond(registry: &IsolateRegistryRef, mut stream: TcpStream) -> io::Result<()> {
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+        let request = match IsolateMetricsExporter::read_request(&mut stream)? {
+            Some(request) => request,
+            None => {
+                return IsolateMetricsExporter::write_response(
+                    &mut stream,
+                    "431 Request Header Fields Too Large",
+                    "",
+                )
+            }
+        };
+
+        let path = request.split_whitespace().nth(1).unwrap_or("");
+        let (status, body) = match (request.starts_with("GET "), path, registry.metrics()) {
+            (true, "/metrics", Ok(metrics)) => ("200 OK", metrics.to_prometheus()),
+            (true, "/metrics", Err(err)) => ("500 Internal Server Error", err.to_string()),
+            _ => ("404 Not Found", String::new()),
+        };
+        IsolateMetricsExporter::write_response(&mut stream, status, &body)
+    }
+
+    /// Read a request up to the end of its headers, returning nothing if it is too long. The
+    /// client has CLIENT_TIMEOUT in all, however slowly it sends.
+    fn read_request(stream: &mut TcpStream) -> io::Result<Option<String>> {
+        let deadline = Instant::now() + CLIENT_TIMEOUT;
+        let mut request = Vec::new();
+        let mut buffer = [0; 1024];
+        let ended = |request: &[u8]| {
+            request.windows(4).any(|end| end == b"\r\n\r\n")
+                || request.windows(2).any(|end| end == b"\n\n")
+        };
+        while !ended(&request) {
+            if request.len() >= MAX_REQUEST {
+                return Ok(None);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::from_secs(0) {
+                return Err(io::ErrorKind::TimedOut.into());
+            }
+            stream.set_read_timeout(Some(remaining))?;
+            let read = stream.read(&mut buffer[..(MAX_REQUEST - request.len()).min(1024)])?;
+            if read == 0 {
+                break;
+            }
+            request.extend_from_slice(&buffer[..read]);
+        }
+        Ok(Some(String::from_utf8_lossy(&request).into_owned()))
+    }
+
+    fn write_response(stream: &mut TcpStream, status: &str, body: &str) -> io::Result<()> {
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )?;
+        stream.flush()
+    }
+}
+
+impl Drop for IsolateMetricsExporter {
+    fn drop(&mut self) {
+        // The server sees it has been stopped the next time it polls for clients, and the
+        // client threads stop once the clients they have are answered
+        self.stopped.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::isolate_metrics::isolate_histogram::IsolateHistogram;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The counters a worker updates as it runs; they are read by the runtime for snapshots
+pub struct IsolateMetricsRecorder {
+    sent: AtomicU64,
+    received: AtomicU64,
+    throttled: AtomicU64,
+    latency: Mutex<IsolateHistogram>,
+}
+
+impl IsolateMetricsRecorder {
+    pub fn new() -> IsolateMetricsRecorder {
+        IsolateMetricsRecorder {
+            sent: AtomicU64::new(0),
+            received: AtomicU64::new(0),
+            throttled: AtomicU64::new(0),
+            latency: Mutex::new(IsolateHistogram::new()),
+        }
+    }
+
+    /// Count a message sent by the worker
+    pub fn sent(&self) {
+        self.sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a message received by the worker
+    pub fn received(&self) {
+        self.received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a message to the worker that was delayed or rejected by a rate limit
+    pub fn throttled(&self) {
+        self.throttled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record how long the worker took to handle a message
+    pub fn observe(&self, duration: Duration) {
+        if let Ok(mut latency) = self.latency.lock() {
+            latency.observe(duration);
+        }
+    }
+
+    /// Return the number of messages sent and received so far
+    pub fn counts(&self) -> (u64, u64) {
+        (
+            self.sent.load(Ordering::Relaxed),
+            self.received.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Return the number of messages to the worker that were throttled so far
+    pub fn throttled_count(&self) -> u64 {
+        self.throttled.load(Ordering::Relaxed)
+    }
+
+    /// Return a copy of the handler latency histogram
+    pub fn latency(&self) -> IsolateHistogram {
+        match self.latency.lock() {
+            Ok(latency) => latency.clone(),
+            Err(_) => IsolateHistogram::new(),
+        }
+    }
+}
@@ -0,0 +1,7 @@
+use crate::IsolateRuntimeMetrics;
+
+/// A runtime that can report metrics without its message type being known
+pub trait IsolateMetricsSource {
+    /// Take a snapshot of the runtime metrics
+    fn metrics(&self) -> Option<IsolateRuntimeMetrics>;
+}
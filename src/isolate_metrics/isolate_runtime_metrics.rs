@@ -0,0 +1,19 @@
+use crate::IsolateWorkerMetrics;
+
+/// A snapshot of the counters of a runtime and each of its running workers
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsolateRuntimeMetrics {
+    /// The name the runtime is bound to in a registry, if it is bound to one
+    pub name: Option<String>,
+    pub spawned: u64,
+    pub exited: u64,
+    /// Messages sent by every worker this runtime has spawned, including those that exited
+    pub sent: u64,
+    /// Messages received by every worker this runtime has spawned, including those that exited
+    pub received: u64,
+    /// Messages to every worker this runtime has spawned that were delayed or rejected by a
+    /// rate limit
+    pub throttled: u64,
+    /// The running workers, ordered by identity
+    pub workers: Vec<IsolateWorkerMetrics>,
+}
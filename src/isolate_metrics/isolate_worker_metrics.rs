@@ -0,0 +1,20 @@
+use crate::IsolateHistogram;
+use crate::IsolateIdentity;
+
+/// A snapshot of the counters of a running worker
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsolateWorkerMetrics {
+    pub identity: IsolateIdentity,
+    /// Messages the worker sent with `reply` on its context; messages sent directly on a
+    /// channel's `sender` aren't counted
+    pub sent: u64,
+    /// Messages the worker received through its context; messages read directly from its
+    /// channel's `receiver` aren't counted
+    pub received: u64,
+    /// Messages to the worker that were delayed or rejected by a rate limit
+    pub throttled: u64,
+    /// Messages waiting in the worker's inbox
+    pub mailbox: usize,
+    /// How long the handler took for each message, for workers run by an `IsolateDriver`
+    pub latency: IsolateHistogram,
+}
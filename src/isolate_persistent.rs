@@ -0,0 +1,33 @@
+pub(crate) mod isolate_file_journal;
+pub(crate) mod isolate_journal;
+pub(crate) mod isolate_journal_error;
+pub(crate) mod isolate_memory_journal;
+pub(crate) mod isolate_persistent_driver;
+pub(crate) mod isolate_record;
+
+use crate::IsolateContext;
+use crate::IsolateFlow;
+use crate::IsolateRecord;
+
+/// IsolatePersistent is the state of a worker that survives restarts. Handling a message only
+/// decides what happened, as events; the state then changes by applying them, so replaying the
+/// journaled events on top of the last snapshot recovers it. Run it with an
+/// IsolatePersistentDriver.
+pub trait IsolatePersistent<T: Send + 'static>: IsolateRecord {
+    /// The record of a change to the state
+    type Event: IsolateRecord;
+
+    /// Handle a message for this worker, pushing the events it causes
+    fn handle(
+        &self,
+        context: &IsolateContext<T>,
+        message: T,
+        events: &mut Vec<Self::Event>,
+    ) -> IsolateFlow;
+
+    /// Change the state by an event, both as it happens and on recovery
+    fn apply(&mut self, event: &Self::Event);
+
+    /// Invoked once the state is recovered, before the first message is handled
+    fn recovered(&mut self, _context: &IsolateContext<T>) {}
+}
@@ -0,0 +1,278 @@
+use crate::IsolateIdentity;
+use crate::IsolateJournal;
+use crate::IsolateJournalError;
+use crate::IsolateJournalEvents;
+use std::collections::HashMap;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// The size of the header before each event; its number, then its length
+const HEADER: usize = 12;
+
+/// The last event number in a worker's log, and the length of the log up to the end of it
+struct IsolateFileJournalLog {
+    last: u64,
+    length: u64,
+}
+
+/// IsolateFileJournal keeps an append-only log of events for each worker in a directory, with
+/// its latest snapshot beside it. Each append is synced to disk before it returns; an event cut
+/// short by a crash is ignored when the log is read back. Saving a snapshot rewrites the log
+/// without the events it covers.
+/// Event numbers are kept in memory once a log has been read, so a journal must be the only
+/// writer to its directory; two journals on the same directory would number events twice.
+pub struct IsolateFileJournal {
+    directory: PathBuf,
+    logs: Mutex<HashMap<IsolateIdentity, IsolateFileJournalLog>>,
+}
+
+impl IsolateFileJournal {
+    /// Create a journal in a directory, creating the directory if it doesn't exist
+    pub fn new(directory: impl Into<PathBuf>) -> Result<IsolateFileJournal, IsolateJournalError> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+        Ok(IsolateFileJournal {
+            directory,
+            logs: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn path(&self, identity: &IsolateIdentity, extension: &str) -> PathBuf {
+        self.directory.join(format!("{}.{}", identity, extension))
+    }
+
+    /// Read every complete event in the log of a worker, and the length of the log they take up
+    fn read(
+        &self,
+        identity: &IsolateIdentity,
+    ) -> Result<(IsolateJournalEvents, u64), IsolateJournalError> {
+        let bytes = match fs::read(self.path(identity, "journal")) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+            Err(err) => return Err(err.into()),
+        };
+        let (events, length) = read_records(&bytes);
+        Ok((events, length as u64))
+    }
+
+    /// Find where the log of a worker ends, the first time it is written to; any event left
+    /// partly written is cut off, so new events follow the last whole one. Events covered by
+    /// the snapshot may already be gone from the log, so numbering carries on from there.
+    fn log<'a>(
+        &self,
+        logs: &'a mut HashMap<IsolateIdentity, IsolateFileJournalLog>,
+        identity: &IsolateIdentity,
+    ) -> Result<&'a mut IsolateFileJournalLog, IsolateJournalError> {
+        if !logs.contains_key(identity) {
+            let (events, length) = self.read(identity)?;
+            if let Ok(file) = OpenOptions::new()
+                .write(true)
+                .open(self.path(identity, "journal"))
+            {
+                if file.metadata()?.len() > length {
+                    file.set_len(length)?;
+                }
+            }
+            let snapshot = self.snapshot(identity)?.map_or(0, |(sequence, _)| sequence);
+            let last = events.last().map_or(0, |(sequence, _)| *sequence);
+            logs.insert(
+                *identity,
+                IsolateFileJournalLog {
+                    last: last.max(snapshot),
+                    length,
+                },
+            );
+        }
+        logs.get_mut(identity)
+            .ok_or(IsolateJournalError::InternalSyncError)
+    }
+}
+
+/// Frame a record with its number and length, to be appended to a log
+pub(crate) fn write_record(sequence: u64, record: &[u8]) -> Vec<u8> {
+    let mut framed = Vec::with_capacity(HEADER + record.len());
+    framed.extend_from_slice(&sequence.to_le_bytes());
+    framed.extend_from_slice(&(record.len() as u32).to_le_bytes());
+    framed.extend_from_slice(record);
+    framed
+}
+
+/// Read every complete record in a log, and the length of the log they take up; a record cut
+/// short at the end is left out.
+pub(crate) fn read_records(bytes: &[u8]) -> (IsolateJournalEvents, usize) {
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while offset + HEADER <= bytes.len() {
+        let mut sequence = [0; 8];
+        let mut length = [0; 4];
+        sequence.copy_from_slice(&bytes[offset..offset + 8]);
+        length.copy_from_slice(&bytes[offset + 8..offset + HEADER]);
+        let start = offset + HEADER;
+        let end = start + u32::from_le_bytes(length) as usize;
+        if end > bytes.len() {
+            break;
+        }
+        records.push((u64::from_le_bytes(sequence), bytes[start..end].to_vec()));
+        offset = end;
+    }
+    (records, offset)
+}
+
+impl IsolateJournal for IsolateFileJournal {
+    fn append(&self, identity: &IsolateIdentity, event: &[u8]) -> Result<u64, IsolateJournalError> {
+        let mut logs = self
+            .logs
+            .lock()
+            .map_err(|_| IsolateJournalError::InternalSyncError)?;
+        let log = self.log(&mut logs, identity)?;
+        let sequence = log.last + 1;
+
+        let record = write_record(sequence, event);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(identity, "journal"))?;
+        file.write_all(&record)?;
+        file.sync_data()?;
+
+        log.last = sequence;
+        log.length += record.len() as u64;
+        Ok(sequence)
+    }
+
+    fn events(
+        &self,
+        identity: &IsolateIdentity,
+        after: u64,
+    ) -> Result<IsolateJournalEvents, IsolateJournalError> {
+        let (mut events, _) = self.read(identity)?;
+        events.retain(|(sequence, _)| *sequence > after);
+        Ok(events)
+    }
+
+    fn save_snapshot(
+        &self,
+        identity: &IsolateIdentity,
+        sequence: u64,
+        state: &[u8],
+    ) -> Result<(), IsolateJournalError> {
+        let mut logs = self
+            .logs
+            .lock()
+            .map_err(|_| IsolateJournalError::InternalSyncError)?;
+        let log = self.log(&mut logs, identity)?;
+
+        // Write the snapshot beside the old one and swap it in, so a crash leaves one or the other
+        let temporary = self.path(identity, "snapshot.tmp");
+        let mut file = fs::File::create(&temporary)?;
+        file.write_all(&sequence.to_le_bytes())?;
+        file.write_all(state)?;
+        file.sync_data()?;
+        fs::rename(&temporary, self.path(identity, "snapshot"))?;
+
+        // Then rewrite the log with only the events after the snapshot, in the same way
+        let (events, _) = self.read(identity)?;
+        let temporary = self.path(identity, "journal.tmp");
+        let mut file = fs::File::create(&temporary)?;
+        let mut length = 0;
+        for (event_sequence, event) in events.iter().filter(|(s, _)| *s > sequence) {
+            let record = write_record(*event_sequence, event);
+            file.write_all(&record)?;
+            length += record.len() as u64;
+        }
+        file.sync_data()?;
+        fs::rename(&temporary, self.path(identity, "journal"))?;
+        log.length = length;
+        Ok(())
+    }
+
+    fn snapshot(
+        &self,
+        identity: &IsolateIdentity,
+    ) -> Result<Option<(u64, Vec<u8>)>, IsolateJournalError> {
+        let bytes = match fs::read(self.path(identity, "snapshot")) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        if bytes.len() < 8 {
+            return Err(IsolateJournalError::InvalidRecord(format!(
+                "Snapshot of {} is truncated",
+                identity
+            )));
+        }
+        let mut sequence = [0; 8];
+        sequence.copy_from_slice(&bytes[..8]);
+        Ok(Some((u64::from_le_bytes(sequence), bytes[8..].to_vec())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::IsolateFileJournal;
+    use crate::IsolateIdentity;
+    use crate::IsolateJournal;
+    use std::fs;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    #[test]
+    pub fn test_append_and_reopen() {
+        let directory =
+            std::env::temp_dir().join(format!("isolate-journal-{}", IsolateIdentity::new()));
+        let identity = IsolateIdentity::new();
+        let journal = IsolateFileJournal::new(&directory).unwrap();
+        assert_eq!(journal.append(&identity, b"one").unwrap(), 1);
+        assert_eq!(journal.append(&identity, b"two").unwrap(), 2);
+        journal.save_snapshot(&identity, 1, b"state").unwrap();
+
+        // A partly written event is dropped, and numbering carries on after reopening
+        let path = directory.join(format!("{}.journal", identity));
+        let mut file = OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(&[3, 0, 0]).unwrap();
+        let journal = IsolateFileJournal::new(&directory).unwrap();
+        assert_eq!(
+            journal.events(&identity, 1).unwrap(),
+            vec![(2, b"two".to_vec())]
+        );
+        assert_eq!(journal.append(&identity, b"three").unwrap(), 3);
+        assert_eq!(
+            journal.events(&identity, 1).unwrap(),
+            vec![(2, b"two".to_vec()), (3, b"three".to_vec())]
+        );
+        assert_eq!(
+            journal.snapshot(&identity).unwrap(),
+            Some((1, b"state".to_vec()))
+        );
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    pub fn test_snapshot_truncates_log() {
+        let directory =
+            std::env::temp_dir().join(format!("isolate-journal-{}", IsolateIdentity::new()));
+        let identity = IsolateIdentity::new();
+        let journal = IsolateFileJournal::new(&directory).unwrap();
+        assert_eq!(journal.append(&identity, b"one").unwrap(), 1);
+        assert_eq!(journal.append(&identity, b"two").unwrap(), 2);
+        journal.save_snapshot(&identity, 2, b"state").unwrap();
+        assert!(journal.events(&identity, 0).unwrap().is_empty());
+        assert_eq!(journal.append(&identity, b"three").unwrap(), 3);
+
+        // Numbering carries on after the snapshot, even with nothing left in the log
+        journal.save_snapshot(&identity, 3, b"state").unwrap();
+        let journal = IsolateFileJournal::new(&directory).unwrap();
+        assert_eq!(journal.append(&identity, b"four").unwrap(), 4);
+        assert_eq!(
+            journal.events(&identity, 0).unwrap(),
+            vec![(4, b"four".to_vec())]
+        );
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
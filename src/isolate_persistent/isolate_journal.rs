@@ -0,0 +1,34 @@
+use crate::IsolateIdentity;
+use crate::IsolateJournalError;
+
+/// Journaled events with their numbers, in order
+pub type IsolateJournalEvents = Vec<(u64, Vec<u8>)>;
+
+/// IsolateJournal stores the events and snapshots of persistent workers, by worker identity.
+/// Events are numbered from 1 for each worker; a snapshot records the number of the last event
+/// it includes.
+pub trait IsolateJournal: Send + Sync {
+    /// Append an event for a worker, returning its number
+    fn append(&self, identity: &IsolateIdentity, event: &[u8]) -> Result<u64, IsolateJournalError>;
+
+    /// Return the events of a worker numbered after a given event, in order
+    fn events(
+        &self,
+        identity: &IsolateIdentity,
+        after: u64,
+    ) -> Result<IsolateJournalEvents, IsolateJournalError>;
+
+    /// Replace the snapshot of a worker
+    fn save_snapshot(
+        &self,
+        identity: &IsolateIdentity,
+        sequence: u64,
+        state: &[u8],
+    ) -> Result<(), IsolateJournalError>;
+
+    /// Return the latest snapshot of a worker, if it has one
+    fn snapshot(
+        &self,
+        identity: &IsolateIdentity,
+    ) -> Result<Option<(u64, Vec<u8>)>, IsolateJournalError>;
+}
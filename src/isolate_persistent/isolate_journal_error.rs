@@ -0,0 +1,25 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+use std::io;
+
+#[derive(Debug)]
+pub enum IsolateJournalError {
+    InternalSyncError,
+    IoError(io::Error),
+    InvalidRecord(String),
+}
+
+impl Error for IsolateJournalError {}
+
+impl Display for IsolateJournalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl From<io::Error> for IsolateJournalError {
+    fn from(err: io::Error) -> Self {
+        IsolateJournalError::IoError(err)
+    }
+}
@@ -0,0 +1,86 @@
+use crate::IsolateIdentity;
+use crate::IsolateJournal;
+use crate::IsolateJournalError;
+use crate::IsolateJournalEvents;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+#[derive(Default)]
+struct IsolateMemoryJournalEntry {
+    events: Vec<Vec<u8>>,
+    snapshot: Option<(u64, Vec<u8>)>,
+}
+
+/// IsolateMemoryJournal keeps events and snapshots in memory, for tests; clones share the same
+/// journal, so a clone can be kept to inspect it or to recover from after the runtime is gone.
+#[derive(Clone, Default)]
+pub struct IsolateMemoryJournal {
+    entries: Arc<Mutex<HashMap<IsolateIdentity, IsolateMemoryJournalEntry>>>,
+}
+
+impl IsolateMemoryJournal {
+    pub fn new() -> IsolateMemoryJournal {
+        IsolateMemoryJournal::default()
+    }
+}
+
+impl IsolateJournal for IsolateMemoryJournal {
+    fn append(&self, identity: &IsolateIdentity, event: &[u8]) -> Result<u64, IsolateJournalError> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|_| IsolateJournalError::InternalSyncError)?;
+        let entry = entries.entry(*identity).or_default();
+        entry.events.push(event.to_vec());
+        Ok(entry.events.len() as u64)
+    }
+
+    fn events(
+        &self,
+        identity: &IsolateIdentity,
+        after: u64,
+    ) -> Result<IsolateJournalEvents, IsolateJournalError> {
+        let entries = self
+            .entries
+            .lock()
+            .map_err(|_| IsolateJournalError::InternalSyncError)?;
+        Ok(entries
+            .get(identity)
+            .map(|entry| {
+                (1..)
+                    .zip(entry.events.iter())
+                    .skip(after as usize)
+                    .map(|(sequence, event)| (sequence, event.clone()))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    fn save_snapshot(
+        &self,
+        identity: &IsolateIdentity,
+        sequence: u64,
+        state: &[u8],
+    ) -> Result<(), IsolateJournalError> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|_| IsolateJournalError::InternalSyncError)?;
+        entries.entry(*identity).or_default().snapshot = Some((sequence, state.to_vec()));
+        Ok(())
+    }
+
+    fn snapshot(
+        &self,
+        identity: &IsolateIdentity,
+    ) -> Result<Option<(u64, Vec<u8>)>, IsolateJournalError> {
+        let entries = self
+            .entries
+            .lock()
+            .map_err(|_| IsolateJournalError::InternalSyncError)?;
+        Ok(entries
+            .get(identity)
+            .and_then(|entry| entry.snapshot.clone()))
+    }
+}
@@ -0,0 +1,154 @@
+use crate::Isolate;
+use crate::IsolateContext;
+use crate::IsolateExitReason;
+use crate::IsolateFileJournal;
+use crate::IsolateFlow;
+use crate::IsolateIdentity;
+use crate::IsolateJournal;
+use crate::IsolateJournalError;
+use crate::IsolatePersistent;
+use crate::IsolateRecord;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// IsolatePersistentDriver implements Isolate for persistent state. Each event is journaled
+/// before it is applied, and the state is snapshotted every so many events; a worker spawned
+/// with the identity of one that exited recovers its state from the journal rather than
+/// building it from the spawn-time args. A worker that can't reach its journal fails.
+pub struct IsolatePersistentDriver<A, P> {
+    journal: Arc<dyn IsolateJournal>,
+    factory: Arc<dyn Fn(A) -> P + Send + Sync + 'static>,
+    snapshot_every: u64,
+    args: PhantomData<fn(A)>,
+}
+
+impl<A: Send + 'static, P> IsolatePersistentDriver<A, P> {
+    /// Create a driver that journals to an `IsolateFileJournal` in a directory, building the
+    /// state of new workers from their spawn-time args
+    pub fn open(
+        directory: impl Into<PathBuf>,
+        factory: impl Fn(A) -> P + Send + Sync + 'static,
+    ) -> Result<IsolatePersistentDriver<A, P>, IsolateJournalError> {
+        Ok(IsolatePersistentDriver::new(
+            IsolateFileJournal::new(directory)?,
+            factory,
+        ))
+    }
+
+    /// Create a driver that journals to the given journal, building the state of new workers
+    /// from their spawn-time args. By default the state is snapshotted every 100 events.
+    pub fn new(
+        journal: impl IsolateJournal + 'static,
+        factory: impl Fn(A) -> P + Send + Sync + 'static,
+    ) -> IsolatePersistentDriver<A, P> {
+        IsolatePersistentDriver {
+            journal: Arc::new(journal),
+            factory: Arc::new(factory),
+            snapshot_every: 100,
+            args: PhantomData,
+        }
+    }
+
+    /// Snapshot the state every given number of events, or never for 0
+    pub fn snapshot_every(mut self, events: u64) -> IsolatePersistentDriver<A, P> {
+        self.snapshot_every = events;
+        self
+    }
+}
+
+/// Recover the state of a worker from its last snapshot and the events after it, returning it
+/// with the numbers of the snapshot and of the last event
+fn recover<P: IsolateRecord, E: IsolateRecord>(
+    journal: &dyn IsolateJournal,
+    identity: &IsolateIdentity,
+    initial: P,
+    apply: impl Fn(&mut P, &E),
+) -> Result<(P, u64, u64), IsolateJournalError> {
+    let (mut state, mut sequence) = match journal.snapshot(identity)? {
+        Some((sequence, record)) => match P::from_record(&record) {
+            Some(state) => (state, sequence),
+            None => {
+                return Err(IsolateJournalError::InvalidRecord(format!(
+                    "Snapshot {} of {}",
+                    sequence, identity
+                )))
+            }
+        },
+        None => (initial, 0),
+    };
+    let snapshot = sequence;
+    for (next, record) in journal.events(identity, snapshot)? {
+        match E::from_record(&record) {
+            Some(event) => apply(&mut state, &event),
+            None => {
+                return Err(IsolateJournalError::InvalidRecord(format!(
+                    "Event {} of {}",
+                    next, identity
+                )))
+            }
+        }
+        sequence = next;
+    }
+    Ok((state, snapshot, sequence))
+}
+
+impl<T, A, P> Isolate<T> for IsolatePersistentDriver<A, P>
+where
+    T: Send + 'static,
+    A: Send + 'static,
+    P: IsolatePersistent<T> + Send + 'static,
+{
+    type Args = A;
+
+    fn spawn(&self, context: IsolateContext<T>, args: A) -> Box<dyn FnMut() + Send + 'static> {
+        let mut initial = Some((self.factory)(args));
+        let journal = self.journal.clone();
+        let snapshot_every = self.snapshot_every;
+        Box::new(move || {
+            let identity = context.identity();
+            let initial = match initial.take() {
+                Some(initial) => initial,
+                None => return,
+            };
+            let recovered = recover(journal.as_ref(), &identity, initial, P::apply);
+            let (mut state, mut snapshot, mut sequence) = match recovered {
+                Ok(recovered) => recovered,
+                Err(err) => {
+                    context.set_exit_reason(IsolateExitReason::Failed(err.to_string()));
+                    return;
+                }
+            };
+            state.recovered(&context);
+
+            let mut events = Vec::new();
+            while let Ok(message) = context.recv() {
+                let started = Instant::now();
+                let flow = state.handle(&context, message, &mut events);
+                for event in events.drain(..) {
+                    match journal.append(&identity, &event.to_record()) {
+                        Ok(next) => sequence = next,
+                        Err(err) => {
+                            context.set_exit_reason(IsolateExitReason::Failed(err.to_string()));
+                            return;
+                        }
+                    }
+                    state.apply(&event);
+                }
+                if snapshot_every > 0 && sequence - snapshot >= snapshot_every {
+                    if let Err(err) = journal.save_snapshot(&identity, sequence, &state.to_record())
+                    {
+                        context.set_exit_reason(IsolateExitReason::Failed(err.to_string()));
+                        return;
+                    }
+                    snapshot = sequence;
+                }
+                context.observe(started.elapsed());
+                if flow == IsolateFlow::Halt {
+                    break;
+                }
+            }
+        })
+    }
+}
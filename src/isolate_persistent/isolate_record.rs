@@ -0,0 +1,58 @@
+/// IsolateRecord converts state and events to and from bytes, so they can be journaled
+pub trait IsolateRecord: Sized {
+    /// Encode this value
+    fn to_record(&self) -> Vec<u8>;
+
+    /// Decode a value, returning None if the bytes aren't a valid record
+    fn from_record(record: &[u8]) -> Option<Self>;
+}
+
+impl IsolateRecord for Vec<u8> {
+    fn to_record(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn from_record(record: &[u8]) -> Option<Self> {
+        Some(record.to_vec())
+    }
+}
+
+impl IsolateRecord for String {
+    fn to_record(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn from_record(record: &[u8]) -> Option<Self> {
+        String::from_utf8(record.to_vec()).ok()
+    }
+}
+
+impl IsolateRecord for i64 {
+    fn to_record(&self) -> Vec<u8> {
+        self.to_le_bytes().to_vec()
+    }
+
+    fn from_record(record: &[u8]) -> Option<Self> {
+        let mut bytes = [0; 8];
+        if record.len() != bytes.len() {
+            return None;
+        }
+        bytes.copy_from_slice(record);
+        Some(i64::from_le_bytes(bytes))
+    }
+}
+
+impl IsolateRecord for u64 {
+    fn to_record(&self) -> Vec<u8> {
+        self.to_le_bytes().to_vec()
+    }
+
+    fn from_record(record: &[u8]) -> Option<Self> {
+        let mut bytes = [0; 8];
+        if record.len() != bytes.len() {
+            return None;
+        }
+        bytes.copy_from_slice(record);
+        Some(u64::from_le_bytes(bytes))
+    }
+}
@@ -0,0 +1,152 @@
+pub(crate) mod isolate_pipeline_handle;
+pub(crate) mod isolate_pipeline_relay;
+pub(crate) mod isolate_pipeline_source;
+pub(crate) mod isolate_pipeline_stage;
+pub(crate) mod isolate_stream_event;
+
+use crate::isolate_pipeline::isolate_pipeline_relay::IsolatePipelineRelay;
+use crate::isolate_pipeline::isolate_pipeline_source::IsolatePipelineSource;
+use crate::isolate_pipeline::isolate_pipeline_stage::IsolatePipelineStage;
+use crate::isolate_pipeline::isolate_pipeline_stage::IsolatePipelineStep;
+use crate::Isolate;
+use crate::IsolateIdentity;
+use crate::IsolatePipelineHandle;
+use crate::IsolateRuntime;
+use crate::IsolateRuntimeError;
+use crate::IsolateStreamEvent;
+use crossbeam::{bounded, Sender};
+use std::mem;
+
+/// Starts the stages of a pipeline up to this one, given where the last of them sends items
+type IsolatePipelineStart<T> = Box<
+    dyn FnOnce(
+        &mut IsolatePipelineHandle,
+        Sender<IsolateStreamEvent<T>>,
+    ) -> Result<(), IsolateRuntimeError>,
+>;
+
+/// IsolatePipeline builds a stream pipeline from a source, through map, filter and batch
+/// stages, or workers of an isolate of your own, to a sink. Each stage is a worker in a runtime
+/// of its own, taking items from a bounded buffer; a stage only takes the next item once the
+/// stage after it has room, so a slow sink holds back the source. Nothing runs until the
+/// pipeline is given a sink.
+pub struct IsolatePipeline<T: Send + 'static> {
+    buffer: usize,
+    start: IsolatePipelineStart<T>,
+}
+
+impl<T: Send + 'static> IsolatePipeline<T> {
+    /// Start a pipeline with the items of an iterator, with buffers of 16 items between stages
+    pub fn source<I>(items: I) -> IsolatePipeline<T>
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: Send + 'static,
+    {
+        let items: Box<dyn Iterator<Item = T> + Send> = Box::new(items.into_iter());
+        IsolatePipeline {
+            buffer: 16,
+            start: Box::new(move |handle, output| {
+                let mut runtime = IsolateRuntime::new(IsolatePipelineSource::<T>::new());
+                let identity = IsolateIdentity::new();
+                runtime.spawn_as(identity, (items, output))?;
+                let source = runtime.as_ref();
+                handle.bind_source(Box::new(move || source.stop(&identity)));
+                handle.push_front(Box::new(runtime));
+                Ok(())
+            }),
+        }
+    }
+
+    /// Set how many items the buffers in front of the stages added after this hold
+    pub fn with_buffer(mut self, buffer: usize) -> IsolatePipeline<T> {
+        self.buffer = buffer.max(1);
+        self
+    }
+
+    /// Add a stage that turns each item into another
+    pub fn map<U: Send + 'static>(
+        self,
+        mut f: impl FnMut(T) -> U + Send + 'static,
+    ) -> IsolatePipeline<U> {
+        self.then(Box::new(move |item| item.map(&mut f)))
+    }
+
+    /// Add a stage that only passes on the items a predicate accepts
+    pub fn filter(self, mut f: impl FnMut(&T) -> bool + Send + 'static) -> IsolatePipeline<T> {
+        self.then(Box::new(move |item| item.filter(|item| f(item))))
+    }
+
+    /// Add a stage that gathers items into batches of a given size; the last batch may be
+    /// smaller
+    pub fn batch(self, size: usize) -> IsolatePipeline<Vec<T>> {
+        let size = size.max(1);
+        let mut batch = Vec::with_capacity(size);
+        self.then(Box::new(move |item| {
+            match item {
+                Some(item) => {
+                    batch.push(item);
+                    if batch.len() < size {
+                        return None;
+                    }
+                }
+                None if batch.is_empty() => return None,
+                None => {}
+            }
+            Some(mem::replace(&mut batch, Vec::with_capacity(size)))
+        }))
+    }
+
+    /// Add a stage that is a worker of an isolate, which receives the stream through its context
+    /// and sends on what it makes of it with `reply`. The worker is sent items while it has less
+    /// than a buffer's worth waiting; at the end of the stream it is sent End, and should pass it
+    /// on or exit.
+    pub fn through(
+        self,
+        isolate: impl Isolate<IsolateStreamEvent<T>, Args = ()> + Send + 'static,
+    ) -> IsolatePipeline<T> {
+        let IsolatePipeline { buffer, start } = self;
+        IsolatePipeline {
+            buffer,
+            start: Box::new(move |handle, output| {
+                let (sender, input) = bounded(buffer);
+                let mut workers = IsolateRuntime::new(isolate);
+                let worker = workers.spawn()?;
+                handle.push_front(Box::new(workers));
+                let mut runtime = IsolateRuntime::new(IsolatePipelineRelay {});
+                runtime.spawn_with((input, worker, output, buffer))?;
+                handle.push_front(Box::new(runtime));
+                start(handle, sender)
+            }),
+        }
+    }
+
+    /// End the pipeline with a stage that hands each item to a function, and start every stage
+    pub fn sink(
+        self,
+        mut f: impl FnMut(T) + Send + 'static,
+    ) -> Result<IsolatePipelineHandle, IsolateRuntimeError> {
+        let mut handle = IsolatePipelineHandle::new();
+        let (sender, input) = bounded(self.buffer);
+        let step: IsolatePipelineStep<T, ()> = Box::new(move |item| item.map(&mut f));
+        let mut runtime = IsolateRuntime::new(IsolatePipelineStage::<T, ()>::new());
+        runtime.spawn_with((input, None::<Sender<IsolateStreamEvent<()>>>, step))?;
+        handle.push_front(Box::new(runtime));
+        (self.start)(&mut handle, sender)?;
+        Ok(handle)
+    }
+
+    /// Add a stage running a step, taking items from a buffer in front of it
+    fn then<U: Send + 'static>(self, step: IsolatePipelineStep<T, U>) -> IsolatePipeline<U> {
+        let IsolatePipeline { buffer, start } = self;
+        IsolatePipeline {
+            buffer,
+            start: Box::new(move |handle, output| {
+                let (sender, input) = bounded(buffer);
+                let mut runtime = IsolateRuntime::new(IsolatePipelineStage::<T, U>::new());
+                runtime.spawn_with((input, Some(output), step))?;
+                handle.push_front(Box::new(runtime));
+                start(handle, sender)
+            }),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use crate::IsolateRuntimeError;
+use crate::IsolateRuntimeWait;
+use std::collections::VecDeque;
+
+/// Stops the source of a pipeline
+type IsolatePipelineStop = Box<dyn Fn() -> Result<bool, IsolateRuntimeError>>;
+
+/// IsolatePipelineHandle is a running pipeline; waiting on it waits for each stage to exit in
+/// turn, from the source to the sink.
+pub struct IsolatePipelineHandle {
+    runtimes: VecDeque<Box<dyn IsolateRuntimeWait>>,
+    source: Option<IsolatePipelineStop>,
+}
+
+impl IsolatePipelineHandle {
+    pub(crate) fn new() -> IsolatePipelineHandle {
+        IsolatePipelineHandle {
+            runtimes: VecDeque::new(),
+            source: None,
+        }
+    }
+
+    /// Add the runtime of a stage; stages are started from the sink back, so each goes in front
+    /// of those already started
+    pub(crate) fn push_front(&mut self, runtime: Box<dyn IsolateRuntimeWait>) {
+        self.runtimes.push_front(runtime);
+    }
+
+    pub(crate) fn bind_source(&mut self, stop: IsolatePipelineStop) {
+        self.source = Some(stop);
+    }
+
+    /// Stop the source, so the stream ends after the items already sent have passed through,
+    /// returning false if it had already stopped
+    pub fn stop(&self) -> Result<bool, IsolateRuntimeError> {
+        match self.source.as_ref() {
+            Some(stop) => stop(),
+            None => Ok(false),
+        }
+    }
+}
+
+impl IsolateRuntimeWait for IsolatePipelineHandle {
+    /// Wait for the stream to end, and every stage to exit
+    fn wait(&self) {
+        self.runtimes.iter().for_each(|runtime| runtime.wait());
+    }
+}
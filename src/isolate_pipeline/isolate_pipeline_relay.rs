@@ -0,0 +1,112 @@
+use crate::Isolate;
+use crate::IsolateChannel;
+use crate::IsolateContext;
+use crate::IsolateExitReason;
+use crate::IsolateStreamEvent;
+use crossbeam::{Receiver, Select, Sender};
+
+/// Where a relay takes items from, the worker it feeds them to, where it sends what the worker
+/// sends back, and how many items the worker may have waiting
+pub(crate) type IsolatePipelineRelayArgs<T> = (
+    Receiver<IsolateStreamEvent<T>>,
+    IsolateChannel<IsolateStreamEvent<T>>,
+    Sender<IsolateStreamEvent<T>>,
+    usize,
+);
+
+/// IsolatePipelineRelay runs a stage that is a worker of the user's own isolate: it feeds the
+/// worker items while it has fewer than a buffer's worth waiting, and passes on whatever the
+/// worker sends back, until the worker passes on the end of the stream or exits.
+pub(crate) struct IsolatePipelineRelay {}
+
+impl<T: Send + 'static> Isolate<IsolateStreamEvent<T>> for IsolatePipelineRelay {
+    type Args = IsolatePipelineRelayArgs<T>;
+
+    fn spawn(
+        &self,
+        context: IsolateContext<IsolateStreamEvent<T>>,
+        (input, worker, output, buffer): IsolatePipelineRelayArgs<T>,
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        let stopped = context.cancellation();
+        let mut ended = false;
+        Box::new(move || loop {
+            if !ended && context.is_stopped() {
+                ended = true;
+                let _ = worker.send(IsolateStreamEvent::End);
+            }
+
+            // Wait for the worker to send something back, or to take an item when its buffer
+            // is full, or for the next item when it isn't, or to be stopped
+            let mut select = Select::new();
+            select.recv(&worker.receiver);
+            let room = !ended && worker.queued() < buffer;
+            let items = match room {
+                true => select.recv(&input),
+                false => usize::MAX,
+            };
+            let taken = worker.taken().filter(|_| !ended && !room);
+            let took = match taken {
+                Some(taken) => select.recv(taken),
+                None => usize::MAX,
+            };
+            let stop = match ended {
+                false => select.recv(stopped.receiver()),
+                true => usize::MAX,
+            };
+            let operation = select.select();
+
+            if operation.index() == stop {
+                let _ = operation.recv(stopped.receiver());
+                continue;
+            }
+            if let Some(taken) = taken.filter(|_| operation.index() == took) {
+                let _ = operation.recv(taken);
+                continue;
+            }
+            if operation.index() == items {
+                let event = match operation.recv(&input) {
+                    Ok(event) => event,
+                    Err(_) => {
+                        context.set_exit_reason(IsolateExitReason::Failed(
+                            "The stage before exited before the end of the stream".to_string(),
+                        ));
+                        IsolateStreamEvent::End
+                    }
+                };
+                ended = matches!(event, IsolateStreamEvent::End);
+                if worker.send(event).is_err() {
+                    context.set_exit_reason(IsolateExitReason::Failed(
+                        "The worker exited before the end of the stream".to_string(),
+                    ));
+                    let _ = output.send(IsolateStreamEvent::End);
+                    return;
+                }
+                continue;
+            }
+
+            match operation.recv(&worker.receiver) {
+                Ok(IsolateStreamEvent::Item(item)) => {
+                    if output.send(IsolateStreamEvent::Item(item)).is_err() {
+                        context.set_exit_reason(IsolateExitReason::Failed(
+                            "The next stage exited before the end of the stream".to_string(),
+                        ));
+                        return;
+                    }
+                }
+                Ok(IsolateStreamEvent::End) => {
+                    let _ = output.send(IsolateStreamEvent::End);
+                    return;
+                }
+                Err(_) => {
+                    if !ended {
+                        context.set_exit_reason(IsolateExitReason::Failed(
+                            "The worker exited before the end of the stream".to_string(),
+                        ));
+                    }
+                    let _ = output.send(IsolateStreamEvent::End);
+                    return;
+                }
+            }
+        })
+    }
+}
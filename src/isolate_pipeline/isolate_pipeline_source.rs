@@ -0,0 +1,49 @@
+use crate::Isolate;
+use crate::IsolateContext;
+use crate::IsolateExitReason;
+use crate::IsolateStreamEvent;
+use crossbeam::Sender;
+use std::marker::PhantomData;
+
+/// The items a source worker sends, and where it sends them
+pub(crate) type IsolatePipelineSourceArgs<T> = (
+    Box<dyn Iterator<Item = T> + Send>,
+    Sender<IsolateStreamEvent<T>>,
+);
+
+/// IsolatePipelineSource is the first stage of a pipeline; it sends items as the next stage
+/// has room for them, ending the stream when it runs out or is stopped.
+pub(crate) struct IsolatePipelineSource<T> {
+    items: PhantomData<fn() -> T>,
+}
+
+impl<T> IsolatePipelineSource<T> {
+    pub fn new() -> IsolatePipelineSource<T> {
+        IsolatePipelineSource { items: PhantomData }
+    }
+}
+
+impl<T: Send + 'static> Isolate<IsolateStreamEvent<T>> for IsolatePipelineSource<T> {
+    type Args = IsolatePipelineSourceArgs<T>;
+
+    fn spawn(
+        &self,
+        context: IsolateContext<IsolateStreamEvent<T>>,
+        (mut items, output): IsolatePipelineSourceArgs<T>,
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        Box::new(move || {
+            for item in items.by_ref() {
+                if context.is_stopped() {
+                    break;
+                }
+                if output.send(IsolateStreamEvent::Item(item)).is_err() {
+                    context.set_exit_reason(IsolateExitReason::Failed(
+                        "The next stage exited before the end of the stream".to_string(),
+                    ));
+                    return;
+                }
+            }
+            let _ = output.send(IsolateStreamEvent::End);
+        })
+    }
+}
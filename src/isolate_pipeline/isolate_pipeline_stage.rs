@@ -0,0 +1,103 @@
+use crate::Isolate;
+use crate::IsolateContext;
+use crate::IsolateContextError;
+use crate::IsolateExitReason;
+use crate::IsolateSelected;
+use crate::IsolateStreamEvent;
+use crossbeam::{Receiver, Select, Sender};
+use std::marker::PhantomData;
+
+/// Turns an item into the item to pass on, if any; it is given None at the end of the stream,
+/// to pass on anything it held back
+pub(crate) type IsolatePipelineStep<I, O> = Box<dyn FnMut(Option<I>) -> Option<O> + Send>;
+
+/// Where a stage worker takes items from, where it sends them, and what it does to them; a
+/// sink has nowhere to send them
+pub(crate) type IsolatePipelineStageArgs<I, O> = (
+    Receiver<IsolateStreamEvent<I>>,
+    Option<Sender<IsolateStreamEvent<O>>>,
+    IsolatePipelineStep<I, O>,
+);
+
+/// IsolatePipelineStage takes items from the stage before it and passes on what its step makes
+/// of them, until the stream ends or it is stopped.
+pub(crate) struct IsolatePipelineStage<I, O> {
+    items: PhantomData<fn(I) -> O>,
+}
+
+impl<I, O> IsolatePipelineStage<I, O> {
+    pub fn new() -> IsolatePipelineStage<I, O> {
+        IsolatePipelineStage { items: PhantomData }
+    }
+}
+
+impl<I: Send + 'static, O: Send + 'static> Isolate<IsolateStreamEvent<I>>
+    for IsolatePipelineStage<I, O>
+{
+    type Args = IsolatePipelineStageArgs<I, O>;
+
+    fn spawn(
+        &self,
+        context: IsolateContext<IsolateStreamEvent<I>>,
+        (input, output, mut step): IsolatePipelineStageArgs<I, O>,
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        let stopped = context.cancellation();
+        let mut halted = false;
+        Box::new(move || loop {
+            // Waiting on a pipeline halts each stage's runtime in turn, closing the worker's own
+            // channel; the stream still runs to its end through the buffer in front of it, unless
+            // the worker is stopped
+            let selected = match halted {
+                false => context.select().recv(&input, |event| event).wait(),
+                true => {
+                    let mut select = Select::new();
+                    let items = select.recv(&input);
+                    select.recv(stopped.receiver());
+                    let operation = select.select();
+                    match operation.index() == items {
+                        true => Ok(match operation.recv(&input) {
+                            Ok(event) => IsolateSelected::Channel(event),
+                            Err(_) => IsolateSelected::Closed(0),
+                        }),
+                        false => {
+                            let _ = operation.recv(stopped.receiver());
+                            Err(IsolateContextError::Stopped)
+                        }
+                    }
+                }
+            };
+            let event = match selected {
+                Ok(IsolateSelected::Message(event)) | Ok(IsolateSelected::Channel(event)) => event,
+                Ok(IsolateSelected::Closed(_)) => {
+                    context.set_exit_reason(IsolateExitReason::Failed(
+                        "The stage before exited before the end of the stream".to_string(),
+                    ));
+                    IsolateStreamEvent::End
+                }
+                Err(IsolateContextError::Disconnected) => {
+                    halted = true;
+                    continue;
+                }
+                Err(_) => IsolateStreamEvent::End,
+            };
+            let (item, end) = match event {
+                IsolateStreamEvent::Item(item) => (Some(item), false),
+                IsolateStreamEvent::End => (None, true),
+            };
+            if let (Some(item), Some(output)) = (step(item), output.as_ref()) {
+                if output.send(IsolateStreamEvent::Item(item)).is_err() {
+                    context.set_exit_reason(IsolateExitReason::Failed(
+                        "The next stage exited before the end of the stream".to_string(),
+                    ));
+                    return;
+                }
+            }
+            if end {
+                if let Some(output) = output.as_ref() {
+                    let _ = output.send(IsolateStreamEvent::End);
+                }
+                return;
+            }
+        })
+    }
+}
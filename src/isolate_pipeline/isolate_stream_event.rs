@@ -0,0 +1,8 @@
+/// What passes between the stages of a pipeline
+#[derive(Debug, Clone, PartialEq)]
+pub enum IsolateStreamEvent<T> {
+    /// The next item of the stream
+    Item(T),
+    /// The stream has ended; a stage passes this on after anything it held back, then exits
+    End,
+}
@@ -6,7 +6,10 @@ use self::isolate_registry_shared::IsolateRegistryShared;
 use crate::isolate_registry::isolate_registry_error::IsolateRegistryError;
 use crate::isolate_registry::isolate_registry_ref::IsolateRegistryRef;
 use crate::Isolate;
+use crate::IsolateDeadLetters;
 use crate::IsolateRuntimeRef;
+use crate::IsolateTree;
+use crate::IsolateMetrics;
 use std::sync::Arc;
 use std::sync::Mutex;
 
@@ -17,6 +20,7 @@ pub struct IsolateRegistry {
     shared: Arc<Mutex<IsolateRegistryShared>>,
 }
 
+#[allow(clippy::new_without_default)]
 impl IsolateRegistry {
     pub fn new() -> IsolateRegistry {
         IsolateRegistry {
@@ -41,6 +45,19 @@ impl IsolateRegistry {
         }
     }
 
+    /// Unbind a runtime from this registry, tripping the cancellation token of each of its
+    /// workers and waiting for them to exit; the name can then be bound again
+    pub fn unbind(&mut self, identity: &str) -> Result<(), IsolateRegistryError> {
+        // Workers may use the registry as they halt, so don't hold the registry lock while waiting
+        let (cancel, wait) = match self.shared.lock() {
+            Ok(mut shared) => shared.unbind(identity)?,
+            Err(_) => return Err(IsolateRegistryError::InternalSyncError),
+        };
+        cancel();
+        wait.wait();
+        Ok(())
+    }
+
     /// Find a specific runtime by name and type.
     /// Even if the name matches, if the downcast type ref is wrong, it'll return an error.
     pub fn find<T: Send + 'static>(
@@ -53,13 +70,51 @@ impl IsolateRegistry {
         }
     }
 
-    /// Wait for all runtimes to halt
-    pub fn wait(self) {
+    /// Return the dead letter sink for undeliverable messages to workers in this registry
+    pub fn dead_letters(&self) -> Result<IsolateDeadLetters, IsolateRegistryError> {
+        match self.shared.lock() {
+            Ok(shared) => Ok(shared.dead_letters()),
+            Err(_) => Err(IsolateRegistryError::InternalSyncError),
+        }
+    }
+
+    /// Return the tree of workers across every runtime in this registry
+    pub fn tree(&self) -> Result<IsolateTree, IsolateRegistryError> {
         match self.shared.lock() {
-            Ok(shared) => shared.wait(),
-            Err(_) => {}
+            Ok(shared) => Ok(shared.tree()),
+            Err(_) => Err(IsolateRegistryError::InternalSyncError),
         }
     }
+
+    /// Take a snapshot of the metrics of every runtime in this registry
+    pub fn metrics(&self) -> Result<IsolateMetrics, IsolateRegistryError> {
+        let sources = match self.shared.lock() {
+            Ok(shared) => shared.metrics_sources(),
+            Err(_) => return Err(IsolateRegistryError::InternalSyncError),
+        };
+        let runtimes = sources.iter().filter_map(|source| source.metrics()).collect();
+        Ok(IsolateMetrics { runtimes })
+    }
+
+    /// Trip the cancellation token of every worker in every runtime, then wait for all runtimes
+    /// to halt
+    pub fn shutdown(self) {
+        let cancels = match self.shared.lock() {
+            Ok(shared) => shared.cancel_handles(),
+            Err(_) => return,
+        };
+        cancels.iter().for_each(|cancel| cancel());
+        self.wait();
+    }
+
+    /// Wait for all runtimes to halt
+    pub fn wait(self) {
+        let handles = match self.shared.lock() {
+            Ok(shared) => shared.wait_handles(),
+            Err(_) => return,
+        };
+        handles.iter().for_each(|handle| handle.wait());
+    }
 }
 
 #[cfg(test)]
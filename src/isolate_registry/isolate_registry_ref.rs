@@ -2,7 +2,10 @@ use std::sync::Arc;
 use std::sync::Mutex;
 use crate::isolate_registry::isolate_registry_shared::IsolateRegistryShared;
 use crate::isolate_registry::isolate_registry_error::IsolateRegistryError;
+use crate::IsolateDeadLetters;
 use crate::IsolateRuntimeRef;
+use crate::IsolateTree;
+use crate::IsolateMetrics;
 
 #[derive(Clone)]
 pub struct IsolateRegistryRef {
@@ -23,4 +26,30 @@ impl IsolateRegistryRef {
             Err(_) => Err(IsolateRegistryError::InternalSyncError)
         }
     }
+
+    /// Return the dead letter sink for undeliverable messages to workers in this registry
+    pub fn dead_letters(&self) -> Result<IsolateDeadLetters, IsolateRegistryError> {
+        match self.shared.lock() {
+            Ok(shared) => Ok(shared.dead_letters()),
+            Err(_) => Err(IsolateRegistryError::InternalSyncError)
+        }
+    }
+
+    /// Return the tree of workers across every runtime in this registry
+    pub fn tree(&self) -> Result<IsolateTree, IsolateRegistryError> {
+        match self.shared.lock() {
+            Ok(shared) => Ok(shared.tree()),
+            Err(_) => Err(IsolateRegistryError::InternalSyncError)
+        }
+    }
+
+    /// Take a snapshot of the metrics of every runtime in this registry
+    pub fn metrics(&self) -> Result<IsolateMetrics, IsolateRegistryError> {
+        let sources = match self.shared.lock() {
+            Ok(shared) => shared.metrics_sources(),
+            Err(_) => return Err(IsolateRegistryError::InternalSyncError)
+        };
+        let runtimes = sources.iter().filter_map(|source| source.metrics()).collect();
+        Ok(IsolateMetrics { runtimes })
+    }
 }
@@ -1,5 +1,8 @@
+#![allow(clippy::needless_return)]
+
 use std::sync::Mutex;
 use std::sync::Arc;
+use std::sync::Weak;
 use crate::isolate_registry::isolate_registry_error::IsolateRegistryError;
 use crate::IsolateRuntimeRef;
 
@@ -8,16 +11,53 @@ use std::collections::HashMap;
 use crate::IsolateRuntime;
 use std::any::Any;
 use crate::isolate_runtime::isolate_runtime_wait::IsolateRuntimeWait;
+use crate::isolate_dead_letters::isolate_dead_letter_route::IsolateDeadLetterRoute;
+use crate::IsolateDeadLetters;
+use crate::IsolateTree;
+use crate::isolate_metrics::isolate_metrics_source::IsolateMetricsSource;
+use crate::isolate_timer::IsolateTimer;
+
+/// Trips the cancellation token of every worker of a runtime
+pub type IsolateRegistryCancel = Arc<dyn Fn() + Send + Sync + 'static>;
+
+/// A runtime bound to the registry; the runtime is kept both as a reference of its concrete
+/// type, to find it by type, and as handles to wait on, cancel and take metrics from.
+struct IsolateRegistryEntry {
+    runtime: Box<dyn Any + Send + 'static>,
+    wait: Arc<dyn IsolateRuntimeWait + Send + Sync + 'static>,
+    cancel: IsolateRegistryCancel,
+    metrics: Arc<dyn IsolateMetricsSource + Send + Sync + 'static>,
+}
 
 pub struct IsolateRegistryShared {
-    registry: HashMap<String, Box<dyn Any + Send + 'static>>
+    registry: HashMap<String, IsolateRegistryEntry>,
+    dead_letters: IsolateDeadLetters,
+    tree: IsolateTree,
+    timer: IsolateTimer,
+    this: Weak<Mutex<IsolateRegistryShared>>,
 }
 
 impl IsolateRegistryShared {
     pub fn new() -> Arc<Mutex<IsolateRegistryShared>> {
-        return Arc::new(Mutex::new(IsolateRegistryShared {
-            registry: HashMap::new()
-        }));
+        return Arc::new_cyclic(|this| {
+            Mutex::new(IsolateRegistryShared {
+                registry: HashMap::new(),
+                dead_letters: IsolateDeadLetters::new(),
+                tree: IsolateTree::new(),
+                timer: IsolateTimer::new(),
+                this: this.clone(),
+            })
+        });
+    }
+
+    /// Return the dead letter sink shared by every runtime in this registry
+    pub fn dead_letters(&self) -> IsolateDeadLetters {
+        self.dead_letters.clone()
+    }
+
+    /// Return the tree of workers across every runtime in this registry
+    pub fn tree(&self) -> IsolateTree {
+        self.tree.clone()
     }
 
     /// Bind a reference identity to a runtime instance.
@@ -30,10 +70,19 @@ impl IsolateRegistryShared {
 
         // Create a new runtime for this isolate
         let runtime = IsolateRuntime::new(isolate);
+        runtime.bind_dead_letters(IsolateDeadLetterRoute::new(identity, self.dead_letters.clone()));
+        runtime.bind_registry((identity.to_string(), self.this.clone()), self.tree.clone());
+        runtime.bind_timer(self.timer.clone());
         let runtime_ref = runtime.as_ref();
+        let cancel_ref = runtime_ref.clone();
 
         // Attach to the registry
-        self.registry.insert(identity.to_string(), Box::new(runtime) as Box<dyn Any + Send + 'static>);
+        self.registry.insert(identity.to_string(), IsolateRegistryEntry {
+            runtime: Box::new(runtime_ref.clone()),
+            wait: Arc::new(runtime_ref.clone()),
+            cancel: Arc::new(move || cancel_ref.cancel_all()),
+            metrics: Arc::new(runtime_ref.clone()),
+        });
         return Ok(runtime_ref);
     }
 
@@ -41,9 +90,9 @@ impl IsolateRegistryShared {
     /// Even if the name matches, if the downcast type ref is wrong, it'll return an error.
     pub fn find<T: Send + 'static>(&self, identity: &str) -> Result<IsolateRuntimeRef<T>, IsolateRegistryError> {
         match self.registry.get(identity) {
-            Some(runtime_any) => {
-                match runtime_any.downcast_ref::<IsolateRuntime<T>>() {
-                    Some(runtime) => Ok(runtime.as_ref()),
+            Some(entry) => {
+                match entry.runtime.downcast_ref::<IsolateRuntimeRef<T>>() {
+                    Some(runtime) => Ok(runtime.clone()),
                     None => Err(IsolateRegistryError::InvalidRuntimeType)
                 }
             }
@@ -51,13 +100,32 @@ impl IsolateRegistryShared {
         }
     }
 
-    /// Wait for all runtimes to halt
-    pub fn wait(&self) {
-        self.registry.iter().for_each(|(_, i)| {
-            match i.downcast_ref::<&dyn IsolateRuntimeWait>() {
-                Some(handle) => { handle.wait(); }
-                None => {}
-            }
-        })
+    /// Return a handle to take metrics from for each runtime, ordered by name.
+    /// Workers may hold their runtime lock while using the registry, so take the snapshots
+    /// after the registry lock is released.
+    pub fn metrics_sources(&self) -> Vec<Arc<dyn IsolateMetricsSource + Send + Sync + 'static>> {
+        let mut names: Vec<_> = self.registry.keys().collect();
+        names.sort();
+        names.into_iter().map(|name| self.registry[name].metrics.clone()).collect()
+    }
+
+    /// Remove a runtime from the registry by name, returning handles to cancel its workers and
+    /// wait on them.
+    pub fn unbind(&mut self, identity: &str) -> Result<(IsolateRegistryCancel, Arc<dyn IsolateRuntimeWait + Send + Sync + 'static>), IsolateRegistryError> {
+        match self.registry.remove(identity) {
+            Some(entry) => Ok((entry.cancel, entry.wait)),
+            None => Err(IsolateRegistryError::NoMatchingIdentity)
+        }
+    }
+
+    /// Return a handle to cancel the workers of each runtime
+    pub fn cancel_handles(&self) -> Vec<IsolateRegistryCancel> {
+        self.registry.values().map(|entry| entry.cancel.clone()).collect()
+    }
+
+    /// Return a handle to wait on for each runtime.
+    /// Workers may use the registry as they halt, so don't hold the registry lock while waiting.
+    pub fn wait_handles(&self) -> Vec<Arc<dyn IsolateRuntimeWait + Send + Sync + 'static>> {
+        self.registry.values().map(|entry| entry.wait.clone()).collect()
     }
 }
\ No newline at end of file
@@ -0,0 +1,286 @@
+pub(crate) mod isolate_router_dispatch;
+pub(crate) mod isolate_router_error;
+pub(crate) mod isolate_router_strategy;
+
+use crate::isolate_router::isolate_router_dispatch::IsolateRouterControl;
+use crate::isolate_router::isolate_router_dispatch::IsolateRouterDispatch;
+use crate::IsolateChannel;
+use crate::IsolateIdentity;
+use crate::IsolateRouterError;
+use crate::IsolateRouterStrategy;
+use crate::IsolateRuntimeRef;
+use crossbeam::{unbounded, Sender};
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+
+/// IsolateRouter is a facade over a pool of workers in a runtime; messages sent to the router
+/// are dispatched to one (or all) of the workers by its strategy, and anything the workers send
+/// back is merged into the router channel.
+/// The pool is released when the router and every channel to it have been dropped.
+pub struct IsolateRouter<T: Send + 'static> {
+    runtime: IsolateRuntimeRef<T>,
+    members: Arc<Mutex<Vec<IsolateIdentity>>>,
+    control: Sender<IsolateRouterControl<T>>,
+    channel: IsolateChannel<T>,
+}
+
+impl<T: Send + 'static> IsolateRouter<T> {
+    /// Create a new router with a pool of `size` workers spawned from the runtime.
+    /// The isolate for the runtime must not require any spawn-time args.
+    pub fn new(
+        runtime: IsolateRuntimeRef<T>,
+        strategy: IsolateRouterStrategy<T>,
+        size: usize,
+    ) -> Result<IsolateRouter<T>, IsolateRouterError> {
+        let (control_s, control_r) = unbounded();
+        let (input_s, input_r) = unbounded();
+        let (output_s, output_r) = unbounded();
+        let members = Arc::new(Mutex::new(Vec::new()));
+
+        let mut dispatch = IsolateRouterDispatch::new(
+            runtime.clone(),
+            strategy,
+            members.clone(),
+            control_r,
+            input_r,
+            output_s,
+        );
+        thread::spawn(move || {
+            dispatch.run();
+        });
+
+        let mut router = IsolateRouter {
+            runtime,
+            members,
+            control: control_s,
+            channel: IsolateChannel::from(input_s, output_r),
+        };
+        router.resize(size)?;
+        Ok(router)
+    }
+
+    /// Return the single sender that dispatches messages to the pool
+    pub fn sender(&self) -> Sender<T> {
+        self.channel.sender.clone()
+    }
+
+    /// Return a channel to the router; the receiver gets output from every worker in the pool
+    pub fn channel(&self) -> IsolateChannel<T> {
+        self.channel.clone()
+    }
+
+    /// Return the identities of the workers currently in the pool
+    pub fn workers(&self) -> Vec<IsolateIdentity> {
+        match self.members.lock() {
+            Ok(members) => members.clone(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Grow or shrink the pool to `size` workers.
+    /// Workers removed from the pool are released from the runtime and will see their channel
+    /// close once any messages already dispatched to them have been handled; what they send back
+    /// for those messages still reaches the router channel.
+    pub fn resize(&mut self, size: usize) -> Result<(), IsolateRouterError> {
+        let mut members = match self.members.lock() {
+            Ok(members) => members,
+            Err(_) => return Err(IsolateRouterError::InternalSyncError),
+        };
+
+        while members.len() < size {
+            let (identity, channel) = self.runtime.spawn_worker(())?;
+            members.push(identity);
+            self.control
+                .send(IsolateRouterControl::Add(identity, Box::new(channel)))
+                .map_err(|_| IsolateRouterError::RouterHalted)?;
+        }
+
+        while members.len() > size {
+            if let Some(identity) = members.pop() {
+                self.control
+                    .send(IsolateRouterControl::Remove(identity))
+                    .map_err(|_| IsolateRouterError::RouterHalted)?;
+                self.runtime.release(&identity)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IsolateRouter;
+    use crate::Isolate;
+    use crate::IsolateContext;
+    use crate::IsolateIdentity;
+    use crate::IsolateRouterStrategy;
+    use crate::IsolateRuntime;
+    use std::collections::HashSet;
+    use std::thread;
+    use std::time::Duration;
+
+    struct TestIsolate {}
+
+    #[derive(Clone, Debug)]
+    enum TestIsolateEvent {
+        Who(usize),
+        SlowWho,
+        Identity(IsolateIdentity),
+    }
+
+    impl Isolate<TestIsolateEvent> for TestIsolate {
+        type Args = ();
+
+        fn spawn(
+            &self,
+            context: IsolateContext<TestIsolateEvent>,
+            _: (),
+        ) -> Box<dyn FnMut() + Send + 'static> {
+            let identity = context.identity();
+            let channel = context.channel().clone();
+            Box::new(move || {
+                while let Ok(event) = channel.receiver.recv() {
+                    match event {
+                        TestIsolateEvent::Who(_) => {}
+                        TestIsolateEvent::SlowWho => thread::sleep(Duration::from_millis(20)),
+                        TestIsolateEvent::Identity(_) => break,
+                    }
+                    let _ = channel.sender.send(TestIsolateEvent::Identity(identity));
+                }
+            })
+        }
+    }
+
+    fn collect_identities(
+        router: &IsolateRouter<TestIsolateEvent>,
+        keys: &[usize],
+    ) -> Vec<IsolateIdentity> {
+        let channel = router.channel();
+        keys.iter()
+            .for_each(|k| channel.sender.send(TestIsolateEvent::Who(*k)).unwrap());
+        keys.iter()
+            .map(|_| match channel.receiver.recv().unwrap() {
+                TestIsolateEvent::Identity(id) => id,
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    pub fn test_round_robin() {
+        let runtime = IsolateRuntime::new(TestIsolate {});
+        let router =
+            IsolateRouter::new(runtime.as_ref(), IsolateRouterStrategy::RoundRobin, 3).unwrap();
+
+        // The first message goes to the first worker
+        let workers = router.workers();
+        assert_eq!(collect_identities(&router, &[0]), vec![workers[0]]);
+
+        let identities = collect_identities(&router, &[0, 1, 2, 3, 4, 5]);
+        let unique = identities.iter().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), 3);
+        workers
+            .iter()
+            .for_each(|w| assert_eq!(identities.iter().filter(|i| *i == w).count(), 2));
+    }
+
+    #[test]
+    pub fn test_random_and_least_loaded() {
+        let runtime = IsolateRuntime::new(TestIsolate {});
+        let random =
+            IsolateRouter::new(runtime.as_ref(), IsolateRouterStrategy::Random, 2).unwrap();
+        let least =
+            IsolateRouter::new(runtime.as_ref(), IsolateRouterStrategy::LeastLoaded, 2).unwrap();
+
+        let workers = random.workers();
+        collect_identities(&random, &[0, 1, 2, 3])
+            .iter()
+            .for_each(|i| assert!(workers.contains(i)));
+
+        let workers = least.workers();
+        collect_identities(&least, &[0, 1, 2, 3])
+            .iter()
+            .for_each(|i| assert!(workers.contains(i)));
+    }
+
+    #[test]
+    pub fn test_consistent_hash() {
+        let runtime = IsolateRuntime::new(TestIsolate {});
+        let router = IsolateRouter::new(
+            runtime.as_ref(),
+            IsolateRouterStrategy::consistent_hash(|event| match event {
+                TestIsolateEvent::Who(key) => *key,
+                _ => 0,
+            }),
+            4,
+        )
+        .unwrap();
+
+        let first = collect_identities(&router, &[7, 7, 7]);
+        assert!(first.iter().all(|i| *i == first[0]));
+
+        let second = collect_identities(&router, &[7]);
+        assert_eq!(first[0], second[0]);
+    }
+
+    #[test]
+    pub fn test_broadcast() {
+        let runtime = IsolateRuntime::new(TestIsolate {});
+        let router =
+            IsolateRouter::new(runtime.as_ref(), IsolateRouterStrategy::broadcast(), 3).unwrap();
+
+        let channel = router.channel();
+        channel.sender.send(TestIsolateEvent::Who(0)).unwrap();
+        let responses = (0..3)
+            .map(|_| match channel.receiver.recv().unwrap() {
+                TestIsolateEvent::Identity(id) => id,
+                _ => unreachable!(),
+            })
+            .collect::<HashSet<IsolateIdentity>>();
+        assert_eq!(responses.len(), 3);
+    }
+
+    #[test]
+    pub fn test_resize() {
+        let runtime = IsolateRuntime::new(TestIsolate {});
+        let mut router =
+            IsolateRouter::new(runtime.as_ref(), IsolateRouterStrategy::RoundRobin, 1).unwrap();
+        assert_eq!(router.workers().len(), 1);
+
+        router.resize(4).unwrap();
+        assert_eq!(router.workers().len(), 4);
+        let identities = collect_identities(&router, &[0, 1, 2, 3]);
+        assert_eq!(identities.iter().collect::<HashSet<_>>().len(), 4);
+
+        router.resize(2).unwrap();
+        let workers = router.workers();
+        assert_eq!(workers.len(), 2);
+        identities
+            .iter()
+            .filter(|i| !workers.contains(i))
+            .for_each(|i| assert!(runtime.as_ref().find(i).is_none()));
+        collect_identities(&router, &[0, 1, 2, 3])
+            .iter()
+            .for_each(|i| assert!(workers.contains(i)));
+    }
+
+    #[test]
+    pub fn test_shrink_keeps_replies() {
+        let runtime = IsolateRuntime::new(TestIsolate {});
+        let mut router =
+            IsolateRouter::new(runtime.as_ref(), IsolateRouterStrategy::RoundRobin, 4).unwrap();
+
+        // Workers removed while they still have requests to answer pass their replies on
+        let channel = router.channel();
+        (0..12).for_each(|_| channel.sender.send(TestIsolateEvent::SlowWho).unwrap());
+        thread::sleep(Duration::from_millis(10));
+        router.resize(1).unwrap();
+        let replies = (0..12)
+            .map(|_| channel.receiver.recv_timeout(Duration::from_secs(5)))
+            .filter(|reply| reply.is_ok())
+            .count();
+        assert_eq!(replies, 12);
+    }
+}
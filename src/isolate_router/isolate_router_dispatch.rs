@@ -0,0 +1,253 @@
+use crate::isolate_router::isolate_router_strategy::hash_of;
+use crate::IsolateChannel;
+use crate::IsolateIdentity;
+use crate::IsolateRouterStrategy;
+use crate::IsolateRuntimeRef;
+use crossbeam::{Receiver, Select, Sender};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// The number of points each worker owns on the consistent hash ring
+const RING_REPLICAS: usize = 32;
+
+/// Control events sent from the router handle to its dispatch thread
+pub enum IsolateRouterControl<T: Send + 'static> {
+    Add(IsolateIdentity, Box<IsolateChannel<T>>),
+    Remove(IsolateIdentity),
+}
+
+/// The outcome of waiting on the dispatch inputs
+enum IsolateRouterEvent<T: Send + 'static> {
+    Control(IsolateRouterControl<T>),
+    ControlClosed,
+    Input(T),
+    InputClosed,
+    Output(T),
+    WorkerClosed(usize),
+    DrainedClosed(usize),
+}
+
+/// IsolateRouterDispatch runs in its own thread and owns the worker pool of a router; it moves
+/// inbound messages to workers, and worker output back to the router channel. Workers removed
+/// from the pool are sent nothing more, but their output is passed on until they exit.
+pub struct IsolateRouterDispatch<T: Send + 'static> {
+    runtime: IsolateRuntimeRef<T>,
+    strategy: IsolateRouterStrategy<T>,
+    members: Arc<Mutex<Vec<IsolateIdentity>>>,
+    workers: Vec<(IsolateIdentity, IsolateChannel<T>)>,
+    draining: Vec<Receiver<T>>,
+    ring: BTreeMap<u64, IsolateIdentity>,
+    control: Receiver<IsolateRouterControl<T>>,
+    input: Receiver<T>,
+    output: Sender<T>,
+    next: usize,
+    seed: u64,
+}
+
+impl<T: Send + 'static> IsolateRouterDispatch<T> {
+    pub fn new(
+        runtime: IsolateRuntimeRef<T>,
+        strategy: IsolateRouterStrategy<T>,
+        members: Arc<Mutex<Vec<IsolateIdentity>>>,
+        control: Receiver<IsolateRouterControl<T>>,
+        input: Receiver<T>,
+        output: Sender<T>,
+    ) -> IsolateRouterDispatch<T> {
+        IsolateRouterDispatch {
+            runtime,
+            strategy,
+            members,
+            workers: Vec::new(),
+            draining: Vec::new(),
+            ring: BTreeMap::new(),
+            control,
+            input,
+            output,
+            next: 0,
+            seed: Uuid::new_v4().as_u128() as u64 | 1,
+        }
+    }
+
+    /// Dispatch messages until every sender to the router has been dropped
+    pub fn run(&mut self) {
+        let mut control_open = true;
+        loop {
+            match self.next_event(control_open) {
+                IsolateRouterEvent::Control(control) => self.apply(control),
+                IsolateRouterEvent::ControlClosed => control_open = false,
+                IsolateRouterEvent::Input(message) => self.route(message),
+                IsolateRouterEvent::InputClosed => break,
+                IsolateRouterEvent::Output(message) => {
+                    let _ = self.output.send(message);
+                }
+                IsolateRouterEvent::WorkerClosed(offset) => {
+                    let identity = self.workers[offset].0;
+                    self.remove(&identity);
+                }
+                IsolateRouterEvent::DrainedClosed(offset) => {
+                    self.draining.remove(offset);
+                }
+            }
+        }
+
+        // Release the pool so the workers see their channels close
+        for (identity, _) in self.workers.drain(..) {
+            let _ = self.runtime.release(&identity);
+        }
+    }
+
+    fn next_event(&self, control_open: bool) -> IsolateRouterEvent<T> {
+        // Select picks at random between ready operations, so pool changes are taken first to
+        // make sure messages sent after a resize see the new pool.
+        if control_open {
+            if let Ok(control) = self.control.try_recv() {
+                return IsolateRouterEvent::Control(control);
+            }
+        }
+
+        let mut select = Select::new();
+        let control_index = if control_open {
+            select.recv(&self.control)
+        } else {
+            usize::MAX
+        };
+        let input_index = select.recv(&self.input);
+        let worker_indexes = self
+            .workers
+            .iter()
+            .map(|(_, channel)| select.recv(&channel.receiver))
+            .collect::<Vec<usize>>();
+        let draining_indexes = self
+            .draining
+            .iter()
+            .map(|receiver| select.recv(receiver))
+            .collect::<Vec<usize>>();
+
+        let operation = select.select();
+        let index = operation.index();
+        if index == control_index {
+            match operation.recv(&self.control) {
+                Ok(control) => IsolateRouterEvent::Control(control),
+                Err(_) => IsolateRouterEvent::ControlClosed,
+            }
+        } else if index == input_index {
+            match operation.recv(&self.input) {
+                Ok(message) => IsolateRouterEvent::Input(message),
+                Err(_) => IsolateRouterEvent::InputClosed,
+            }
+        } else if let Some(offset) = worker_indexes.iter().position(|i| *i == index) {
+            match operation.recv(&self.workers[offset].1.receiver) {
+                Ok(message) => IsolateRouterEvent::Output(message),
+                Err(_) => IsolateRouterEvent::WorkerClosed(offset),
+            }
+        } else {
+            let offset = draining_indexes.iter().position(|i| *i == index).unwrap();
+            match operation.recv(&self.draining[offset]) {
+                Ok(message) => IsolateRouterEvent::Output(message),
+                Err(_) => IsolateRouterEvent::DrainedClosed(offset),
+            }
+        }
+    }
+
+    fn apply(&mut self, control: IsolateRouterControl<T>) {
+        match control {
+            IsolateRouterControl::Add(identity, channel) => {
+                for replica in 0..RING_REPLICAS {
+                    self.ring.insert(hash_of(&(identity, replica)), identity);
+                }
+                self.workers.push((identity, *channel));
+            }
+            IsolateRouterControl::Remove(identity) => {
+                // Keep passing on what the worker sends back for messages it was already sent;
+                // dropping its sender lets it see its channel close once it has handled them
+                if let Some((_, channel)) = self.workers.iter().find(|(i, _)| *i == identity) {
+                    self.draining.push(channel.receiver.clone());
+                }
+                self.remove(&identity);
+            }
+        }
+    }
+
+    fn remove(&mut self, identity: &IsolateIdentity) {
+        self.workers.retain(|(i, _)| i != identity);
+        self.ring.retain(|_, i| i != identity);
+        if let Ok(mut members) = self.members.lock() {
+            members.retain(|i| i != identity);
+        }
+    }
+
+    /// Deliver a message to the worker picked by the strategy; if the worker has exited, it is
+    /// dropped from the pool and the message is routed again. A message that no worker is left
+    /// to take, or a broadcast copy for a worker that has exited, is passed to the dead letters,
+    /// if there are any. Rate limits don't hold up the dispatch thread; a message that would
+    /// have to wait is rejected.
+    fn route(&mut self, message: T) {
+        if let IsolateRouterStrategy::Broadcast(copy) = &self.strategy {
+            let mut failed = Vec::new();
+            for (identity, channel) in self.workers.iter() {
+                if let Ok(Some(returned)) = channel.try_send_or_return(copy(&message)) {
+                    let _ = channel.undeliverable(returned);
+                    failed.push(*identity);
+                }
+            }
+            failed.iter().for_each(|identity| self.remove(identity));
+            return;
+        }
+
+        let mut message = message;
+        let mut closed = None;
+        while let Some(offset) = self.pick(&message) {
+            let (identity, channel) = self.workers[offset].clone();
+            match channel.try_send_or_return(message) {
+                Ok(Some(returned)) => {
+                    message = returned;
+                    closed = Some(channel);
+                    self.remove(&identity);
+                }
+                _ => return,
+            }
+        }
+        if let Some(channel) = closed {
+            let _ = channel.undeliverable(message);
+        }
+    }
+
+    fn pick(&mut self, message: &T) -> Option<usize> {
+        if self.workers.is_empty() {
+            return None;
+        }
+        match &self.strategy {
+            IsolateRouterStrategy::RoundRobin => {
+                let offset = self.next % self.workers.len();
+                self.next = self.next.wrapping_add(1);
+                Some(offset)
+            }
+            IsolateRouterStrategy::Random => {
+                // xorshift64; this only needs to spread load, not be unpredictable
+                self.seed ^= self.seed << 13;
+                self.seed ^= self.seed >> 7;
+                self.seed ^= self.seed << 17;
+                Some((self.seed % self.workers.len() as u64) as usize)
+            }
+            IsolateRouterStrategy::LeastLoaded => self
+                .workers
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, (_, channel))| channel.queued())
+                .map(|(offset, _)| offset),
+            IsolateRouterStrategy::ConsistentHash(key) => {
+                let hash = key(message);
+                let owner = self
+                    .ring
+                    .range(hash..)
+                    .next()
+                    .or_else(|| self.ring.iter().next())
+                    .map(|(_, identity)| *identity)?;
+                self.workers.iter().position(|(i, _)| *i == owner)
+            }
+            IsolateRouterStrategy::Broadcast(_) => None,
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use crate::IsolateRuntimeError;
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum IsolateRouterError {
+    InternalSyncError,
+    RouterHalted,
+    RuntimeError(IsolateRuntimeError),
+}
+
+impl Error for IsolateRouterError {}
+
+impl Display for IsolateRouterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl From<IsolateRuntimeError> for IsolateRouterError {
+    fn from(err: IsolateRuntimeError) -> Self {
+        IsolateRouterError::RuntimeError(err)
+    }
+}
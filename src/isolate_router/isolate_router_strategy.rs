@@ -0,0 +1,45 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+
+/// IsolateRouterStrategy decides which worker in a router pool receives each message.
+pub enum IsolateRouterStrategy<T: Send + 'static> {
+    /// Send each message to the next worker in turn.
+    RoundRobin,
+
+    /// Send each message to a randomly picked worker.
+    Random,
+
+    /// Send each message to the worker with the fewest messages waiting in its mailbox.
+    LeastLoaded,
+
+    /// Send each message to the worker that owns the hash of its key on a consistent hash ring,
+    /// so the same key keeps going to the same worker while the pool is resized.
+    ConsistentHash(Box<dyn Fn(&T) -> u64 + Send + 'static>),
+
+    /// Send a copy of each message to every worker.
+    Broadcast(Box<dyn Fn(&T) -> T + Send + 'static>),
+}
+
+impl<T: Send + 'static> IsolateRouterStrategy<T> {
+    /// Route by consistent hash on the key returned for each message
+    pub fn consistent_hash<K: Hash>(key: impl Fn(&T) -> K + Send + 'static) -> Self {
+        IsolateRouterStrategy::ConsistentHash(Box::new(move |message| hash_of(&key(message))))
+    }
+
+    /// Route a clone of each message to every worker
+    pub fn broadcast() -> Self
+    where
+        T: Clone,
+    {
+        IsolateRouterStrategy::Broadcast(Box::new(T::clone))
+    }
+}
+
+/// Return a stable hash for a value; the default hasher uses fixed keys so this doesn't vary
+/// between calls, which the hash ring depends on.
+pub(crate) fn hash_of(value: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
@@ -1,69 +1,176 @@
+pub(crate) mod isolate_broadcast_report;
+pub(crate) mod isolate_cancellation_token;
+pub(crate) mod isolate_current_worker;
+pub(crate) mod isolate_down;
+pub(crate) mod isolate_exit_guard;
+pub(crate) mod isolate_exit_reason;
 pub(crate) mod isolate_identity;
 pub(crate) mod isolate_runtime_error;
 pub(crate) mod isolate_runtime_ref;
 pub(crate) mod isolate_runtime_shared;
 pub(crate) mod isolate_runtime_wait;
+pub(crate) mod isolate_watch;
+pub(crate) mod isolate_worker_info;
+pub(crate) mod isolate_worker_state;
 
+use crate::isolate_context::IsolateContextRegistry;
+use crate::isolate_dead_letters::isolate_dead_letter_route::IsolateDeadLetterRoute;
+use crate::isolate_mailbox::isolate_mailbox_binding::IsolateMailboxBinding;
 use crate::isolate_runtime::isolate_runtime_shared::IsolateRuntimeShared;
 use crate::isolate_runtime::isolate_runtime_wait::IsolateRuntimeWait;
+use crate::isolate_runtime::isolate_worker_state::IsolateWorkerState;
 use crate::Isolate;
 use crate::IsolateChannel;
+use crate::IsolateDurableMailbox;
+use crate::IsolateIdentity;
+use crate::IsolateRateLimit;
+use crate::IsolateRecord;
 use crate::IsolateRuntimeError;
 use crate::IsolateRuntimeRef;
-use std::collections::HashMap;
-use std::mem;
+use crate::isolate_timer::IsolateTimer;
+use crate::IsolateTree;
+use std::marker::PhantomData;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::thread::JoinHandle;
+use std::time::Duration;
 
 pub struct IsolateRef<T: Send + 'static> {
     channel: IsolateChannel<T>,
     handle: JoinHandle<()>,
+    state: Arc<IsolateWorkerState>,
 }
 
-pub struct IsolateRuntime<T: Send + 'static> {
+/// IsolateRuntime runs the workers of an isolate; `A` is the Args type of the isolate, so
+/// spawning with the wrong args doesn't compile.
+pub struct IsolateRuntime<T: Send + 'static, A: Send + 'static = ()> {
     shared: Arc<Mutex<IsolateRuntimeShared<T>>>,
+    args: PhantomData<fn(A)>,
 }
 
 impl<T: Send + 'static> IsolateRuntime<T> {
+    /// Spawn a new isolate worker thread and run it
+    pub fn spawn(&mut self) -> Result<IsolateChannel<T>, IsolateRuntimeError> {
+        self.spawn_with(())
+    }
+}
+
+impl<T: Send + 'static, A: Send + 'static> IsolateRuntime<T, A> {
     /// Create a new runner with a specific isolate instance
-    pub fn new(isolate: impl Isolate<T> + Send + 'static) -> IsolateRuntime<T> {
+    pub fn new(isolate: impl Isolate<T, Args = A> + Send + 'static) -> IsolateRuntime<T, A> {
         IsolateRuntime {
             shared: IsolateRuntimeShared::<T>::new(isolate),
+            args: PhantomData,
         }
     }
 
-    /// Spawn a new isolate worker thread and run it
-    pub fn spawn(&mut self) -> Result<IsolateChannel<T>, IsolateRuntimeError> {
+    /// Spawn a new isolate worker thread and run it with a set of spawn-time args
+    pub fn spawn_with(&mut self, args: A) -> Result<IsolateChannel<T>, IsolateRuntimeError> {
+        match self.shared.lock() {
+            Ok(mut inner) => inner.spawn_with(args),
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+
+    /// Spawn a new isolate worker with a given identity and spawn-time args, such as to bring
+    /// back a worker that exited. Fails if a running worker already has the identity.
+    pub fn spawn_as(
+        &mut self,
+        identity: IsolateIdentity,
+        args: A,
+    ) -> Result<IsolateChannel<T>, IsolateRuntimeError> {
         match self.shared.lock() {
-            Ok(mut inner) => Ok(inner.spawn()),
+            Ok(mut inner) => inner
+                .spawn_as(identity, args, None)
+                .map(|(_, channel)| channel),
             Err(_) => Err(IsolateRuntimeError::InternalSyncError),
         }
     }
 
-    /// Return a reference instance
+    /// Spawn a new isolate worker with spawn-time args and tags, so it can be found by tag with
+    /// `find_tagged` from the start
+    pub fn spawn_tagged(
+        &mut self,
+        args: A,
+        tags: &[(&str, &str)],
+    ) -> Result<IsolateChannel<T>, IsolateRuntimeError> {
+        match self.shared.lock() {
+            Ok(mut inner) => inner
+                .spawn_tagged(IsolateIdentity::new(), args, None, tags)
+                .map(|(_, channel)| channel),
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+
+    /// Limit the rate of messages to each worker of this runtime, on top of any limits of the
+    /// channels they are sent through
+    pub fn with_rate_limit(self, limit: IsolateRateLimit) -> IsolateRuntime<T, A> {
+        if let Ok(mut inner) = self.shared.lock() {
+            inner.bind_rate_limit(limit);
+        }
+        self
+    }
+
+    /// Have workers of this runtime wait out a window after the first message of a batch, so
+    /// `recv_batch` gathers the messages arriving close together
+    pub fn with_coalescing(self, window: Duration) -> IsolateRuntime<T, A> {
+        if let Ok(mut inner) = self.shared.lock() {
+            inner.bind_coalescing(window);
+        }
+        self
+    }
+
+    /// Trip the cancellation token of every worker of this runtime, then wait for them to exit
+    pub fn shutdown(&self) {
+        self.as_ref().shutdown();
+    }
+
+    /// Return a reference instance; spawning through a reference checks the args when the
+    /// worker is spawned, since a reference doesn't carry the Args type
     pub fn as_ref(&self) -> IsolateRuntimeRef<T> {
         IsolateRuntimeRef::new(self.shared.clone())
     }
+
+    /// Send undeliverable messages for workers of this runtime to a dead letter sink
+    pub(crate) fn bind_dead_letters(&self, route: Arc<IsolateDeadLetterRoute>) {
+        if let Ok(mut inner) = self.shared.lock() {
+            inner.bind_dead_letters(route);
+        }
+    }
+
+    /// Schedule timers for workers of this runtime on the timer of the registry it is bound to
+    pub(crate) fn bind_timer(&self, timer: IsolateTimer) {
+        if let Ok(mut inner) = self.shared.lock() {
+            inner.bind_timer(timer);
+        }
+    }
+
+    /// Let workers of this runtime find the registry it is bound to, and its name there
+    pub(crate) fn bind_registry(&self, registry: IsolateContextRegistry, tree: IsolateTree) {
+        if let Ok(mut inner) = self.shared.lock() {
+            inner.bind_registry(registry, tree);
+        }
+    }
 }
 
-impl<T: Send + 'static> IsolateRuntimeWait for IsolateRuntime<T> {
+impl<T: IsolateRecord + Send + 'static, A: Send + 'static> IsolateRuntime<T, A> {
+    /// Keep the messages sent to workers of this runtime in a durable mailbox until they are
+    /// handled, so a worker spawned again with `spawn_as` after a restart gets the messages its
+    /// identity didn't get to. Only messages sent with `send` are kept, and the worker must
+    /// receive them through its context; they arrive on a queue of their own, so they aren't
+    /// ordered with messages sent straight on the sender.
+    pub fn with_mailbox(self, mailbox: IsolateDurableMailbox) -> IsolateRuntime<T, A> {
+        if let Ok(mut inner) = self.shared.lock() {
+            inner.bind_mailbox(IsolateMailboxBinding::new(mailbox));
+        }
+        self
+    }
+}
+
+impl<T: Send + 'static, A: Send + 'static> IsolateRuntimeWait for IsolateRuntime<T, A> {
     /// Halt this runner and wait for all its workers to shutdown
     fn wait(&self) {
-        match self.shared.lock() {
-            Ok(mut inner) => {
-                let mut refs = HashMap::new();
-                mem::swap(&mut refs, &mut inner.refs);
-                refs.into_iter()
-                    .map(|(_, r)| r.handle)
-                    .collect::<Vec<JoinHandle<()>>>() // Drop original references
-                    .into_iter()
-                    .for_each(|h| {
-                        let _ = h.join();
-                    });
-            }
-            Err(_) => {}
-        }
+        self.as_ref().wait();
     }
 }
 
@@ -72,8 +179,9 @@ mod tests {
     use super::IsolateRuntime;
     use crate::isolate_runtime::isolate_runtime_wait::IsolateRuntimeWait;
     use crate::Isolate;
-    use crate::IsolateChannel;
+    use crate::IsolateContext;
     use crate::IsolateIdentity;
+    use crate::IsolateRuntimeError;
 
     struct TestIsolate {}
 
@@ -85,30 +193,27 @@ mod tests {
     }
 
     impl Isolate<TestIsolateEvent> for TestIsolate {
+        type Args = ();
+
         fn spawn(
             &self,
-            identity: IsolateIdentity,
-            channel: IsolateChannel<TestIsolateEvent>,
+            context: IsolateContext<TestIsolateEvent>,
+            _: (),
         ) -> Box<dyn FnMut() + Send + 'static> {
+            let identity = context.identity();
+            let channel = context.channel().clone();
             Box::new(move || {
-                loop {
-                    match channel.receiver.recv() {
-                        Ok(v) => {
-                            match v {
-                                TestIsolateEvent::Who => {
-                                    channel
-                                        .sender
-                                        .send(TestIsolateEvent::Identity(identity))
-                                        .unwrap();
-                                }
-                                _ => {
-                                    // Ignore send errors; the connections may be broken by the tests.
-                                    let _ = channel.sender.send(v);
-                                }
-                            }
+                while let Ok(v) = channel.receiver.recv() {
+                    match v {
+                        TestIsolateEvent::Who => {
+                            channel
+                                .sender
+                                .send(TestIsolateEvent::Identity(identity))
+                                .unwrap();
                         }
-                        Err(_) => {
-                            break;
+                        _ => {
+                            // Ignore send errors; the connections may be broken by the tests.
+                            let _ = channel.sender.send(v);
                         }
                     }
                 }
@@ -168,6 +273,15 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn test_spawn_with_invalid_args() {
+        let runner = IsolateRuntime::new(TestIsolate {});
+        match runner.as_ref().spawn_with("Not unit") {
+            Err(IsolateRuntimeError::InvalidArgsType) => {}
+            _ => unreachable!(),
+        }
+    }
+
     #[test]
     pub fn test_broadcast_to_instance() {
         let mut runner = IsolateRuntime::new(TestIsolate {});
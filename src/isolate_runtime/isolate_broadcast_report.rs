@@ -0,0 +1,18 @@
+use crate::IsolateChannelError;
+use crate::IsolateIdentity;
+
+/// Which workers a broadcast reached, and which it didn't
+#[derive(Debug)]
+pub struct IsolateBroadcastReport {
+    /// The workers the message was sent to
+    pub delivered: Vec<IsolateIdentity>,
+    /// The workers the message couldn't be sent to, and why
+    pub failed: Vec<(IsolateIdentity, IsolateChannelError)>,
+}
+
+impl IsolateBroadcastReport {
+    /// Return true if the message was sent to every worker it was meant for
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
@@ -0,0 +1,69 @@
+use crate::IsolateContextError;
+use crossbeam::{Receiver, RecvTimeoutError, TryRecvError};
+use std::time::Duration;
+
+/// IsolateCancellationToken is tripped when a worker is asked to stop, by `stop`, `kill`, a
+/// runtime `shutdown` or unbinding its runtime from a registry. Receiving through the context
+/// already returns `Stopped` once it is tripped; long running work should check the token
+/// itself. Tokens are cheap to clone, and can be handed to other threads the worker starts.
+#[derive(Clone)]
+pub struct IsolateCancellationToken {
+    stopped: Receiver<()>,
+}
+
+impl IsolateCancellationToken {
+    pub(crate) fn new(stopped: Receiver<()>) -> IsolateCancellationToken {
+        IsolateCancellationToken { stopped }
+    }
+
+    /// Return true if the worker has been asked to stop
+    pub fn is_cancelled(&self) -> bool {
+        self.stopped.try_recv() == Err(TryRecvError::Disconnected)
+    }
+
+    /// Return `Stopped` if the worker has been asked to stop, to bail out with `?`
+    pub fn check(&self) -> Result<(), IsolateContextError> {
+        match self.is_cancelled() {
+            true => Err(IsolateContextError::Stopped),
+            false => Ok(()),
+        }
+    }
+
+    /// Sleep for up to a timeout, waking early if the worker is asked to stop; returns true if
+    /// it was
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        self.stopped.recv_timeout(timeout) == Err(RecvTimeoutError::Disconnected)
+    }
+
+    /// Return a channel that disconnects when the worker is asked to stop, to wait on together
+    /// with other channels
+    pub fn receiver(&self) -> &Receiver<()> {
+        &self.stopped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IsolateCancellationToken;
+    use crate::IsolateContextError;
+    use crossbeam::bounded;
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_trip_token() {
+        let (stop, stopped) = bounded::<()>(0);
+        let token = IsolateCancellationToken::new(stopped);
+        let shared = token.clone();
+        assert!(!token.is_cancelled());
+        assert!(token.check().is_ok());
+        assert!(!token.wait_timeout(Duration::from_millis(1)));
+
+        drop(stop);
+        assert!(shared.is_cancelled());
+        assert!(shared.wait_timeout(Duration::from_secs(10)));
+        match token.check() {
+            Err(IsolateContextError::Stopped) => {}
+            result => panic!("Expected the token to be tripped: {:?}", result),
+        }
+    }
+}
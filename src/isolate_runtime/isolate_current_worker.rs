@@ -0,0 +1,17 @@
+use crate::IsolateIdentity;
+use std::cell::Cell;
+
+thread_local! {
+    /// The worker running on this thread, if it is a worker thread
+    static CURRENT_WORKER: Cell<Option<IsolateIdentity>> = const { Cell::new(None) };
+}
+
+/// Record the worker that runs on this thread
+pub fn enter(identity: IsolateIdentity) {
+    CURRENT_WORKER.with(|current| current.set(Some(identity)));
+}
+
+/// Return the worker running on this thread, if it is a worker thread
+pub fn current() -> Option<IsolateIdentity> {
+    CURRENT_WORKER.with(|current| current.get())
+}
@@ -0,0 +1,12 @@
+use crate::IsolateExitReason;
+use crate::IsolateIdentity;
+
+/// The notification delivered to a watching worker when a worker it monitors, or is linked to,
+/// exits. Message types that can receive it implement `From<IsolateDown>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsolateDown {
+    /// The worker that exited
+    pub identity: IsolateIdentity,
+    /// Why the worker exited
+    pub reason: IsolateExitReason,
+}
@@ -0,0 +1,36 @@
+use crate::IsolateExitReason;
+use std::thread;
+
+/// IsolateExitGuard is held by a worker thread for as long as the worker runs; when it is
+/// dropped, either because the worker returned or because it panicked, the exit handler runs.
+pub struct IsolateExitGuard {
+    on_exit: Option<Box<dyn FnOnce(IsolateExitReason) + Send + 'static>>,
+    reason: Option<IsolateExitReason>,
+}
+
+impl IsolateExitGuard {
+    pub fn new(on_exit: impl FnOnce(IsolateExitReason) + Send + 'static) -> IsolateExitGuard {
+        IsolateExitGuard {
+            on_exit: Some(Box::new(on_exit)),
+            reason: None,
+        }
+    }
+
+    /// Set the reason passed to the exit handler
+    pub fn set_reason(&mut self, reason: IsolateExitReason) {
+        self.reason = Some(reason);
+    }
+}
+
+impl Drop for IsolateExitGuard {
+    fn drop(&mut self) {
+        let reason = match self.reason.take() {
+            Some(reason) => reason,
+            None if thread::panicking() => IsolateExitReason::Panicked("Unknown panic".to_string()),
+            None => IsolateExitReason::Normal,
+        };
+        if let Some(on_exit) = self.on_exit.take() {
+            (on_exit)(reason);
+        }
+    }
+}
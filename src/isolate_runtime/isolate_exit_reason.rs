@@ -0,0 +1,34 @@
+use crate::IsolateIdentity;
+use std::any::Any;
+
+/// Why a worker exited
+#[derive(Debug, Clone, PartialEq)]
+pub enum IsolateExitReason {
+    /// The worker returned
+    Normal,
+    /// The worker panicked, with the panic message
+    Panicked(String),
+    /// The worker reported a failure before it exited
+    Failed(String),
+    /// The worker exited after a worker it was linked to exited abnormally
+    Linked(IsolateIdentity),
+}
+
+impl IsolateExitReason {
+    /// Return true for any exit that should be propagated over links
+    pub fn is_abnormal(&self) -> bool {
+        *self != IsolateExitReason::Normal
+    }
+
+    /// The exit reason for a panic with the given payload
+    pub(crate) fn panicked(payload: &(dyn Any + Send)) -> IsolateExitReason {
+        let message = match payload.downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => match payload.downcast_ref::<String>() {
+                Some(message) => message.clone(),
+                None => "Unknown panic".to_string(),
+            },
+        };
+        IsolateExitReason::Panicked(message)
+    }
+}
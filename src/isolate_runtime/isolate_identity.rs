@@ -9,6 +9,7 @@ pub struct IsolateIdentity {
     identity: Uuid
 }
 
+#[allow(clippy::new_without_default)]
 impl IsolateIdentity {
     pub fn new() -> IsolateIdentity {
         IsolateIdentity {
@@ -16,10 +17,12 @@ impl IsolateIdentity {
         }
     }
 
+    #[allow(clippy::inherent_to_string_shadow_display)]
     pub fn to_string(&self) -> String {
         self.identity.to_string()
     }
 
+    #[allow(deprecated)]
     pub fn try_from(value: &str) -> Result<IsolateIdentity, IsolateRuntimeError> {
         match Uuid::parse_str(value) {
             Ok(id) => {
@@ -1,11 +1,17 @@
 use std::fmt::Display;
 use std::error::Error;
 use std::fmt;
+use crate::IsolateIdentity;
+use crate::IsolateMailboxError;
 
 #[derive(Debug)]
 pub enum IsolateRuntimeError {
     InternalSyncError,
-    InvalidIdentity(String)
+    InvalidIdentity(String),
+    InvalidArgsType,
+    NoMatchingIdentity,
+    IdentityInUse(IsolateIdentity),
+    MailboxError(IsolateMailboxError),
 }
 
 impl Error for IsolateRuntimeError {}
@@ -14,4 +20,4 @@ impl Display for IsolateRuntimeError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}", self)
     }
-}
\ No newline at end of file
+}
@@ -1,9 +1,26 @@
 use crate::isolate_runtime::isolate_runtime_shared::IsolateRuntimeShared;
+use crate::isolate_runtime::isolate_runtime_shared::IsolateTestHook;
+use crate::isolate_metrics::isolate_metrics_source::IsolateMetricsSource;
+use crate::isolate_runtime::isolate_runtime_wait::IsolateRuntimeWait;
+use crate::isolate_runtime::isolate_watch::IsolateWatch;
+use crate::isolate_timer::IsolateTimer;
+use crate::IsolateBroadcastReport;
 use crate::IsolateChannel;
+use crate::IsolateChannelError;
+use crate::IsolateDeadLetterReason;
+use crate::IsolateDown;
 use crate::IsolateIdentity;
+use crate::IsolateRateLimit;
+use crate::IsolatePriority;
 use crate::IsolateRuntimeError;
+use crate::IsolateRuntimeMetrics;
+use crate::IsolateTimerHandle;
+use crate::IsolateTree;
+use crate::IsolateWorkerInfo;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::sync::Weak;
+use std::time::Duration;
 
 pub struct IsolateRuntimeRef<T: Send + 'static> {
     shared: Arc<Mutex<IsolateRuntimeShared<T>>>,
@@ -14,6 +31,13 @@ impl<T: Send + 'static> IsolateRuntimeRef<T> {
         IsolateRuntimeRef { shared }
     }
 
+    /// Return a weak handle to the runtime, for holding on to it without keeping it alive
+    pub(crate) fn downgrade(&self) -> Weak<Mutex<IsolateRuntimeShared<T>>> {
+        Arc::downgrade(&self.shared)
+    }
+
+    /// Find the channel for a specific worker, if it is still bound to this runtime
+    #[allow(clippy::manual_map)]
     pub fn find(&self, identity: &IsolateIdentity) -> Option<IsolateChannel<T>> {
         match self.shared.lock() {
             Ok(inner) => match inner.refs.get(identity) {
@@ -24,11 +48,455 @@ impl<T: Send + 'static> IsolateRuntimeRef<T> {
         }
     }
 
+    /// Find the channels of every worker bound to this runtime with a tag of the given value
+    pub fn find_tagged(&self, key: &str, value: &str) -> Vec<IsolateChannel<T>> {
+        match self.shared.lock() {
+            Ok(inner) => inner
+                .workers()
+                .into_iter()
+                .filter(|(info, _)| info.tags.get(key).map(|v| v.as_str()) == Some(value))
+                .map(|(_, channel)| channel)
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Send a message to a specific worker.
+    /// If the worker isn't bound to this runtime, the message is passed to the dead letter sink.
+    pub fn send(&self, identity: &IsolateIdentity, message: T) -> Result<(), IsolateChannelError> {
+        let inner = match self.shared.lock() {
+            Ok(inner) => inner,
+            Err(_) => return Err(IsolateChannelError::Disconnected),
+        };
+        match inner.refs.get(identity).map(|r| r.channel.clone()) {
+            Some(channel) => {
+                drop(inner);
+                channel.send(message)
+            }
+            None => {
+                let reason = IsolateDeadLetterReason::NoMatchingIdentity;
+                if inner.dead_letter(*identity, reason, message) {
+                    Err(IsolateChannelError::DeadLetter)
+                } else {
+                    Err(IsolateChannelError::Disconnected)
+                }
+            }
+        }
+    }
+
+    /// Send a copy of a message to every running worker of this runtime, reporting which
+    /// deliveries failed
+    pub fn broadcast(&self, message: T) -> Result<IsolateBroadcastReport, IsolateRuntimeError>
+    where
+        T: Clone,
+    {
+        self.broadcast_where(message, |_| true)
+    }
+
+    /// Send a copy of a message to every running worker of this runtime that a filter picks
+    /// out, reporting which deliveries failed. A copy over a rate limit that delays messages is
+    /// rejected as `Throttled` rather than hold up the other workers.
+    pub fn broadcast_where(
+        &self,
+        message: T,
+        filter: impl Fn(&IsolateWorkerInfo) -> bool,
+    ) -> Result<IsolateBroadcastReport, IsolateRuntimeError>
+    where
+        T: Clone,
+    {
+        // The filter and the sends may block, so they run without the runtime locked
+        let workers = match self.shared.lock() {
+            Ok(inner) => inner.workers(),
+            Err(_) => return Err(IsolateRuntimeError::InternalSyncError),
+        };
+        let mut report = IsolateBroadcastReport {
+            delivered: Vec::new(),
+            failed: Vec::new(),
+        };
+        for (info, channel) in workers.into_iter().filter(|(info, _)| filter(info)) {
+            match channel.try_send(message.clone()) {
+                Ok(()) => report.delivered.push(info.identity),
+                Err(err) => report.failed.push((info.identity, err)),
+            }
+        }
+        Ok(report)
+    }
+
+    /// Send a message to a specific worker after a delay.
+    /// The timer is cancelled automatically if the worker exits first.
+    pub fn send_after(
+        &self,
+        identity: &IsolateIdentity,
+        delay: Duration,
+        message: T,
+    ) -> Result<IsolateTimerHandle, IsolateRuntimeError> {
+        let mut message = Some(message);
+        match self.shared.lock() {
+            Ok(mut inner) => inner.schedule(identity, delay, None, move || message.take()),
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+
+    /// Send a copy of a message to a specific worker every period, until the timer is cancelled
+    /// or the worker exits.
+    pub fn send_interval(
+        &self,
+        identity: &IsolateIdentity,
+        period: Duration,
+        message: T,
+    ) -> Result<IsolateTimerHandle, IsolateRuntimeError>
+    where
+        T: Clone,
+    {
+        match self.shared.lock() {
+            Ok(mut inner) => inner.schedule(identity, period, Some(period), move || {
+                Some(message.clone())
+            }),
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+
     /// Spawn a new isolate worker thread and run it
     pub fn spawn(&mut self) -> Result<IsolateChannel<T>, IsolateRuntimeError> {
+        self.spawn_with(())
+    }
+
+    /// Spawn a new isolate worker thread and run it with a set of spawn-time args.
+    /// The args must match the Args type of the isolate bound to this runtime; a reference
+    /// doesn't carry that type, so they are checked when the worker is spawned.
+    pub fn spawn_with<A: Send + 'static>(
+        &mut self,
+        args: A,
+    ) -> Result<IsolateChannel<T>, IsolateRuntimeError> {
+        match self.shared.lock() {
+            Ok(mut inner) => inner.spawn_with(args),
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+
+    /// Spawn a new isolate worker with a given identity and spawn-time args, such as to bring
+    /// back a worker that exited. Fails if a running worker already has the identity.
+    pub fn spawn_as<A: Send + 'static>(
+        &mut self,
+        identity: IsolateIdentity,
+        args: A,
+    ) -> Result<IsolateChannel<T>, IsolateRuntimeError> {
+        match self.shared.lock() {
+            Ok(mut inner) => inner
+                .spawn_as(identity, args, None)
+                .map(|(_, channel)| channel),
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+
+    /// Spawn a new isolate worker with spawn-time args and tags, so it can be found by tag with
+    /// `find_tagged` from the start
+    pub fn spawn_tagged<A: Send + 'static>(
+        &mut self,
+        args: A,
+        tags: &[(&str, &str)],
+    ) -> Result<IsolateChannel<T>, IsolateRuntimeError> {
+        match self.shared.lock() {
+            Ok(mut inner) => inner
+                .spawn_tagged(IsolateIdentity::new(), args, None, tags)
+                .map(|(_, channel)| channel),
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+
+    /// Spawn a new isolate worker, returning both its identity and a channel to it.
+    pub(crate) fn spawn_worker<A: Send + 'static>(
+        &self,
+        args: A,
+    ) -> Result<(IsolateIdentity, IsolateChannel<T>), IsolateRuntimeError> {
+        self.spawn_child(args, None)
+    }
+
+    /// Spawn a new isolate worker as the child of another worker, which may be in another
+    /// runtime, returning both its identity and a channel to it.
+    pub(crate) fn spawn_child<A: Send + 'static>(
+        &self,
+        args: A,
+        parent: Option<IsolateIdentity>,
+    ) -> Result<(IsolateIdentity, IsolateChannel<T>), IsolateRuntimeError> {
+        match self.shared.lock() {
+            Ok(mut inner) => inner.spawn_worker(args, parent),
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+
+    /// Limit the rate of messages to each worker spawned from now on, such as for a runtime
+    /// bound to a registry
+    pub fn set_rate_limit(&self, limit: IsolateRateLimit) -> Result<(), IsolateRuntimeError> {
+        match self.shared.lock() {
+            Ok(mut inner) => {
+                inner.bind_rate_limit(limit);
+                Ok(())
+            }
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+
+    /// Have workers spawned from now on wait out a window after the first message of a batch,
+    /// such as for a runtime bound to a registry
+    pub fn set_coalescing(&self, window: Duration) -> Result<(), IsolateRuntimeError> {
+        match self.shared.lock() {
+            Ok(mut inner) => {
+                inner.bind_coalescing(window);
+                Ok(())
+            }
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+
+    /// Return the timer of this runtime, to tell the time by; under the test scheduler this runs
+    /// on virtual time
+    pub(crate) fn clock(&self) -> IsolateTimer {
+        match self.shared.lock() {
+            Ok(inner) => inner.clock(),
+            Err(_) => IsolateTimer::new(),
+        }
+    }
+
+    /// Take a snapshot of the counters of this runtime and its running workers
+    pub fn metrics(&self) -> Result<IsolateRuntimeMetrics, IsolateRuntimeError> {
+        match self.shared.lock() {
+            Ok(inner) => Ok(inner.metrics()),
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+
+    /// Return the tree of workers for this runtime; for a runtime bound to a registry, this is
+    /// shared by every runtime in the registry.
+    pub fn tree(&self) -> Result<IsolateTree, IsolateRuntimeError> {
+        match self.shared.lock() {
+            Ok(inner) => Ok(inner.tree()),
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+
+    /// Return the state a state machine worker is in, or None if the worker isn't a state
+    /// machine with states of this type
+    pub fn current_state<S: Clone + 'static>(
+        &self,
+        identity: &IsolateIdentity,
+    ) -> Result<Option<S>, IsolateRuntimeError> {
+        match self.shared.lock() {
+            Ok(inner) => match inner.refs.get(identity) {
+                Some(r) => Ok(r.state.machine_state()),
+                None => Err(IsolateRuntimeError::NoMatchingIdentity),
+            },
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+
+    /// Release a worker from this runtime; it can no longer be found by identity, and it will see
+    /// its channel close once every other connection to it has been dropped.
+    pub fn release(&self, identity: &IsolateIdentity) -> Result<bool, IsolateRuntimeError> {
+        match self.shared.lock() {
+            Ok(mut inner) => Ok(inner.release(identity)),
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+
+    /// Monitor a worker, which may be in another runtime; when the target exits, for any
+    /// reason, the watcher in this runtime is sent an IsolateDown notification.
+    pub fn monitor<W: Send + 'static>(
+        &self,
+        watcher: &IsolateIdentity,
+        targets: &IsolateRuntimeRef<W>,
+        target: &IsolateIdentity,
+    ) -> Result<(), IsolateRuntimeError>
+    where
+        T: From<IsolateDown>,
+    {
+        targets.watch(target, self.watcher(watcher, false)?)
+    }
+
+    /// Stop monitoring a worker, returning false if the watcher wasn't monitoring it
+    pub fn demonitor<W: Send + 'static>(
+        &self,
+        watcher: &IsolateIdentity,
+        targets: &IsolateRuntimeRef<W>,
+        target: &IsolateIdentity,
+    ) -> Result<bool, IsolateRuntimeError> {
+        targets.unwatch(watcher, target, false)
+    }
+
+    /// Link a worker in this runtime to a worker which may be in another runtime; when either
+    /// exits abnormally, the other is sent an IsolateDown notification. A worker that returns
+    /// after being notified exits abnormally in turn, with IsolateExitReason::Linked, so failures
+    /// propagate across chains of links.
+    pub fn link<W>(
+        &self,
+        identity: &IsolateIdentity,
+        peers: &IsolateRuntimeRef<W>,
+        peer: &IsolateIdentity,
+    ) -> Result<(), IsolateRuntimeError>
+    where
+        T: From<IsolateDown>,
+        W: Send + From<IsolateDown> + 'static,
+    {
+        peers.watch(peer, self.watcher(identity, true)?)?;
+        match self.watch(identity, peers.watcher(peer, true)?) {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                peers.unwatch(identity, peer, true)?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Remove the link between two workers, returning false if they weren't linked
+    pub fn unlink<W: Send + 'static>(
+        &self,
+        identity: &IsolateIdentity,
+        peers: &IsolateRuntimeRef<W>,
+        peer: &IsolateIdentity,
+    ) -> Result<bool, IsolateRuntimeError> {
+        let unlinked = peers.unwatch(identity, peer, true)?;
+        Ok(self.unwatch(peer, identity, true)? || unlinked)
+    }
+
+    /// Create a watch that notifies a worker in this runtime
+    fn watcher(
+        &self,
+        watcher: &IsolateIdentity,
+        linked: bool,
+    ) -> Result<IsolateWatch, IsolateRuntimeError>
+    where
+        T: From<IsolateDown>,
+    {
+        if self.find(watcher).is_none() {
+            return Err(IsolateRuntimeError::NoMatchingIdentity);
+        }
+
+        // The watch only holds a weak reference, so it doesn't keep the watcher channel open
+        let runtime = Arc::downgrade(&self.shared);
+        let identity = *watcher;
+        Ok(IsolateWatch {
+            watcher: identity,
+            linked,
+            notify: Arc::new(move |down| {
+                let channel = runtime.upgrade().and_then(|shared| match shared.lock() {
+                    Ok(mut inner) => inner.watcher_channel(&identity, down, linked),
+                    Err(_) => None,
+                });
+                if let Some(channel) = channel {
+                    let _ = channel.send_priority(T::from(down.clone()), IsolatePriority::System);
+                }
+            }),
+        })
+    }
+
+    fn watch(
+        &self,
+        target: &IsolateIdentity,
+        watch: IsolateWatch,
+    ) -> Result<(), IsolateRuntimeError> {
+        match self.shared.lock() {
+            Ok(mut inner) => inner.watch(target, watch),
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+
+    fn unwatch(
+        &self,
+        watcher: &IsolateIdentity,
+        target: &IsolateIdentity,
+        linked: bool,
+    ) -> Result<bool, IsolateRuntimeError> {
         match self.shared.lock() {
-            Ok(mut inner) => Ok(inner.spawn()),
+            Ok(mut inner) => Ok(inner.unwatch(watcher, target, linked)),
             Err(_) => Err(IsolateRuntimeError::InternalSyncError),
         }
     }
+
+    /// Run workers spawned from now on under the test scheduler, with timers on virtual time
+    pub(crate) fn bind_test(
+        &self,
+        hook: IsolateTestHook<T>,
+        timer: IsolateTimer,
+    ) -> Result<(), IsolateRuntimeError> {
+        match self.shared.lock() {
+            Ok(mut inner) => {
+                inner.bind_test(hook, timer);
+                Ok(())
+            }
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+
+    /// Ask a worker and every worker below it in the tree to stop; workers waiting on their
+    /// context wake up and see they are stopped.
+    /// Returns false if the worker isn't bound to this runtime.
+    pub fn stop(&self, identity: &IsolateIdentity) -> Result<bool, IsolateRuntimeError> {
+        match self.shared.lock() {
+            Ok(inner) => Ok(inner.stop(identity)),
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+
+    /// Ask a worker and every worker below it to stop, tripping their cancellation tokens, and
+    /// release it from this runtime at once, so it can't be found or sent to as it winds down.
+    /// Returns false if the worker isn't bound to this runtime.
+    pub fn kill(&self, identity: &IsolateIdentity) -> Result<bool, IsolateRuntimeError> {
+        match self.shared.lock() {
+            Ok(mut inner) => Ok(inner.kill(identity)),
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+
+    /// Trip the cancellation token of every worker of this runtime, then wait for them to exit
+    pub fn shutdown(&self) {
+        self.cancel_all();
+        self.wait();
+    }
+
+    /// Trip the cancellation token of every worker of this runtime, without waiting
+    pub(crate) fn cancel_all(&self) {
+        if let Ok(inner) = self.shared.lock() {
+            inner.stop_all();
+        }
+    }
+
+    /// Add a hook that is invoked with the identity of each worker in this runtime that exits
+    pub(crate) fn add_exit_hook(
+        &self,
+        hook: impl Fn(&IsolateIdentity) + Send + Sync + 'static,
+    ) -> Result<(), IsolateRuntimeError> {
+        match self.shared.lock() {
+            Ok(mut inner) => {
+                inner.add_exit_hook(hook);
+                Ok(())
+            }
+            Err(_) => Err(IsolateRuntimeError::InternalSyncError),
+        }
+    }
+}
+
+impl<T: Send + 'static> IsolateRuntimeWait for IsolateRuntimeRef<T> {
+    /// Halt the runtime and wait for all its workers to shutdown
+    fn wait(&self) {
+        // Workers update the runtime as they exit, so the lock must not be held while joining.
+        let handles = match self.shared.lock() {
+            Ok(mut inner) => inner.take_handles(),
+            Err(_) => return,
+        };
+        handles.into_iter().for_each(|h| {
+            let _ = h.join();
+        });
+    }
+}
+
+impl<T: Send + 'static> IsolateMetricsSource for IsolateRuntimeRef<T> {
+    fn metrics(&self) -> Option<IsolateRuntimeMetrics> {
+        IsolateRuntimeRef::metrics(self).ok()
+    }
+}
+
+impl<T: Send + 'static> Clone for IsolateRuntimeRef<T> {
+    fn clone(&self) -> IsolateRuntimeRef<T> {
+        IsolateRuntimeRef::new(self.shared.clone())
+    }
 }
@@ -1,36 +1,295 @@
+use crate::isolate::IsolateSpawn;
+#[cfg(feature = "tracing")]
+use crate::isolate_channel::isolate_channel_trace::IsolateChannelTrace;
+use crate::isolate_context::IsolateContextRegistry;
+use crate::isolate_channel::isolate_priority_lanes::IsolatePriorityLanes;
+use crate::isolate_dead_letters::isolate_dead_letter_route::IsolateDeadLetterRoute;
+use crate::isolate_mailbox::isolate_mailbox_binding::IsolateMailboxBinding;
+use crate::isolate_runtime::isolate_current_worker;
+use crate::isolate_runtime::isolate_exit_guard::IsolateExitGuard;
+use crate::isolate_runtime::isolate_watch::IsolateWatch;
+use crate::isolate_runtime::isolate_worker_state::IsolateWorkerState;
 use crate::isolate_runtime::IsolateRef;
+use crate::isolate_timer::IsolateTimer;
 use crate::Isolate;
 use crate::IsolateChannel;
+use crate::IsolateContext;
+use crate::IsolateChannelError;
+use crate::IsolateDeadLetterReason;
+use crate::IsolateDown;
+use crate::IsolateExitReason;
 use crate::IsolateIdentity;
+use crate::IsolateRateLimit;
+use crate::IsolateRuntimeMetrics;
+use crate::IsolateRuntimeError;
+use crate::IsolateTimerHandle;
+use crate::IsolateTree;
+use crate::IsolateWorkerInfo;
+use crate::IsolateWorkerMetrics;
+use crossbeam::{bounded, unbounded, Receiver, Sender};
 use std::collections::HashMap;
+use std::panic;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::sync::Weak;
 use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// A hook invoked with the identity of each worker that exits
+pub type IsolateExitHook = Arc<dyn Fn(&IsolateIdentity) + Send + Sync + 'static>;
+
+/// A hook that hands the staging queue and inbox of each new worker to the test scheduler
+pub type IsolateTestHook<T> = Box<
+    dyn Fn(IsolateIdentity, Receiver<T>, Sender<T>, Option<Arc<IsolateDeadLetterRoute>>)
+        + Send
+        + 'static,
+>;
 
 pub struct IsolateRuntimeShared<T: Send + 'static> {
     pub refs: HashMap<IsolateIdentity, IsolateRef<T>>,
-    isolate: Box<dyn Isolate<T> + Send + 'static>,
+    pub released: Vec<JoinHandle<()>>,
+    isolate: Box<dyn IsolateSpawn<T> + Send + 'static>,
+    exit_hooks: Vec<IsolateExitHook>,
+    dead_letters: Option<Arc<IsolateDeadLetterRoute>>,
+    mailbox: Option<IsolateMailboxBinding<T>>,
+    rate_limit: Option<IsolateRateLimit>,
+    coalescing: Option<Duration>,
+    registry: Option<IsolateContextRegistry>,
+    tree: IsolateTree,
+    timer: Option<IsolateTimer>,
+    timers: HashMap<IsolateIdentity, Vec<IsolateTimerHandle>>,
+    test_hook: Option<IsolateTestHook<T>>,
+    watches: HashMap<IsolateIdentity, Vec<IsolateWatch>>,
+    linked_exits: HashMap<IsolateIdentity, IsolateIdentity>,
+    spawned: u64,
+    exited: u64,
+    exited_counts: (u64, u64, u64),
+    this: Weak<Mutex<IsolateRuntimeShared<T>>>,
 }
 
 impl<T: Send + 'static> IsolateRuntimeShared<T> {
     pub fn new(isolate: impl Isolate<T> + Send + 'static) -> Arc<Mutex<IsolateRuntimeShared<T>>> {
-        Arc::new(Mutex::new(IsolateRuntimeShared {
-            isolate: Box::new(isolate),
-            refs: HashMap::new(),
-        }))
+        Arc::new_cyclic(|this| {
+            Mutex::new(IsolateRuntimeShared {
+                isolate: Box::new(isolate),
+                refs: HashMap::new(),
+                released: Vec::new(),
+                exit_hooks: Vec::new(),
+                dead_letters: None,
+                mailbox: None,
+                rate_limit: None,
+                coalescing: None,
+                registry: None,
+                tree: IsolateTree::new(),
+                timer: None,
+                timers: HashMap::new(),
+                test_hook: None,
+                watches: HashMap::new(),
+                linked_exits: HashMap::new(),
+                spawned: 0,
+                exited: 0,
+                exited_counts: (0, 0, 0),
+                this: this.clone(),
+            })
+        })
     }
 
-    /// Spawn a new isolate worker thread and run it
-    pub fn spawn(&mut self) -> IsolateChannel<T> {
-        let (ref_channel, worker_channel) = IsolateChannel::<T>::new();
+    /// Spawn a new isolate worker thread and run it, passing it the given spawn-time args.
+    /// If the args are not the Args type of the isolate, no worker is spawned.
+    pub fn spawn_with<A: Send + 'static>(
+        &mut self,
+        args: A,
+    ) -> Result<IsolateChannel<T>, IsolateRuntimeError> {
+        self.spawn_worker(args, None).map(|(_, channel)| channel)
+    }
+
+    /// Spawn a new isolate worker, returning both its identity and a channel to it.
+    /// A worker spawned by another worker is its child, and is stopped when its parent stops.
+    pub fn spawn_worker<A: Send + 'static>(
+        &mut self,
+        args: A,
+        parent: Option<IsolateIdentity>,
+    ) -> Result<(IsolateIdentity, IsolateChannel<T>), IsolateRuntimeError> {
+        self.spawn_as(IsolateIdentity::new(), args, parent)
+    }
+
+    /// Spawn a new isolate worker with a given identity, such as that of a worker that exited
+    /// earlier. The identity can't be in use by a running worker of this runtime.
+    pub fn spawn_as<A: Send + 'static>(
+        &mut self,
+        worker_identity: IsolateIdentity,
+        args: A,
+        parent: Option<IsolateIdentity>,
+    ) -> Result<(IsolateIdentity, IsolateChannel<T>), IsolateRuntimeError> {
+        self.spawn_tagged(worker_identity, args, parent, &[])
+    }
+
+    /// Spawn a new isolate worker with a given identity, carrying tags from the start
+    pub fn spawn_tagged<A: Send + 'static>(
+        &mut self,
+        worker_identity: IsolateIdentity,
+        args: A,
+        parent: Option<IsolateIdentity>,
+        tags: &[(&str, &str)],
+    ) -> Result<(IsolateIdentity, IsolateChannel<T>), IsolateRuntimeError> {
+        if self.refs.contains_key(&worker_identity) {
+            return Err(IsolateRuntimeError::IdentityInUse(worker_identity));
+        }
+        let name = self.registry.as_ref().map(|(name, _)| name.clone());
+        #[cfg(feature = "tracing")]
+        let runtime_name = name.clone().unwrap_or_default();
+        #[cfg(feature = "tracing")]
+        let _spawn = tracing::info_span!(
+            "isolate.spawn",
+            runtime = %runtime_name,
+            worker = %worker_identity
+        )
+        .entered();
+
+        let (mut ref_channel, worker_channel, staged) = match self.test_hook {
+            // Under the test scheduler, messages to the worker are staged and handed over
+            // through an inbox with no capacity, one at a time.
+            Some(_) => {
+                let (stage_s, stage_r) = unbounded();
+                let (inbox_s, inbox_r) = bounded(0);
+                let (output_s, output_r) = unbounded();
+                (
+                    IsolateChannel::from(stage_s, output_r),
+                    IsolateChannel::from(output_s, inbox_r),
+                    Some((stage_r, inbox_s)),
+                )
+            }
+            None => {
+                let (ref_channel, worker_channel) = IsolateChannel::<T>::new();
+                let (ref_lanes, worker_lanes) = IsolatePriorityLanes::pair();
+                (
+                    ref_channel.with_lanes(ref_lanes),
+                    worker_channel.with_lanes(worker_lanes),
+                    None,
+                )
+            }
+        };
+        let inbox = worker_channel.receiver.clone();
+        let lanes = worker_channel.lanes().cloned();
+        if let Some(route) = self.dead_letters.as_ref() {
+            ref_channel = ref_channel.with_dead_letters(worker_identity, route.clone());
+        }
+        // Messages the worker's identity didn't get to last time are waiting in its mailbox
+        // before anything new; workers under the test scheduler don't have durable mailboxes
+        let mut receipt = None;
+        if let (Some(mailbox), None) = (self.mailbox.as_ref(), staged.as_ref()) {
+            let (sender, worker_receipt) = mailbox
+                .open(&worker_identity)
+                .map_err(IsolateRuntimeError::MailboxError)?;
+            ref_channel = ref_channel.with_mailbox(sender);
+            receipt = Some(Arc::new(worker_receipt));
+        }
+        #[cfg(feature = "tracing")]
+        let (ref_channel, worker_channel) = {
+            let (ref_trace, worker_trace) = IsolateChannelTrace::pair(&runtime_name, worker_identity);
+            (
+                ref_channel.with_trace(ref_trace),
+                worker_channel.with_trace(worker_trace),
+            )
+        };
 
         // Handle worker
-        let worker_identity = IsolateIdentity::new();
-        let mut worker = self.isolate.spawn(worker_identity.clone(), worker_channel);
+        let state = Arc::new(IsolateWorkerState::new());
+        tags.iter().for_each(|(key, value)| state.set_tag(key, value));
+        let mut ref_channel = ref_channel.with_worker(state.clone());
+        if let Some(limit) = self.rate_limit.as_ref() {
+            ref_channel = ref_channel.throttled(limit.clone());
+        }
+        let context = IsolateContext::new(
+            worker_identity,
+            worker_channel,
+            state.clone(),
+            self.this.clone(),
+            self.registry.clone(),
+            self.tree.clone(),
+        );
+        let context = match receipt.as_ref() {
+            Some(receipt) => context.with_mailbox(receipt.clone()),
+            None => context,
+        };
+        let context = match self.coalescing {
+            Some(window) => context.with_coalescing(window),
+            None => context,
+        };
+        let worker = self.isolate.spawn_any(context, Box::new(args))?;
+        self.tree.add(worker_identity, parent, name, state.clone());
+        let tree = self.tree.clone();
+        let runtime = self.this.clone();
+        let dead_letters = self.dead_letters.clone();
+        let worker_state = state.clone();
+        #[cfg(feature = "tracing")]
+        let worker_span = tracing::info_span!(
+            "isolate.worker",
+            runtime = %runtime_name,
+            worker = %worker_identity
+        );
+        #[cfg(feature = "tracing")]
+        let exit_span = worker_span.clone();
+        let mut guard = IsolateExitGuard::new(move |reason| {
+            worker_state.set_exited();
+            let reason = match reason {
+                IsolateExitReason::Normal => worker_state.take_reason().unwrap_or(reason),
+                _ => reason,
+            };
+            #[cfg(feature = "tracing")]
+            tracing::info!(parent: &exit_span, reason = ?reason, "worker exited");
+
+            // A worker that returned normally is done with the message it was handling; after
+            // a panic or failure the message is delivered again
+            if let Some(receipt) = receipt {
+                if reason == IsolateExitReason::Normal {
+                    receipt.complete();
+                }
+                receipt.close();
+            }
+
+            // Leave the tree before the runtime lets go of the worker, so anyone waiting on the
+            // runtime sees the tree without it
+            tree.exited(&worker_identity);
+            IsolateRuntimeShared::exited(&runtime, &worker_identity, reason, &worker_state);
+
+            // Anything the worker didn't get to is undeliverable now, except for durable
+            // messages, which stay in the mailbox for the next worker with this identity
+            if let Some(route) = dead_letters {
+                let urgent = lanes.map(|lanes| lanes.drain()).unwrap_or_default();
+                urgent.into_iter().chain(inbox.try_iter()).for_each(|message| {
+                    route.post(
+                        worker_identity,
+                        IsolateDeadLetterReason::Unprocessed,
+                        message,
+                    );
+                });
+            }
+        });
         let handle = thread::spawn(move || {
-            (worker)();
+            isolate_current_worker::enter(worker_identity);
+            #[cfg(feature = "tracing")]
+            let _worker = worker_span.entered();
+            #[cfg(feature = "tracing")]
+            tracing::info!("worker started");
+
+            // The worker is dropped inside the closure, closing its end of the channel before
+            // the exit handler runs.
+            let result = panic::catch_unwind(AssertUnwindSafe(move || {
+                let mut worker = worker;
+                (worker)();
+            }));
+            if let Err(payload) = result {
+                guard.set_reason(IsolateExitReason::panicked(payload.as_ref()));
+            }
         });
 
+        if let (Some(hook), Some((stage, inbox))) = (self.test_hook.as_ref(), staged) {
+            hook(worker_identity, stage, inbox, self.dead_letters.clone());
+        }
+
         // Keep reference
         let consumer_channel = ref_channel.clone();
         self.refs.insert(
@@ -38,9 +297,329 @@ impl<T: Send + 'static> IsolateRuntimeShared<T> {
             IsolateRef {
                 channel: ref_channel,
                 handle,
+                state,
             },
         );
+        self.spawned += 1;
+
+        Ok((worker_identity, consumer_channel))
+    }
+
+    /// Drop the runtime reference to a worker; it will no longer be found by identity, and will
+    /// see its channel close once every other open connection to it is dropped.
+    pub fn release(&mut self, identity: &IsolateIdentity) -> bool {
+        match self.refs.remove(identity) {
+            Some(r) => {
+                // Threads that have finished don't need joining any more
+                self.released.retain(|handle| !handle.is_finished());
+                self.released.push(r.handle);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Ask a worker and every worker below it to stop, returning false if the worker isn't
+    /// bound to this runtime
+    pub fn stop(&self, identity: &IsolateIdentity) -> bool {
+        self.refs.contains_key(identity) && self.tree.stop(identity)
+    }
+
+    /// Ask every worker of this runtime, and every worker below them, to stop
+    pub fn stop_all(&self) {
+        self.refs.keys().for_each(|identity| {
+            self.tree.stop(identity);
+        });
+    }
+
+    /// Ask a worker and every worker below it to stop, and release it from the runtime, returning
+    /// false if the worker isn't bound to this runtime
+    pub fn kill(&mut self, identity: &IsolateIdentity) -> bool {
+        self.stop(identity) && self.release(identity)
+    }
+
+    /// Take a snapshot of the counters of this runtime and its running workers
+    pub fn metrics(&self) -> IsolateRuntimeMetrics {
+        let mut workers: Vec<_> = self
+            .refs
+            .iter()
+            .map(|(identity, r)| {
+                let recorder = r.state.recorder();
+                let (sent, received) = recorder.counts();
+                IsolateWorkerMetrics {
+                    identity: *identity,
+                    sent,
+                    received,
+                    throttled: recorder.throttled_count(),
+                    mailbox: r.channel.queued(),
+                    latency: recorder.latency(),
+                }
+            })
+            .collect();
+        workers.sort_by_key(|w| w.identity.to_string());
+        let (sent, received, throttled) = self.exited_counts;
+        IsolateRuntimeMetrics {
+            name: self.registry.as_ref().map(|(name, _)| name.clone()),
+            spawned: self.spawned,
+            exited: self.exited,
+            sent: sent + workers.iter().map(|w| w.sent).sum::<u64>(),
+            received: received + workers.iter().map(|w| w.received).sum::<u64>(),
+            throttled: throttled + workers.iter().map(|w| w.throttled).sum::<u64>(),
+            workers,
+        }
+    }
+
+    /// Return what is known about each running worker, with a channel to it
+    pub fn workers(&self) -> Vec<(IsolateWorkerInfo, IsolateChannel<T>)> {
+        let runtime = self.registry.as_ref().map(|(name, _)| name.clone());
+        let mut workers: Vec<_> = self
+            .refs
+            .iter()
+            .map(|(identity, r)| {
+                let info = IsolateWorkerInfo {
+                    identity: *identity,
+                    parent: self.tree.parent(identity),
+                    runtime: runtime.clone(),
+                    tags: r.state.tags(),
+                };
+                (info, r.channel.clone())
+            })
+            .collect();
+        workers.sort_by_key(|(info, _)| info.identity.to_string());
+        workers
+    }
+
+    /// Return the tree of workers this runtime adds its workers to
+    pub fn tree(&self) -> IsolateTree {
+        self.tree.clone()
+    }
+
+    /// Set the registry this runtime is bound to, so its workers can find it; its workers join
+    /// the tree of the registry.
+    pub fn bind_registry(&mut self, registry: IsolateContextRegistry, tree: IsolateTree) {
+        self.registry = Some(registry);
+        self.tree = tree;
+    }
+
+    /// Set the dead letter sink for messages that can't be delivered to workers of this runtime
+    pub fn bind_dead_letters(&mut self, route: Arc<IsolateDeadLetterRoute>) {
+        self.dead_letters = Some(route);
+    }
+
+    /// Keep messages sent to workers spawned from now on in a durable mailbox
+    pub fn bind_mailbox(&mut self, mailbox: IsolateMailboxBinding<T>) {
+        self.mailbox = Some(mailbox);
+    }
+
+    /// Limit the rate of messages to each worker spawned from now on
+    pub fn bind_rate_limit(&mut self, limit: IsolateRateLimit) {
+        self.rate_limit = Some(limit);
+    }
+
+    /// Let workers spawned from now on wait out a window for more messages when receiving a
+    /// batch
+    pub fn bind_coalescing(&mut self, window: Duration) {
+        self.coalescing = Some(window);
+    }
+
+    /// Return the timer of this runtime, to tell the time by; under the test scheduler this runs
+    /// on virtual time
+    pub fn clock(&self) -> IsolateTimer {
+        self.timer.clone().unwrap_or_default()
+    }
+
+    /// Schedule timers for workers of this runtime on a timer shared with other runtimes
+    pub fn bind_timer(&mut self, timer: IsolateTimer) {
+        self.timer = Some(timer);
+    }
+
+    /// Run workers spawned from now on under the test scheduler, with timers on virtual time
+    pub fn bind_test(&mut self, hook: IsolateTestHook<T>, timer: IsolateTimer) {
+        self.test_hook = Some(hook);
+        self.timer = Some(timer);
+    }
+
+    /// Post a message that couldn't be delivered to a worker to the dead letter sink, returning
+    /// false if there is no sink for this runtime and the message was dropped.
+    pub fn dead_letter(
+        &self,
+        identity: IsolateIdentity,
+        reason: IsolateDeadLetterReason,
+        message: T,
+    ) -> bool {
+        match self.dead_letters.as_ref() {
+            Some(route) => {
+                route.post(identity, reason, message);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drop the runtime references to every worker, and return their thread handles to join
+    pub fn take_handles(&mut self) -> Vec<JoinHandle<()>> {
+        self.refs
+            .drain()
+            .map(|(_, r)| r.handle)
+            .chain(self.released.drain(..))
+            .collect()
+    }
+
+    /// Schedule messages for a worker from the runtime timer thread, after a delay and then
+    /// every period if there is one. The timer stops when next_message returns None, or when
+    /// the worker exits.
+    pub fn schedule(
+        &mut self,
+        identity: &IsolateIdentity,
+        delay: Duration,
+        period: Option<Duration>,
+        mut next_message: impl FnMut() -> Option<T> + Send + 'static,
+    ) -> Result<IsolateTimerHandle, IsolateRuntimeError> {
+        if !self.refs.contains_key(identity) {
+            return Err(IsolateRuntimeError::NoMatchingIdentity);
+        }
+
+        // The timer only holds a weak reference to the runtime, so pending timers don't keep the
+        // worker channel open.
+        let runtime = self.this.clone();
+        let target = *identity;
+        let dead_letters = self.dead_letters.clone();
+        let action = Box::new(move || {
+            let channel = runtime.upgrade().and_then(|shared| match shared.lock() {
+                Ok(inner) => inner.refs.get(&target).map(|r| r.channel.clone()),
+                Err(_) => None,
+            });
+            let (channel, message) = match (channel, next_message()) {
+                (Some(channel), Some(message)) => (channel, message),
+                _ => return false,
+            };
+            // The timer only stops once the worker has gone; a message over a rate limit goes to
+            // the dead letters without waiting, since the timer thread is shared, and the next
+            // one is sent as usual
+            match channel.try_send_or_return(message) {
+                Ok(None) => true,
+                Ok(Some(message)) => {
+                    if let Some(route) = dead_letters.as_ref() {
+                        route.post(target, IsolateDeadLetterReason::WorkerExited, message);
+                    }
+                    false
+                }
+                Err(IsolateChannelError::Disconnected) => false,
+                Err(_) => true,
+            }
+        });
+
+        let timer = self.timer.get_or_insert_with(IsolateTimer::new);
+        let handle = match period {
+            Some(period) => timer.interval(period, action),
+            None => timer.after(delay, action),
+        };
+
+        let handles = self.timers.entry(target).or_default();
+        handles.retain(|h| !h.is_cancelled());
+        handles.push(handle.clone());
+        Ok(handle)
+    }
+
+    /// Notify a watcher when a target worker in this runtime exits
+    pub fn watch(
+        &mut self,
+        target: &IsolateIdentity,
+        watch: IsolateWatch,
+    ) -> Result<(), IsolateRuntimeError> {
+        if !self.refs.contains_key(target) {
+            return Err(IsolateRuntimeError::NoMatchingIdentity);
+        }
+        self.watches.entry(*target).or_default().push(watch);
+        Ok(())
+    }
+
+    /// Stop notifying a watcher about a target worker, returning false if it wasn't watching
+    pub fn unwatch(
+        &mut self,
+        watcher: &IsolateIdentity,
+        target: &IsolateIdentity,
+        linked: bool,
+    ) -> bool {
+        match self.watches.get_mut(target) {
+            Some(watches) => {
+                let count = watches.len();
+                watches.retain(|w| w.watcher != *watcher || w.linked != linked);
+                watches.len() != count
+            }
+            None => false,
+        }
+    }
+
+    /// Find the channel to notify a watching worker of an exit on; if the worker was notified
+    /// over a link, its own exit will be treated as abnormal.
+    pub fn watcher_channel(
+        &mut self,
+        watcher: &IsolateIdentity,
+        down: &IsolateDown,
+        linked: bool,
+    ) -> Option<IsolateChannel<T>> {
+        let channel = self.refs.get(watcher).map(|r| r.channel.clone())?;
+        if linked {
+            self.linked_exits.insert(*watcher, down.identity);
+        }
+        Some(channel)
+    }
+
+    /// Add a hook that is invoked with the identity of each worker that exits
+    pub fn add_exit_hook(&mut self, hook: impl Fn(&IsolateIdentity) + Send + Sync + 'static) {
+        self.exit_hooks.push(Arc::new(hook));
+    }
+
+    /// Invoked from the worker thread once a worker has exited; the runtime lock is released
+    /// before watchers are notified and the exit hooks run, so they are free to use the runtime.
+    fn exited(
+        runtime: &Weak<Mutex<IsolateRuntimeShared<T>>>,
+        identity: &IsolateIdentity,
+        reason: IsolateExitReason,
+        state: &IsolateWorkerState,
+    ) {
+        let shared = match runtime.upgrade() {
+            Some(shared) => shared,
+            None => return,
+        };
+        let (down, notifications, hooks) = match shared.lock() {
+            Ok(mut inner) => {
+                // The worker still has its exit to finish, so keep its thread to join
+                inner.release(identity);
+                let (sent, received) = state.recorder().counts();
+                inner.exited += 1;
+                inner.exited_counts.0 += sent;
+                inner.exited_counts.1 += received;
+                inner.exited_counts.2 += state.recorder().throttled_count();
+                if let Some(handles) = inner.timers.remove(identity) {
+                    handles.iter().for_each(|h| h.cancel());
+                }
 
-        return consumer_channel;
+                // A worker that returns after being told a linked worker failed has failed too
+                let reason = match inner.linked_exits.remove(identity) {
+                    Some(origin) if !reason.is_abnormal() => IsolateExitReason::Linked(origin),
+                    _ => reason,
+                };
+                let down = IsolateDown {
+                    identity: *identity,
+                    reason,
+                };
+                let watches = inner.watches.remove(identity).unwrap_or_default();
+                inner
+                    .watches
+                    .values_mut()
+                    .for_each(|w| w.retain(|w| w.watcher != *identity));
+                let notifications: Vec<_> = watches
+                    .into_iter()
+                    .filter(|w| w.notifies(&down))
+                    .map(|w| w.notify)
+                    .collect();
+                (down, notifications, inner.exit_hooks.clone())
+            }
+            Err(_) => return,
+        };
+        notifications.iter().for_each(|notify| notify(&down));
+        hooks.iter().for_each(|hook| hook(identity));
     }
 }
@@ -0,0 +1,20 @@
+use crate::IsolateDown;
+use crate::IsolateIdentity;
+use std::sync::Arc;
+
+/// Delivers a down notification to a watching worker, which may be in another runtime
+pub type IsolateNotify = Arc<dyn Fn(&IsolateDown) + Send + Sync + 'static>;
+
+/// A worker watching another worker for its exit, either by monitor or by link
+pub struct IsolateWatch {
+    pub watcher: IsolateIdentity,
+    pub linked: bool,
+    pub notify: IsolateNotify,
+}
+
+impl IsolateWatch {
+    /// Return true if the watcher should be told about an exit; links only carry abnormal exits
+    pub fn notifies(&self, down: &IsolateDown) -> bool {
+        !self.linked || down.reason.is_abnormal()
+    }
+}
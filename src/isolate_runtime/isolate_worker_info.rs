@@ -0,0 +1,15 @@
+use crate::IsolateIdentity;
+use std::collections::HashMap;
+
+/// What is known about a running worker, for picking workers out of a runtime
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsolateWorkerInfo {
+    /// The worker
+    pub identity: IsolateIdentity,
+    /// The worker that spawned it, if it was spawned by a worker
+    pub parent: Option<IsolateIdentity>,
+    /// The name of the runtime the worker runs in, if it is bound to a registry
+    pub runtime: Option<String>,
+    /// The tags the worker was spawned with or has set on itself
+    pub tags: HashMap<String, String>,
+}
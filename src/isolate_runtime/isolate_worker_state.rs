@@ -0,0 +1,155 @@
+use crate::isolate_metrics::isolate_metrics_recorder::IsolateMetricsRecorder;
+use crate::IsolateCancellationToken;
+use crate::IsolateExitReason;
+use crossbeam::{bounded, Receiver, Sender, TryRecvError};
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+
+/// The state of a worker shared between its context and the runtime; a request to stop the
+/// worker disconnects the stop channel, so a worker waiting on it wakes up.
+pub struct IsolateWorkerState {
+    stop: Mutex<Option<Sender<()>>>,
+    stopped: Receiver<()>,
+    reason: Mutex<Option<IsolateExitReason>>,
+    recorder: IsolateMetricsRecorder,
+    machine_state: Mutex<Option<Box<dyn Any + Send>>>,
+    tags: Mutex<HashMap<String, String>>,
+    reads_lanes: AtomicBool,
+    exited: AtomicBool,
+    taken: (Sender<()>, Receiver<()>),
+}
+
+impl IsolateWorkerState {
+    pub fn new() -> IsolateWorkerState {
+        let (stop, stopped) = bounded(0);
+        IsolateWorkerState {
+            stop: Mutex::new(Some(stop)),
+            stopped,
+            reason: Mutex::new(None),
+            recorder: IsolateMetricsRecorder::new(),
+            machine_state: Mutex::new(None),
+            tags: Mutex::new(HashMap::new()),
+            reads_lanes: AtomicBool::new(false),
+            exited: AtomicBool::new(false),
+            taken: bounded(1),
+        }
+    }
+
+    /// Ask the worker to stop, returning false if it was already asked to
+    pub fn stop(&self) -> bool {
+        match self.stop.lock() {
+            Ok(mut stop) => stop.take().is_some(),
+            Err(_) => false,
+        }
+    }
+
+    /// Return true if the worker has been asked to stop
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.try_recv() == Err(TryRecvError::Disconnected)
+    }
+
+    /// Return a channel that disconnects when the worker is asked to stop
+    pub fn stopped(&self) -> &Receiver<()> {
+        &self.stopped
+    }
+
+    /// Return a token that is tripped when the worker is asked to stop
+    pub fn cancellation(&self) -> IsolateCancellationToken {
+        IsolateCancellationToken::new(self.stopped.clone())
+    }
+
+    /// Record that the worker receives through its context, which reads the urgent queues
+    pub fn receiving(&self) {
+        self.reads_lanes.store(true, Ordering::Relaxed);
+    }
+
+    /// Count a message the worker received through its context, waking anyone waiting for it
+    /// to take one
+    pub fn received(&self) {
+        self.recorder.received();
+        let _ = self.taken.0.try_send(());
+    }
+
+    /// Return a channel that has a message once the worker has received through its context
+    /// since it was last read, for a sender waiting for room in the worker's inbox
+    pub fn taken(&self) -> &Receiver<()> {
+        &self.taken.1
+    }
+
+    /// Return true once the worker has received through its context; until then urgent messages
+    /// go to its inbox, in case it reads the channel directly
+    pub fn reads_lanes(&self) -> bool {
+        self.reads_lanes.load(Ordering::Relaxed)
+    }
+
+    /// Record that the worker has returned, and no longer receives messages
+    pub fn set_exited(&self) {
+        self.exited.store(true, Ordering::Relaxed);
+    }
+
+    /// Return true once the worker has returned, so messages to it are undeliverable
+    pub fn has_exited(&self) -> bool {
+        self.exited.load(Ordering::Relaxed)
+    }
+
+    /// Set the reason the worker will report when it exits
+    pub fn set_reason(&self, reason: IsolateExitReason) {
+        if let Ok(mut current) = self.reason.lock() {
+            *current = Some(reason);
+        }
+    }
+
+    /// Return the counters the worker updates as it runs
+    pub fn recorder(&self) -> &IsolateMetricsRecorder {
+        &self.recorder
+    }
+
+    /// Take the reason the worker set for its exit, if it set one
+    pub fn take_reason(&self) -> Option<IsolateExitReason> {
+        match self.reason.lock() {
+            Ok(mut reason) => reason.take(),
+            Err(_) => None,
+        }
+    }
+
+    /// Set the state a state machine worker is in
+    pub fn set_machine_state(&self, state: Box<dyn Any + Send>) {
+        if let Ok(mut current) = self.machine_state.lock() {
+            *current = Some(state);
+        }
+    }
+
+    /// Return the state a state machine worker is in, if it is one with states of this type
+    pub fn machine_state<S: Clone + 'static>(&self) -> Option<S> {
+        let current = self.machine_state.lock().ok()?;
+        current.as_ref()?.downcast_ref::<S>().cloned()
+    }
+
+    /// Set a tag on the worker, replacing any value it had
+    pub fn set_tag(&self, key: &str, value: &str) {
+        if let Ok(mut tags) = self.tags.lock() {
+            tags.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    /// Remove a tag from the worker, returning the value it had
+    pub fn remove_tag(&self, key: &str) -> Option<String> {
+        self.tags.lock().ok()?.remove(key)
+    }
+
+    /// Return the tags on the worker
+    pub fn tags(&self) -> HashMap<String, String> {
+        match self.tags.lock() {
+            Ok(tags) => tags.clone(),
+            Err(_) => HashMap::new(),
+        }
+    }
+
+    /// Return the value of a tag on the worker
+    pub fn tag(&self, key: &str) -> Option<String> {
+        self.tags.lock().ok()?.get(key).cloned()
+    }
+}
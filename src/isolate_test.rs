@@ -0,0 +1,282 @@
+pub(crate) mod isolate_test_delivery;
+pub(crate) mod isolate_test_mailbox;
+
+use crate::isolate_test::isolate_test_mailbox::IsolateTestMailbox;
+use crate::isolate_test::isolate_test_mailbox::IsolateTestMailboxOf;
+use crate::isolate_timer::IsolateTimer;
+use crate::IsolateIdentity;
+use crate::IsolateRuntimeError;
+use crate::IsolateRuntimeRef;
+use crate::IsolateTestDelivery;
+use crossbeam::{unbounded, Receiver, Sender};
+use std::fmt::Debug;
+use std::time::Duration;
+use std::time::Instant;
+
+/// How long a worker may run on a single message before the scheduler gives up on it
+const DEFAULT_WATCHDOG: Duration = Duration::from_secs(10);
+
+/// IsolateTestScheduler runs the workers of attached runtimes deterministically, for tests.
+/// Messages sent to workers are held until the scheduler delivers them, one message at a time,
+/// in an order picked from a seed; runtime timers run on virtual time that only moves when
+/// the scheduler is advanced.
+/// Workers must block on their receiver when they are waiting for work, and only see their
+/// channel close once the scheduler is shut down or dropped.
+pub struct IsolateTestScheduler {
+    mailboxes: Vec<Box<dyn IsolateTestMailbox>>,
+    spawned: Receiver<Box<dyn IsolateTestMailbox>>,
+    spawn: Sender<Box<dyn IsolateTestMailbox>>,
+    deliveries: Vec<IsolateTestDelivery>,
+    timer: IsolateTimer,
+    start: Instant,
+    state: u64,
+    watchdog: Duration,
+}
+
+impl IsolateTestScheduler {
+    /// Create a new scheduler; the same seed always gives the same message interleaving
+    pub fn new(seed: u64) -> IsolateTestScheduler {
+        let (spawn, spawned) = unbounded();
+        let timer = IsolateTimer::new_virtual();
+        IsolateTestScheduler {
+            mailboxes: Vec::new(),
+            spawned,
+            spawn,
+            deliveries: Vec::new(),
+            start: timer.now(),
+            timer,
+            state: seed.max(1),
+            watchdog: DEFAULT_WATCHDOG,
+        }
+    }
+
+    /// Set how long to wait for a worker to finish with a message before panicking
+    pub fn set_watchdog(&mut self, watchdog: Duration) {
+        self.watchdog = watchdog;
+    }
+
+    /// Run the workers a runtime spawns from now on under this scheduler
+    pub fn attach<T: Send + Debug + 'static>(
+        &self,
+        runtime: &IsolateRuntimeRef<T>,
+    ) -> Result<(), IsolateRuntimeError> {
+        let spawn = self.spawn.clone();
+        runtime.bind_test(
+            Box::new(move |identity, staged, inbox, dead_letters| {
+                let mailbox = IsolateTestMailboxOf::new(identity, staged, inbox, dead_letters);
+                let _ = spawn.send(Box::new(mailbox));
+            }),
+            self.timer.clone(),
+        )
+    }
+
+    /// Return the identities of the workers running under this scheduler, in spawn order
+    pub fn workers(&mut self) -> Vec<IsolateIdentity> {
+        self.mailboxes.extend(self.spawned.try_iter());
+        self.mailboxes.iter().map(|m| m.identity()).collect()
+    }
+
+    /// Deliver messages until every worker is waiting on an empty mailbox, or has exited
+    pub fn run_until_idle(&mut self) {
+        loop {
+            self.mailboxes.extend(self.spawned.try_iter());
+            for mailbox in self.mailboxes.iter() {
+                if !mailbox.wait_ready(self.watchdog) {
+                    panic!(
+                        "Worker {} did not wait for a message within {:?}",
+                        mailbox.identity(),
+                        self.watchdog
+                    );
+                }
+            }
+
+            // Workers may have spawned more workers while they ran
+            if !self.spawned.is_empty() {
+                continue;
+            }
+
+            let pending: Vec<usize> = (0..self.mailboxes.len())
+                .filter(|i| self.mailboxes[*i].has_pending())
+                .collect();
+            if pending.is_empty() {
+                return;
+            }
+
+            let index = pending[(self.next_random() % pending.len() as u64) as usize];
+            let mailbox = &mut self.mailboxes[index];
+            if let Some(message) = mailbox.deliver() {
+                self.deliveries.push(IsolateTestDelivery {
+                    identity: mailbox.identity(),
+                    message,
+                    at: self.timer.now() - self.start,
+                });
+            }
+            if mailbox.is_exited() {
+                self.mailboxes.remove(index);
+            }
+        }
+    }
+
+    /// Move virtual time forward, firing each timer due on the way and running the workers
+    /// until idle after each one.
+    pub fn advance(&mut self, duration: Duration) {
+        let until = self.timer.now() + duration;
+        self.run_until_idle();
+        while self.timer.fire_next(until) {
+            self.run_until_idle();
+        }
+    }
+
+    /// Run until idle, then close the channel to every worker, so the runtimes can be waited on
+    pub fn shutdown(&mut self) {
+        self.run_until_idle();
+        self.mailboxes.clear();
+    }
+
+    /// Return the virtual time elapsed since the scheduler was created
+    pub fn now(&self) -> Duration {
+        self.timer.now() - self.start
+    }
+
+    /// Return every message delivered so far, in delivery order
+    pub fn deliveries(&self) -> &[IsolateTestDelivery] {
+        &self.deliveries
+    }
+
+    /// Return the messages delivered so far to a specific worker, in delivery order
+    pub fn deliveries_to(&self, identity: &IsolateIdentity) -> Vec<&IsolateTestDelivery> {
+        self.deliveries
+            .iter()
+            .filter(|d| d.identity == *identity)
+            .collect()
+    }
+
+    /// Panic unless a message with the given Debug output was delivered to a specific worker
+    pub fn assert_delivered(&self, identity: &IsolateIdentity, message: &str) {
+        let delivered = self.deliveries_to(identity);
+        if !delivered.iter().any(|d| d.message == message) {
+            panic!(
+                "{} was not delivered to worker {}; delivered: {:?}",
+                message,
+                identity,
+                delivered.iter().map(|d| &d.message).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    /// Panic unless a specific worker was delivered exactly these messages, in this order
+    pub fn assert_delivered_seq(&self, identity: &IsolateIdentity, messages: &[&str]) {
+        let delivered: Vec<&str> = self
+            .deliveries_to(identity)
+            .iter()
+            .map(|d| d.message.as_str())
+            .collect();
+        assert_eq!(delivered, messages, "Deliveries to worker {}", identity);
+    }
+
+    fn next_random(&mut self) -> u64 {
+        // xorshift64
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IsolateTestScheduler;
+    use crate::Isolate;
+    use crate::IsolateContext;
+    use crate::IsolateRuntime;
+    use std::time::Duration;
+
+    struct TestIsolate {}
+
+    impl Isolate<u32> for TestIsolate {
+        type Args = ();
+
+        fn spawn(&self, context: IsolateContext<u32>, _: ()) -> Box<dyn FnMut() + Send + 'static> {
+            let channel = context.channel().clone();
+            Box::new(move || {
+                while let Ok(v) = channel.receiver.recv() {
+                    if v == 0 {
+                        break;
+                    }
+                    let _ = channel.sender.send(v);
+                }
+            })
+        }
+    }
+
+    fn interleaving(seed: u64) -> Vec<usize> {
+        let mut scheduler = IsolateTestScheduler::new(seed);
+        let mut runtime = IsolateRuntime::new(TestIsolate {});
+        scheduler.attach(&runtime.as_ref()).unwrap();
+
+        let channels: Vec<_> = (0..3).map(|_| runtime.spawn().unwrap()).collect();
+        for i in 1..5 {
+            channels.iter().for_each(|c| c.sender.send(i).unwrap());
+        }
+        scheduler.run_until_idle();
+
+        let workers = scheduler.workers();
+        scheduler
+            .deliveries()
+            .iter()
+            .map(|d| workers.iter().position(|w| *w == d.identity).unwrap())
+            .collect()
+    }
+
+    #[test]
+    pub fn test_run_until_idle() {
+        let mut scheduler = IsolateTestScheduler::new(1);
+        let mut runtime = IsolateRuntime::new(TestIsolate {});
+        scheduler.attach(&runtime.as_ref()).unwrap();
+
+        let channel = runtime.spawn().unwrap();
+        channel.sender.send(1).unwrap();
+        channel.sender.send(2).unwrap();
+
+        // Nothing is delivered until the scheduler runs
+        assert!(channel.receiver.try_recv().is_err());
+        scheduler.run_until_idle();
+        assert_eq!(channel.receiver.try_iter().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(scheduler.deliveries().len(), 2);
+    }
+
+    #[test]
+    pub fn test_seeded_interleaving() {
+        assert_eq!(interleaving(7), interleaving(7));
+        assert_eq!(interleaving(7).len(), 12);
+    }
+
+    #[test]
+    pub fn test_advance() {
+        let mut scheduler = IsolateTestScheduler::new(1);
+        let mut runtime = IsolateRuntime::new(TestIsolate {});
+        scheduler.attach(&runtime.as_ref()).unwrap();
+
+        let channel = runtime.spawn().unwrap();
+        let identity = scheduler.workers()[0];
+        runtime
+            .as_ref()
+            .send_interval(&identity, Duration::from_secs(60), 5)
+            .unwrap();
+
+        scheduler.advance(Duration::from_secs(150));
+        assert_eq!(scheduler.now(), Duration::from_secs(150));
+        assert_eq!(channel.receiver.try_iter().collect::<Vec<_>>(), vec![5, 5]);
+
+        let times: Vec<_> = scheduler
+            .deliveries_to(&identity)
+            .iter()
+            .map(|d| d.at)
+            .collect();
+        assert_eq!(
+            times,
+            vec![Duration::from_secs(60), Duration::from_secs(120)]
+        );
+        scheduler.assert_delivered(&identity, "5");
+    }
+}
@@ -0,0 +1,13 @@
+use crate::IsolateIdentity;
+use std::time::Duration;
+
+/// A record of a message the test scheduler delivered to a worker
+#[derive(Debug, Clone)]
+pub struct IsolateTestDelivery {
+    /// The worker the message was delivered to
+    pub identity: IsolateIdentity,
+    /// The message, formatted with Debug
+    pub message: String,
+    /// The virtual time the message was delivered at, since the scheduler was created
+    pub at: Duration,
+}
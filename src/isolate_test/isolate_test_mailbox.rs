@@ -0,0 +1,104 @@
+use crate::isolate_dead_letters::isolate_dead_letter_route::IsolateDeadLetterRoute;
+use crate::IsolateDeadLetterReason;
+use crate::IsolateIdentity;
+use crossbeam::{Receiver, Select, Sender};
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The mailbox of a worker spawned under the test scheduler; messages sent to the worker are
+/// staged here until the scheduler hands them over one at a time.
+pub trait IsolateTestMailbox: Send {
+    /// The identity of the worker
+    fn identity(&self) -> IsolateIdentity;
+
+    /// Wait until the worker is blocked waiting for a message, or has exited.
+    /// Returns false if it is still busy once the timeout expires.
+    fn wait_ready(&self, timeout: Duration) -> bool;
+
+    /// Return true if there are staged messages for the worker
+    fn has_pending(&self) -> bool;
+
+    /// Hand the next staged message to the worker, returning it formatted with Debug.
+    /// Returns None if the worker has exited; anything still staged goes to the dead letter sink.
+    fn deliver(&mut self) -> Option<String>;
+
+    /// Return true once delivery to the worker has failed because it exited
+    fn is_exited(&self) -> bool;
+}
+
+/// The mailbox for workers with a specific message type
+pub struct IsolateTestMailboxOf<T: Send + 'static> {
+    identity: IsolateIdentity,
+    staged: Option<Receiver<T>>,
+    inbox: Sender<T>,
+    dead_letters: Option<Arc<IsolateDeadLetterRoute>>,
+}
+
+impl<T: Send + 'static> IsolateTestMailboxOf<T> {
+    /// Create a mailbox from the staging queue for a worker, and its rendezvous inbox
+    pub fn new(
+        identity: IsolateIdentity,
+        staged: Receiver<T>,
+        inbox: Sender<T>,
+        dead_letters: Option<Arc<IsolateDeadLetterRoute>>,
+    ) -> IsolateTestMailboxOf<T> {
+        IsolateTestMailboxOf {
+            identity,
+            staged: Some(staged),
+            inbox,
+            dead_letters,
+        }
+    }
+
+    /// The worker has exited; dropping the staging queue fails any further sends to it
+    fn close(&mut self, undelivered: T) {
+        let staged = self.staged.take();
+        if let Some(route) = self.dead_letters.as_ref() {
+            let reason = IsolateDeadLetterReason::WorkerExited;
+            route.post(self.identity, reason, undelivered);
+            if let Some(staged) = staged {
+                staged.try_iter().for_each(|message| {
+                    route.post(self.identity, reason, message);
+                });
+            }
+        }
+    }
+}
+
+impl<T: Send + Debug + 'static> IsolateTestMailbox for IsolateTestMailboxOf<T> {
+    fn identity(&self) -> IsolateIdentity {
+        self.identity
+    }
+
+    fn wait_ready(&self, timeout: Duration) -> bool {
+        // The inbox has no capacity, so it is only ready to send to while the worker is blocked
+        // receiving from it, or once the worker has dropped it on exit.
+        let mut select = Select::new();
+        select.send(&self.inbox);
+        select.ready_timeout(timeout).is_ok()
+    }
+
+    fn has_pending(&self) -> bool {
+        match self.staged.as_ref() {
+            Some(staged) => !staged.is_empty(),
+            None => false,
+        }
+    }
+
+    fn deliver(&mut self) -> Option<String> {
+        let message = self.staged.as_ref()?.try_recv().ok()?;
+        let text = format!("{:?}", message);
+        match self.inbox.send(message) {
+            Ok(_) => Some(text),
+            Err(err) => {
+                self.close(err.into_inner());
+                None
+            }
+        }
+    }
+
+    fn is_exited(&self) -> bool {
+        self.staged.is_none()
+    }
+}
@@ -0,0 +1,183 @@
+pub(crate) mod isolate_rate_limit;
+pub(crate) mod isolate_throttle_mode;
+
+use crate::isolate_runtime::isolate_current_worker;
+use crate::IsolateChannelError;
+use crate::IsolateIdentity;
+use crate::IsolateRateLimit;
+use crate::IsolateThrottleMode;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+/// The tokens left in a bucket, as of when it was last topped up
+struct IsolateTokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// How a message fared against a rate limit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IsolateAdmission {
+    /// The message was within the limit
+    Admitted,
+    /// The message was held back until it was within the limit
+    Delayed,
+    /// The message was over the limit, and must not be sent
+    Rejected,
+}
+
+/// IsolateThrottle applies a rate limit to the messages sent through a channel, with a token
+/// bucket for all senders or for each sending worker. Buckets that have filled up again are
+/// dropped, so there is only one for each worker that has sent recently.
+pub struct IsolateThrottle {
+    limit: IsolateRateLimit,
+    buckets: Mutex<HashMap<Option<IsolateIdentity>, IsolateTokenBucket>>,
+}
+
+impl IsolateThrottle {
+    pub fn new(limit: IsolateRateLimit) -> IsolateThrottle {
+        IsolateThrottle {
+            limit,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take a token for a message, waiting for one if the limit delays messages and `wait` is
+    /// set; without it, a message that would have to wait is rejected, so a caller delivering
+    /// to many workers is never held up by one of them. A limit that lets no messages through
+    /// rejects them, since waiting for a token would never end.
+    pub fn admit(&self, wait: bool) -> Result<IsolateAdmission, IsolateChannelError> {
+        if self.limit.messages == 0 || self.limit.burst == 0 {
+            return Ok(IsolateAdmission::Rejected);
+        }
+        let sender = if self.limit.per_sender {
+            isolate_current_worker::current()
+        } else {
+            None
+        };
+        let mut admission = IsolateAdmission::Admitted;
+        loop {
+            let wait_for = match self.take(sender)? {
+                Some(wait_for) => wait_for,
+                None => return Ok(admission),
+            };
+            match self.limit.mode {
+                IsolateThrottleMode::Delay if wait => {
+                    admission = IsolateAdmission::Delayed;
+                    thread::sleep(wait_for);
+                }
+                _ => return Ok(IsolateAdmission::Rejected),
+            }
+        }
+    }
+
+    /// Take a token from a sender's bucket, or return how long until there is one
+    fn take(
+        &self,
+        sender: Option<IsolateIdentity>,
+    ) -> Result<Option<Duration>, IsolateChannelError> {
+        let rate = f64::from(self.limit.messages) / self.limit.per.as_secs_f64();
+        let burst = f64::from(self.limit.burst);
+        let now = Instant::now();
+        let mut buckets = self
+            .buckets
+            .lock()
+            .map_err(|_| IsolateChannelError::InternalSyncError)?;
+        if !buckets.contains_key(&sender) {
+            // A full bucket is the same as a new one, so drop those of senders that have gone
+            // quiet, or exited, rather than keep one for every worker that ever sent
+            buckets.retain(|_, bucket| {
+                let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+                bucket.tokens + elapsed * rate < burst
+            });
+        }
+        let bucket = buckets.entry(sender).or_insert(IsolateTokenBucket {
+            tokens: burst,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(None);
+        }
+        Ok(Some(Duration::from_secs_f64((1.0 - bucket.tokens) / rate)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IsolateAdmission;
+    use super::IsolateThrottle;
+    use crate::isolate_runtime::isolate_current_worker;
+    use crate::IsolateIdentity;
+    use crate::IsolateRateLimit;
+    use crate::IsolateThrottleMode;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    #[test]
+    pub fn test_token_bucket() {
+        let throttle = IsolateThrottle::new(IsolateRateLimit::new(2, Duration::from_secs(60)));
+        assert_eq!(throttle.admit(true).unwrap(), IsolateAdmission::Admitted);
+        assert_eq!(throttle.admit(true).unwrap(), IsolateAdmission::Admitted);
+        assert_eq!(throttle.admit(true).unwrap(), IsolateAdmission::Rejected);
+
+        let limit = IsolateRateLimit::new(100, Duration::from_secs(1))
+            .burst(1)
+            .mode(IsolateThrottleMode::Delay);
+        let throttle = IsolateThrottle::new(limit);
+        let started = Instant::now();
+        assert_eq!(throttle.admit(true).unwrap(), IsolateAdmission::Admitted);
+        assert_eq!(throttle.admit(true).unwrap(), IsolateAdmission::Delayed);
+        assert!(started.elapsed() >= Duration::from_millis(5));
+
+        // Without waiting, a message that would be delayed is rejected instead
+        assert_eq!(throttle.admit(false).unwrap(), IsolateAdmission::Rejected);
+    }
+
+    #[test]
+    pub fn test_zero_limit_rejects() {
+        let limit =
+            IsolateRateLimit::new(0, Duration::from_secs(1)).mode(IsolateThrottleMode::Delay);
+        assert_eq!(
+            IsolateThrottle::new(limit).admit(true).unwrap(),
+            IsolateAdmission::Rejected
+        );
+
+        let limit = IsolateRateLimit::new(10, Duration::from_secs(1)).burst(0);
+        assert_eq!(
+            IsolateThrottle::new(limit).admit(true).unwrap(),
+            IsolateAdmission::Rejected
+        );
+    }
+
+    #[test]
+    pub fn test_drop_full_buckets() {
+        let limit = IsolateRateLimit::new(1, Duration::from_millis(20)).per_sender();
+        let throttle = Arc::new(IsolateThrottle::new(limit));
+        let admit_from_worker = |throttle: &Arc<IsolateThrottle>| {
+            let throttle = throttle.clone();
+            thread::spawn(move || {
+                isolate_current_worker::enter(IsolateIdentity::new());
+                throttle.admit(true).unwrap()
+            })
+            .join()
+            .unwrap()
+        };
+        assert_eq!(admit_from_worker(&throttle), IsolateAdmission::Admitted);
+        assert_eq!(admit_from_worker(&throttle), IsolateAdmission::Admitted);
+        assert_eq!(throttle.buckets.lock().unwrap().len(), 2);
+
+        // Once the earlier senders' buckets have filled up, a new sender replaces them
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(admit_from_worker(&throttle), IsolateAdmission::Admitted);
+        assert_eq!(throttle.buckets.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,47 @@
+use crate::IsolateThrottleMode;
+use std::time::Duration;
+
+/// IsolateRateLimit is a token bucket limit on messages: a number of messages per period, with
+/// bursts of up to `burst` messages after a quiet spell. By default messages over the limit are
+/// rejected, and the limit applies to all senders together.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsolateRateLimit {
+    pub(crate) messages: u32,
+    pub(crate) per: Duration,
+    pub(crate) burst: u32,
+    pub(crate) mode: IsolateThrottleMode,
+    pub(crate) per_sender: bool,
+}
+
+impl IsolateRateLimit {
+    /// Allow a number of messages per period, in bursts of up to the same number. A limit of no
+    /// messages, or a burst of none, rejects every message, in either mode.
+    pub fn new(messages: u32, per: Duration) -> IsolateRateLimit {
+        IsolateRateLimit {
+            messages,
+            per,
+            burst: messages,
+            mode: IsolateThrottleMode::Reject,
+            per_sender: false,
+        }
+    }
+
+    /// Set the largest burst of messages allowed at once
+    pub fn burst(mut self, burst: u32) -> IsolateRateLimit {
+        self.burst = burst;
+        self
+    }
+
+    /// Set what happens to messages over the limit
+    pub fn mode(mut self, mode: IsolateThrottleMode) -> IsolateRateLimit {
+        self.mode = mode;
+        self
+    }
+
+    /// Give each sending worker a limit of its own; messages sent from outside any worker share
+    /// one between them
+    pub fn per_sender(mut self) -> IsolateRateLimit {
+        self.per_sender = true;
+        self
+    }
+}
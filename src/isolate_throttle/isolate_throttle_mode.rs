@@ -0,0 +1,10 @@
+/// What happens to a message sent over a rate limit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IsolateThrottleMode {
+    /// Fail the send with `IsolateChannelError::Throttled`, passing the message to the dead
+    /// letter sink if there is one
+    Reject,
+    /// Block the sender until the message is within the limit; topics, routers, broadcasts
+    /// and timers don't wait, and reject the message instead
+    Delay,
+}
@@ -0,0 +1,271 @@
+pub(crate) mod isolate_timer_entry;
+pub(crate) mod isolate_timer_handle;
+pub(crate) mod isolate_timer_queue;
+
+use crate::isolate_timer::isolate_timer_entry::IsolateTimerAction;
+use crate::isolate_timer::isolate_timer_entry::IsolateTimerEntry;
+use crate::isolate_timer::isolate_timer_queue::IsolateTimerQueue;
+use crate::IsolateTimerHandle;
+use crossbeam::{unbounded, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+/// The state of a timer on virtual time, which only moves when it is advanced
+pub struct IsolateVirtualTime {
+    now: Instant,
+    queue: IsolateTimerQueue,
+}
+
+enum IsolateTimerMode {
+    /// The timer thread, once the first timer has been scheduled
+    Thread(Arc<Mutex<Option<Sender<IsolateTimerEntry>>>>),
+    Virtual(Arc<Mutex<IsolateVirtualTime>>),
+}
+
+/// IsolateTimer runs scheduled timers from a single thread; every runtime bound to a registry
+/// shares the timer of the registry, and a runtime on its own has a timer of its own.
+/// The thread is started when the first timer is scheduled, and halts when the timer and its
+/// clones are dropped. A virtual timer has no thread; its timers only fire when it is advanced.
+pub struct IsolateTimer {
+    mode: IsolateTimerMode,
+}
+
+impl IsolateTimer {
+    pub fn new() -> IsolateTimer {
+        IsolateTimer {
+            mode: IsolateTimerMode::Thread(Arc::new(Mutex::new(None))),
+        }
+    }
+
+    /// Create a timer on virtual time, starting now
+    pub fn new_virtual() -> IsolateTimer {
+        IsolateTimer {
+            mode: IsolateTimerMode::Virtual(Arc::new(Mutex::new(IsolateVirtualTime {
+                now: Instant::now(),
+                queue: IsolateTimerQueue::new(),
+            }))),
+        }
+    }
+
+    /// Run an action once after a delay
+    pub fn after(&self, delay: Duration, action: IsolateTimerAction) -> IsolateTimerHandle {
+        self.add(delay, None, action)
+    }
+
+    /// Run an action every period, until it is cancelled or the action returns false
+    pub fn interval(&self, period: Duration, action: IsolateTimerAction) -> IsolateTimerHandle {
+        self.add(period, Some(period), action)
+    }
+
+    /// Return the current time; for a virtual timer, this is the time it has been advanced to
+    pub fn now(&self) -> Instant {
+        match &self.mode {
+            IsolateTimerMode::Thread(_) => Instant::now(),
+            IsolateTimerMode::Virtual(time) => match time.lock() {
+                Ok(time) => time.now,
+                Err(_) => Instant::now(),
+            },
+        }
+    }
+
+    /// Fire the next virtual timer due at or before `until`, moving virtual time to its deadline.
+    /// Returns false if there are no more timers due; virtual time is then moved to `until`.
+    /// The timer lock isn't held while the timer fires, so timer actions can schedule timers.
+    pub fn fire_next(&self, until: Instant) -> bool {
+        let time = match &self.mode {
+            IsolateTimerMode::Virtual(time) => time,
+            IsolateTimerMode::Thread(_) => return false,
+        };
+        let entry = match time.lock() {
+            Ok(mut time) => match time.queue.pop_due(until) {
+                Some(entry) => {
+                    time.now = time.now.max(entry.deadline);
+                    entry
+                }
+                None => {
+                    time.now = time.now.max(until);
+                    return false;
+                }
+            },
+            Err(_) => return false,
+        };
+        if let Some(entry) = entry.fire() {
+            if let Ok(mut time) = time.lock() {
+                time.queue.push(entry);
+            }
+        }
+        true
+    }
+
+    fn add(
+        &self,
+        delay: Duration,
+        period: Option<Duration>,
+        action: IsolateTimerAction,
+    ) -> IsolateTimerHandle {
+        let handle = IsolateTimerHandle::new();
+        let mut entry = IsolateTimerEntry {
+            deadline: Instant::now() + delay,
+            period,
+            handle: handle.clone(),
+            action,
+        };
+        match &self.mode {
+            IsolateTimerMode::Thread(schedule) => match schedule.lock() {
+                Ok(mut schedule) => {
+                    let schedule = schedule.get_or_insert_with(|| {
+                        let (schedule, entries) = unbounded();
+                        thread::spawn(move || {
+                            IsolateTimer::run(entries);
+                        });
+                        schedule
+                    });
+                    if schedule.send(entry).is_err() {
+                        handle.cancel();
+                    }
+                }
+                Err(_) => handle.cancel(),
+            },
+            IsolateTimerMode::Virtual(time) => match time.lock() {
+                Ok(mut time) => {
+                    entry.deadline = time.now + delay;
+                    time.queue.push(entry);
+                }
+                Err(_) => handle.cancel(),
+            },
+        }
+        handle
+    }
+
+    /// The timer thread; wait for the next deadline, or for a new timer to be scheduled
+    fn run(entries: Receiver<IsolateTimerEntry>) {
+        let mut queue = IsolateTimerQueue::new();
+        loop {
+            let received = match queue.next_deadline() {
+                Some(deadline) => {
+                    entries.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                }
+                None => entries.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+
+            match received {
+                Ok(entry) => queue.push(entry),
+                Err(RecvTimeoutError::Disconnected) => break,
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+
+            let now = Instant::now();
+            while let Some(entry) = queue.pop_due(now) {
+                if let Some(entry) = entry.fire() {
+                    queue.push(entry);
+                }
+            }
+        }
+    }
+}
+
+impl Clone for IsolateTimer {
+    fn clone(&self) -> IsolateTimer {
+        IsolateTimer {
+            mode: match &self.mode {
+                IsolateTimerMode::Thread(schedule) => IsolateTimerMode::Thread(schedule.clone()),
+                IsolateTimerMode::Virtual(time) => IsolateTimerMode::Virtual(time.clone()),
+            },
+        }
+    }
+}
+
+impl Default for IsolateTimer {
+    fn default() -> Self {
+        IsolateTimer::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IsolateTimer;
+    use crossbeam::unbounded;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    #[test]
+    pub fn test_after() {
+        let timer = IsolateTimer::new();
+        let (sender, receiver) = unbounded();
+
+        let start = Instant::now();
+        let handle = timer.after(
+            Duration::from_millis(20),
+            Box::new(move || sender.send(()).is_ok()),
+        );
+        receiver.recv().unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+        assert!(handle.is_cancelled());
+    }
+
+    #[test]
+    pub fn test_interval_and_cancel() {
+        let timer = IsolateTimer::new();
+        let (sender, receiver) = unbounded();
+
+        let handle = timer.interval(
+            Duration::from_millis(5),
+            Box::new(move || sender.send(()).is_ok()),
+        );
+        for _ in 0..3 {
+            receiver.recv().unwrap();
+        }
+        handle.cancel();
+
+        // At most one tick may already have been in flight
+        let _ = receiver.try_recv();
+        assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+    }
+
+    #[test]
+    pub fn test_ordering() {
+        let timer = IsolateTimer::new();
+        let (sender, receiver) = unbounded();
+
+        let late = sender.clone();
+        timer.after(
+            Duration::from_millis(40),
+            Box::new(move || late.send(2).is_ok()),
+        );
+        timer.after(
+            Duration::from_millis(10),
+            Box::new(move || sender.send(1).is_ok()),
+        );
+        assert_eq!(receiver.recv().unwrap(), 1);
+        assert_eq!(receiver.recv().unwrap(), 2);
+    }
+
+    #[test]
+    pub fn test_virtual_time() {
+        let timer = IsolateTimer::new_virtual();
+        let (sender, receiver) = unbounded();
+
+        let start = timer.now();
+        timer.interval(
+            Duration::from_secs(10),
+            Box::new(move || sender.send(()).is_ok()),
+        );
+
+        // Nothing fires until time is advanced
+        assert!(!timer.fire_next(start + Duration::from_secs(5)));
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(timer.now(), start + Duration::from_secs(5));
+
+        assert!(timer.fire_next(start + Duration::from_secs(25)));
+        assert_eq!(timer.now(), start + Duration::from_secs(10));
+        assert!(timer.fire_next(start + Duration::from_secs(25)));
+        assert_eq!(timer.now(), start + Duration::from_secs(20));
+        assert!(!timer.fire_next(start + Duration::from_secs(25)));
+        assert_eq!(timer.now(), start + Duration::from_secs(25));
+        assert_eq!(receiver.try_iter().count(), 2);
+    }
+}
@@ -0,0 +1,34 @@
+use crate::IsolateTimerHandle;
+use std::time::Duration;
+use std::time::Instant;
+
+/// The action for a timer; it returns false if the timer should stop repeating
+pub type IsolateTimerAction = Box<dyn FnMut() -> bool + Send + 'static>;
+
+/// IsolateTimerEntry is a single scheduled timer
+pub struct IsolateTimerEntry {
+    pub deadline: Instant,
+    pub period: Option<Duration>,
+    pub handle: IsolateTimerHandle,
+    pub action: IsolateTimerAction,
+}
+
+impl IsolateTimerEntry {
+    /// Run the timer action, returning the entry again if it should be rescheduled
+    pub fn fire(mut self) -> Option<IsolateTimerEntry> {
+        if self.handle.is_cancelled() {
+            return None;
+        }
+        let again = (self.action)();
+        match self.period {
+            Some(period) if again && !self.handle.is_cancelled() => {
+                self.deadline += period;
+                Some(self)
+            }
+            _ => {
+                self.handle.cancel();
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// IsolateTimerHandle is returned when a timer is scheduled, and can be used to cancel it.
+/// A one-shot timer is also marked as cancelled once it has fired.
+#[derive(Clone, Debug)]
+pub struct IsolateTimerHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl IsolateTimerHandle {
+    pub fn new() -> IsolateTimerHandle {
+        IsolateTimerHandle {
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Cancel the timer; it will not fire again
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Check if the timer has been cancelled, or has finished
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+impl Default for IsolateTimerHandle {
+    fn default() -> Self {
+        IsolateTimerHandle::new()
+    }
+}
@@ -0,0 +1,120 @@
+use crate::isolate_timer::isolate_timer_entry::IsolateTimerEntry;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// IsolateTimerQueue orders scheduled timers by deadline; timers with the same deadline fire in
+/// the order they were scheduled. Cancelled timers are skipped when they reach the front, and
+/// swept out whenever the queue has doubled in size since the last sweep.
+pub struct IsolateTimerQueue {
+    queue: BinaryHeap<Reverse<(Instant, u64)>>,
+    pending: HashMap<u64, IsolateTimerEntry>,
+    next_id: u64,
+    sweep_at: usize,
+}
+
+/// The smallest queue that is swept for cancelled timers
+const SWEEP_MIN: usize = 64;
+
+impl IsolateTimerQueue {
+    pub fn new() -> IsolateTimerQueue {
+        IsolateTimerQueue {
+            queue: BinaryHeap::new(),
+            pending: HashMap::new(),
+            next_id: 0,
+            sweep_at: SWEEP_MIN,
+        }
+    }
+
+    /// Add a timer to the queue
+    pub fn push(&mut self, entry: IsolateTimerEntry) {
+        if self.pending.len() >= self.sweep_at {
+            self.sweep();
+        }
+        self.next_id += 1;
+        self.queue.push(Reverse((entry.deadline, self.next_id)));
+        self.pending.insert(self.next_id, entry);
+    }
+
+    /// Return the deadline of the next timer due that hasn't been cancelled
+    pub fn next_deadline(&mut self) -> Option<Instant> {
+        while let Some(Reverse((deadline, id))) = self.queue.peek() {
+            match self.pending.get(id) {
+                Some(entry) if !entry.handle.is_cancelled() => return Some(*deadline),
+                _ => {
+                    let id = *id;
+                    self.queue.pop();
+                    self.pending.remove(&id);
+                }
+            }
+        }
+        None
+    }
+
+    /// Remove every cancelled timer from the queue
+    fn sweep(&mut self) {
+        self.pending.retain(|_, entry| !entry.handle.is_cancelled());
+        let pending = &self.pending;
+        self.queue = self
+            .queue
+            .drain()
+            .filter(|Reverse((_, id))| pending.contains_key(id))
+            .collect();
+        self.sweep_at = SWEEP_MIN.max(self.pending.len() * 2);
+    }
+
+    /// Remove the next timer from the queue, if it is due at or before now
+    pub fn pop_due(&mut self, now: Instant) -> Option<IsolateTimerEntry> {
+        match self.next_deadline() {
+            Some(deadline) if deadline <= now => {
+                let Reverse((_, id)) = self.queue.pop()?;
+                self.pending.remove(&id)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IsolateTimerQueue;
+    use crate::isolate_timer::isolate_timer_entry::IsolateTimerEntry;
+    use crate::IsolateTimerHandle;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    #[test]
+    pub fn test_drop_cancelled() {
+        let mut queue = IsolateTimerQueue::new();
+        let start = Instant::now();
+        let handles: Vec<IsolateTimerHandle> = (0..100)
+            .map(|i| {
+                let handle = IsolateTimerHandle::new();
+                queue.push(IsolateTimerEntry {
+                    deadline: start + Duration::from_secs(i),
+                    period: None,
+                    handle: handle.clone(),
+                    action: Box::new(|| true),
+                });
+                handle
+            })
+            .collect();
+
+        // A cancelled timer at the front doesn't hold up the next deadline
+        handles[0].cancel();
+        assert_eq!(queue.next_deadline(), Some(start + Duration::from_secs(1)));
+
+        // Cancelled timers further back are swept out as more are scheduled
+        handles.iter().skip(10).for_each(|handle| handle.cancel());
+        for _ in 0..100 {
+            queue.push(IsolateTimerEntry {
+                deadline: start,
+                period: None,
+                handle: IsolateTimerHandle::new(),
+                action: Box::new(|| true),
+            });
+        }
+        assert!(queue.pending.len() < 120);
+    }
+}
@@ -0,0 +1,311 @@
+pub(crate) mod isolate_topics_error;
+pub(crate) mod isolate_topics_shared;
+
+use crate::isolate_runtime::isolate_runtime_shared::IsolateRuntimeShared;
+use crate::isolate_topics::isolate_topics_shared::IsolateTopicsShared;
+use crate::IsolateIdentity;
+use crate::IsolateRuntimeRef;
+use crate::IsolateTopicsError;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::RwLock;
+use std::sync::Weak;
+
+/// IsolateTopics lets the workers of a runtime subscribe to named topics by identity, so a
+/// message published to a topic is delivered to every subscribed worker.
+/// Subscriptions are removed automatically when a worker exits. Topics only hold the identities
+/// of their subscribers and a weak handle to the runtime, so they don't keep workers running.
+pub struct IsolateTopics<T: Send + 'static> {
+    runtime: Weak<Mutex<IsolateRuntimeShared<T>>>,
+    shared: Arc<RwLock<IsolateTopicsShared>>,
+}
+
+impl<T: Send + 'static> IsolateTopics<T> {
+    /// Create a new set of topics for the workers of a runtime
+    pub fn new(runtime: IsolateRuntimeRef<T>) -> Result<IsolateTopics<T>, IsolateTopicsError> {
+        let shared = Arc::new(RwLock::new(IsolateTopicsShared::new()));
+        let shared_ref = Arc::downgrade(&shared);
+        runtime.add_exit_hook(move |identity| {
+            if let Some(shared) = shared_ref.upgrade() {
+                if let Ok(mut inner) = shared.write() {
+                    inner.unsubscribe_all(identity);
+                }
+            }
+        })?;
+        Ok(IsolateTopics {
+            runtime: runtime.downgrade(),
+            shared,
+        })
+    }
+
+    /// Return the runtime of the subscribers, unless it has been dropped
+    fn runtime(&self) -> Option<IsolateRuntimeRef<T>> {
+        self.runtime.upgrade().map(IsolateRuntimeRef::new)
+    }
+
+    /// Subscribe a worker to a topic
+    pub fn subscribe(
+        &self,
+        topic: &str,
+        identity: &IsolateIdentity,
+    ) -> Result<(), IsolateTopicsError> {
+        let runtime = self.runtime().ok_or(IsolateTopicsError::RuntimeHalted)?;
+        if runtime.find(identity).is_none() {
+            return Err(IsolateTopicsError::NoMatchingIdentity);
+        }
+        match self.shared.write() {
+            Ok(mut inner) => {
+                inner.subscribe(topic, *identity);
+                Ok(())
+            }
+            Err(_) => Err(IsolateTopicsError::InternalSyncError),
+        }
+    }
+
+    /// Unsubscribe a worker from a topic, returning true if it was subscribed
+    pub fn unsubscribe(
+        &self,
+        topic: &str,
+        identity: &IsolateIdentity,
+    ) -> Result<bool, IsolateTopicsError> {
+        match self.shared.write() {
+            Ok(mut inner) => Ok(inner.unsubscribe(topic, identity)),
+            Err(_) => Err(IsolateTopicsError::InternalSyncError),
+        }
+    }
+
+    /// Return the identities of every worker subscribed to a topic
+    pub fn subscribers(&self, topic: &str) -> Vec<IsolateIdentity> {
+        match self.shared.read() {
+            Ok(inner) => match inner.subscribers(topic) {
+                Some(subscribers) => subscribers.iter().cloned().collect(),
+                None => Vec::new(),
+            },
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Publish a message to every subscriber of a topic, returning the number of workers it was
+    /// delivered to; messages a subscriber can't take go to the dead letters, as for any send,
+    /// except that a rate limit that delays messages rejects them rather than wait. Subscribers are found in the runtime as the message is published, and
+    /// those that are no longer bound to it are unsubscribed. The topics lock is only held to
+    /// find the subscribers, not while sending.
+    pub fn publish(&self, topic: &str, message: T) -> usize
+    where
+        T: Clone,
+    {
+        let subscribers = match self.shared.read() {
+            Ok(inner) => match inner.subscribers(topic) {
+                Some(subscribers) => subscribers,
+                None => return 0,
+            },
+            Err(_) => return 0,
+        };
+        let runtime = match self.runtime() {
+            Some(runtime) => runtime,
+            None => return 0,
+        };
+        let mut delivered = 0;
+        let mut unbound = Vec::new();
+        for identity in subscribers.iter() {
+            match runtime.find(identity) {
+                Some(channel) => {
+                    if channel.try_send(message.clone()).is_ok() {
+                        delivered += 1;
+                    }
+                }
+                None => unbound.push(*identity),
+            }
+        }
+        if !unbound.is_empty() {
+            if let Ok(mut inner) = self.shared.write() {
+                unbound.iter().for_each(|identity| {
+                    inner.unsubscribe(topic, identity);
+                });
+            }
+        }
+        delivered
+    }
+}
+
+impl<T: Send + 'static> Clone for IsolateTopics<T> {
+    fn clone(&self) -> IsolateTopics<T> {
+        IsolateTopics {
+            runtime: self.runtime.clone(),
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IsolateTopics;
+    use crate::Isolate;
+    use crate::IsolateChannel;
+    use crate::IsolateContext;
+    use crate::IsolateIdentity;
+    use crate::IsolateRateLimit;
+    use crate::IsolateRuntime;
+    use crate::IsolateRuntimeWait;
+    use std::thread;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    struct TestIsolate {}
+
+    #[derive(Clone, Debug)]
+    enum TestIsolateEvent {
+        Halt,
+        Who,
+        Identity(IsolateIdentity),
+        Notice(String),
+    }
+
+    impl Isolate<TestIsolateEvent> for TestIsolate {
+        type Args = ();
+
+        fn spawn(
+            &self,
+            context: IsolateContext<TestIsolateEvent>,
+            _: (),
+        ) -> Box<dyn FnMut() + Send + 'static> {
+            let identity = context.identity();
+            let channel = context.channel().clone();
+            Box::new(move || {
+                while let Ok(event) = channel.receiver.recv() {
+                    match event {
+                        TestIsolateEvent::Halt => break,
+                        TestIsolateEvent::Who => {
+                            let _ = channel.sender.send(TestIsolateEvent::Identity(identity));
+                        }
+                        _ => {
+                            let _ = channel.sender.send(event);
+                        }
+                    }
+                }
+            })
+        }
+    }
+
+    fn identity_of(channel: &IsolateChannel<TestIsolateEvent>) -> IsolateIdentity {
+        channel.sender.send(TestIsolateEvent::Who).unwrap();
+        match channel.receiver.recv().unwrap() {
+            TestIsolateEvent::Identity(id) => id,
+            _ => unreachable!(),
+        }
+    }
+
+    fn notice_of(channel: &IsolateChannel<TestIsolateEvent>) -> String {
+        match channel.receiver.recv().unwrap() {
+            TestIsolateEvent::Notice(s) => s,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    pub fn test_publish_to_subscribers() {
+        let mut runtime = IsolateRuntime::new(TestIsolate {});
+        let topics = IsolateTopics::new(runtime.as_ref()).unwrap();
+
+        let c1 = runtime.spawn().unwrap();
+        let c2 = runtime.spawn().unwrap();
+        topics.subscribe("news", &identity_of(&c1)).unwrap();
+        topics.subscribe("news", &identity_of(&c2)).unwrap();
+        topics.subscribe("sport", &identity_of(&c2)).unwrap();
+
+        assert_eq!(
+            topics.publish("news", TestIsolateEvent::Notice("A".to_string())),
+            2
+        );
+        assert_eq!(notice_of(&c1), "A");
+        assert_eq!(notice_of(&c2), "A");
+
+        assert_eq!(
+            topics.publish("sport", TestIsolateEvent::Notice("B".to_string())),
+            1
+        );
+        assert_eq!(notice_of(&c2), "B");
+
+        assert_eq!(
+            topics.publish("weather", TestIsolateEvent::Notice("C".to_string())),
+            0
+        );
+    }
+
+    #[test]
+    pub fn test_publish_within_rate_limit() {
+        let limit = IsolateRateLimit::new(1, Duration::from_secs(60));
+        let mut runtime = IsolateRuntime::new(TestIsolate {}).with_rate_limit(limit);
+        let topics = IsolateTopics::new(runtime.as_ref()).unwrap();
+
+        let c1 = runtime.spawn().unwrap();
+        topics.subscribe("news", &identity_of(&c1)).unwrap();
+        assert_eq!(
+            topics.publish("news", TestIsolateEvent::Notice("A".to_string())),
+            1
+        );
+        assert_eq!(
+            topics.publish("news", TestIsolateEvent::Notice("B".to_string())),
+            0
+        );
+        assert_eq!(notice_of(&c1), "A");
+    }
+
+    #[test]
+    pub fn test_unsubscribe() {
+        let mut runtime = IsolateRuntime::new(TestIsolate {});
+        let topics = IsolateTopics::new(runtime.as_ref()).unwrap();
+
+        let c1 = runtime.spawn().unwrap();
+        let id1 = identity_of(&c1);
+        topics.subscribe("news", &id1).unwrap();
+        assert!(topics.unsubscribe("news", &id1).unwrap());
+        assert!(!topics.unsubscribe("news", &id1).unwrap());
+        assert!(topics.subscribers("news").is_empty());
+    }
+
+    #[test]
+    pub fn test_subscribe_unknown_identity() {
+        let runtime = IsolateRuntime::new(TestIsolate {});
+        let topics = IsolateTopics::new(runtime.as_ref()).unwrap();
+        assert!(topics.subscribe("news", &IsolateIdentity::new()).is_err());
+    }
+
+    #[test]
+    pub fn test_worker_exit_removes_subscriptions() {
+        let mut runtime = IsolateRuntime::new(TestIsolate {});
+        let topics = IsolateTopics::new(runtime.as_ref()).unwrap();
+
+        let c1 = runtime.spawn().unwrap();
+        topics.subscribe("news", &identity_of(&c1)).unwrap();
+        assert_eq!(topics.subscribers("news").len(), 1);
+
+        c1.sender.send(TestIsolateEvent::Halt).unwrap();
+        runtime.wait();
+
+        assert!(topics.subscribers("news").is_empty());
+    }
+
+    #[test]
+    pub fn test_kill_removes_subscriptions() {
+        let mut runtime = IsolateRuntime::new(TestIsolate {});
+        let topics = IsolateTopics::new(runtime.as_ref()).unwrap();
+
+        let c1 = runtime.spawn().unwrap();
+        let id1 = identity_of(&c1);
+        topics.subscribe("news", &id1).unwrap();
+        drop(c1);
+
+        assert!(runtime.as_ref().kill(&id1).unwrap());
+        assert_eq!(
+            topics.publish("news", TestIsolateEvent::Notice("A".to_string())),
+            0
+        );
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !topics.subscribers("news").is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(topics.subscribers("news").is_empty());
+        runtime.wait();
+    }
+}
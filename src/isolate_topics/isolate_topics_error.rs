@@ -0,0 +1,26 @@
+use crate::IsolateRuntimeError;
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum IsolateTopicsError {
+    InternalSyncError,
+    NoMatchingIdentity,
+    RuntimeHalted,
+    RuntimeError(IsolateRuntimeError),
+}
+
+impl Error for IsolateTopicsError {}
+
+impl Display for IsolateTopicsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl From<IsolateRuntimeError> for IsolateTopicsError {
+    fn from(err: IsolateRuntimeError) -> Self {
+        IsolateTopicsError::RuntimeError(err)
+    }
+}
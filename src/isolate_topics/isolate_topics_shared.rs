@@ -0,0 +1,65 @@
+use crate::IsolateIdentity;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// The subscribers to a single topic.
+/// This is replaced rather than modified when subscriptions change, so publishers only need to
+/// hold the topics lock long enough to take a reference to it.
+pub type IsolateTopicSubscribers = Arc<HashSet<IsolateIdentity>>;
+
+pub struct IsolateTopicsShared {
+    topics: HashMap<String, IsolateTopicSubscribers>,
+}
+
+impl IsolateTopicsShared {
+    pub fn new() -> IsolateTopicsShared {
+        IsolateTopicsShared {
+            topics: HashMap::new(),
+        }
+    }
+
+    /// Return the current subscribers of a topic
+    pub fn subscribers(&self, topic: &str) -> Option<IsolateTopicSubscribers> {
+        self.topics.get(topic).cloned()
+    }
+
+    /// Add a subscriber to a topic
+    pub fn subscribe(&mut self, topic: &str, identity: IsolateIdentity) {
+        let mut subscribers = match self.topics.get(topic) {
+            Some(existing) => existing.as_ref().clone(),
+            None => HashSet::new(),
+        };
+        subscribers.insert(identity);
+        self.topics.insert(topic.to_string(), Arc::new(subscribers));
+    }
+
+    /// Remove a subscriber from a topic, returning true if it was subscribed
+    pub fn unsubscribe(&mut self, topic: &str, identity: &IsolateIdentity) -> bool {
+        let subscribers = match self.topics.get(topic) {
+            Some(existing) if existing.contains(identity) => existing,
+            _ => return false,
+        };
+        let mut subscribers = subscribers.as_ref().clone();
+        subscribers.remove(identity);
+        if subscribers.is_empty() {
+            self.topics.remove(topic);
+        } else {
+            self.topics.insert(topic.to_string(), Arc::new(subscribers));
+        }
+        true
+    }
+
+    /// Remove every subscription held by an identity
+    pub fn unsubscribe_all(&mut self, identity: &IsolateIdentity) {
+        let topics = self
+            .topics
+            .iter()
+            .filter(|(_, subscribers)| subscribers.contains(identity))
+            .map(|(topic, _)| topic.clone())
+            .collect::<Vec<String>>();
+        topics.iter().for_each(|topic| {
+            self.unsubscribe(topic, identity);
+        });
+    }
+}
@@ -0,0 +1,206 @@
+pub(crate) mod isolate_tree_entry;
+pub(crate) mod isolate_tree_node;
+
+use crate::isolate_runtime::isolate_worker_state::IsolateWorkerState;
+use crate::isolate_tree::isolate_tree_node::IsolateTreeNode;
+use crate::IsolateIdentity;
+use crate::IsolateTreeEntry;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// IsolateTree tracks which worker spawned which, across every runtime in a registry.
+/// Stopping a worker stops everything below it, and when a worker exits, its children are
+/// stopped too.
+pub struct IsolateTree {
+    nodes: Arc<Mutex<HashMap<IsolateIdentity, IsolateTreeNode>>>,
+}
+
+impl IsolateTree {
+    pub fn new() -> IsolateTree {
+        IsolateTree {
+            nodes: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Return the worker that spawned a worker, if it was spawned by one that is still running
+    pub fn parent(&self, identity: &IsolateIdentity) -> Option<IsolateIdentity> {
+        match self.nodes.lock() {
+            Ok(nodes) => nodes.get(identity).and_then(|n| n.parent),
+            Err(_) => None,
+        }
+    }
+
+    /// Return the workers spawned by a worker that are still running, in spawn order
+    pub fn children(&self, identity: &IsolateIdentity) -> Vec<IsolateIdentity> {
+        match self.nodes.lock() {
+            Ok(nodes) => nodes
+                .get(identity)
+                .map(|n| n.children.clone())
+                .unwrap_or_default(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Return the running workers that have no parent
+    pub fn roots(&self) -> Vec<IsolateIdentity> {
+        match self.nodes.lock() {
+            Ok(nodes) => nodes
+                .iter()
+                .filter(|(_, n)| n.parent.is_none())
+                .map(|(identity, _)| *identity)
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Walk the subtree below a worker depth first, starting with the worker itself
+    pub fn walk(&self, identity: &IsolateIdentity) -> Vec<IsolateTreeEntry> {
+        let nodes = match self.nodes.lock() {
+            Ok(nodes) => nodes,
+            Err(_) => return Vec::new(),
+        };
+        let mut entries = Vec::new();
+        let mut pending = vec![(*identity, 0)];
+        while let Some((identity, depth)) = pending.pop() {
+            if let Some(node) = nodes.get(&identity) {
+                entries.push(IsolateTreeEntry {
+                    identity,
+                    parent: node.parent,
+                    runtime: node.runtime.clone(),
+                    depth,
+                });
+                pending.extend(node.children.iter().rev().map(|c| (*c, depth + 1)));
+            }
+        }
+        entries
+    }
+
+    /// Stop a worker and every worker below it, returning false if the worker isn't running
+    pub fn stop(&self, identity: &IsolateIdentity) -> bool {
+        let entries = self.walk(identity);
+        let nodes = match self.nodes.lock() {
+            Ok(nodes) => nodes,
+            Err(_) => return false,
+        };
+        entries
+            .iter()
+            .filter_map(|e| nodes.get(&e.identity))
+            .for_each(|n| {
+                n.state.stop();
+            });
+        !entries.is_empty()
+    }
+
+    /// Add a worker to the tree, below the worker that spawned it
+    pub(crate) fn add(
+        &self,
+        identity: IsolateIdentity,
+        parent: Option<IsolateIdentity>,
+        runtime: Option<String>,
+        state: Arc<IsolateWorkerState>,
+    ) {
+        if let Ok(mut nodes) = self.nodes.lock() {
+            // A worker spawned by one that has already exited is a root
+            let parent = parent.filter(|p| match nodes.get_mut(p) {
+                Some(node) => {
+                    node.children.push(identity);
+                    true
+                }
+                None => false,
+            });
+            nodes.insert(
+                identity,
+                IsolateTreeNode {
+                    parent,
+                    children: Vec::new(),
+                    runtime,
+                    state,
+                },
+            );
+        }
+    }
+
+    /// Remove a worker that has exited from the tree, stopping its children
+    pub(crate) fn exited(&self, identity: &IsolateIdentity) {
+        let node = match self.nodes.lock() {
+            Ok(mut nodes) => match nodes.remove(identity) {
+                Some(node) => {
+                    if let Some(parent) = node.parent.and_then(|p| nodes.get_mut(&p)) {
+                        parent.children.retain(|c| c != identity);
+                    }
+                    node.children.iter().for_each(|c| {
+                        if let Some(child) = nodes.get_mut(c) {
+                            child.parent = None;
+                        }
+                    });
+                    node
+                }
+                None => return,
+            },
+            Err(_) => return,
+        };
+        node.children.iter().for_each(|c| {
+            self.stop(c);
+        });
+    }
+}
+
+impl Clone for IsolateTree {
+    fn clone(&self) -> IsolateTree {
+        IsolateTree {
+            nodes: self.nodes.clone(),
+        }
+    }
+}
+
+impl Default for IsolateTree {
+    fn default() -> Self {
+        IsolateTree::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::isolate_runtime::isolate_worker_state::IsolateWorkerState;
+    use crate::IsolateIdentity;
+    use crate::IsolateTree;
+    use std::sync::Arc;
+
+    #[test]
+    pub fn test_walk_and_cascade() {
+        let tree = IsolateTree::new();
+        let ids: Vec<_> = (0..4).map(|_| IsolateIdentity::new()).collect();
+        let states: Vec<_> = (0..4)
+            .map(|_| Arc::new(IsolateWorkerState::new()))
+            .collect();
+        tree.add(ids[0], None, None, states[0].clone());
+        tree.add(ids[1], Some(ids[0]), None, states[1].clone());
+        tree.add(ids[2], Some(ids[1]), None, states[2].clone());
+        tree.add(ids[3], Some(ids[0]), None, states[3].clone());
+
+        let walk: Vec<_> = tree
+            .walk(&ids[0])
+            .iter()
+            .map(|e| (e.identity, e.depth))
+            .collect();
+        assert_eq!(
+            walk,
+            vec![(ids[0], 0), (ids[1], 1), (ids[2], 2), (ids[3], 1)]
+        );
+        assert_eq!(tree.parent(&ids[2]), Some(ids[1]));
+        assert_eq!(tree.roots(), vec![ids[0]]);
+
+        // Stopping a worker stops its subtree, but not its parent or siblings
+        assert!(tree.stop(&ids[1]));
+        assert!(states[1].is_stopped() && states[2].is_stopped());
+        assert!(!states[0].is_stopped() && !states[3].is_stopped());
+
+        // When a worker exits, its children are orphaned and stopped
+        tree.exited(&ids[0]);
+        assert!(states[3].is_stopped());
+        assert_eq!(tree.parent(&ids[3]), None);
+        assert_eq!(tree.roots().len(), 2);
+        assert!(!tree.stop(&ids[0]));
+    }
+}
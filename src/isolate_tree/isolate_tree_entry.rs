@@ -0,0 +1,14 @@
+use crate::IsolateIdentity;
+
+/// A worker found walking the tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsolateTreeEntry {
+    /// The worker
+    pub identity: IsolateIdentity,
+    /// The worker that spawned it, if it was spawned by a worker
+    pub parent: Option<IsolateIdentity>,
+    /// The name of the runtime the worker runs in, if it is bound to a registry
+    pub runtime: Option<String>,
+    /// How far the worker is below the worker the walk started from
+    pub depth: usize,
+}
@@ -0,0 +1,11 @@
+use crate::isolate_runtime::isolate_worker_state::IsolateWorkerState;
+use crate::IsolateIdentity;
+use std::sync::Arc;
+
+/// A running worker in the tree
+pub struct IsolateTreeNode {
+    pub parent: Option<IsolateIdentity>,
+    pub children: Vec<IsolateIdentity>,
+    pub runtime: Option<String>,
+    pub state: Arc<IsolateWorkerState>,
+}
@@ -1,15 +1,92 @@
+mod isolate_breaker;
 mod isolate_channel;
+mod isolate_context;
+mod isolate_dead_letters;
+mod isolate_envelope;
 mod isolate;
+mod isolate_fsm;
+mod isolate_handler;
+mod isolate_mailbox;
+mod isolate_metrics;
+mod isolate_persistent;
+mod isolate_pipeline;
 mod isolate_runtime;
 mod isolate_registry;
+mod isolate_router;
+mod isolate_test;
+mod isolate_throttle;
+mod isolate_timer;
+mod isolate_topics;
+mod isolate_tree;
 
 pub use isolate::Isolate;
+pub use isolate_breaker::IsolateCircuitBreaker;
+pub use isolate_breaker::isolate_breaker_error::IsolateBreakerError;
+pub use isolate_breaker::isolate_breaker_state::IsolateBreakerState;
 pub use isolate_channel::IsolateChannel;
+pub use isolate_channel::isolate_channel_error::IsolateChannelError;
+pub use isolate_channel::isolate_priority::IsolatePriority;
+pub use isolate_context::IsolateContext;
+pub use isolate_context::isolate_context_error::IsolateContextError;
+pub use isolate_context::isolate_select::IsolateSelect;
+pub use isolate_context::isolate_select::IsolateSelected;
+pub use isolate_dead_letters::IsolateDeadLetters;
+pub use isolate_dead_letters::isolate_dead_letter::IsolateDeadLetter;
+pub use isolate_dead_letters::isolate_dead_letter::IsolateDeadLetterReason;
+pub use isolate_envelope::IsolateEnvelope;
+pub use isolate_envelope::isolate_correlation_id::IsolateCorrelationId;
+pub use isolate_fsm::IsolateFsm;
+pub use isolate_fsm::isolate_fsm_state::IsolateFsmState;
+pub use isolate_fsm::isolate_transition::IsolateTransition;
+pub use isolate_fsm::isolate_unhandled::IsolateUnhandled;
+pub use isolate_handler::IsolateHandler;
+pub use isolate_handler::isolate_driver::IsolateDriver;
+pub use isolate_handler::isolate_flow::IsolateFlow;
+pub use isolate_mailbox::IsolateDurableMailbox;
+pub use isolate_mailbox::isolate_mailbox_error::IsolateMailboxError;
+pub use isolate_mailbox::isolate_mailbox_sync::IsolateMailboxSync;
+pub use isolate_metrics::IsolateMetrics;
+pub use isolate_metrics::isolate_histogram::IsolateHistogram;
+#[cfg(feature = "prometheus")]
+pub use isolate_metrics::isolate_metrics_error::IsolateMetricsError;
+#[cfg(feature = "prometheus")]
+pub use isolate_metrics::isolate_metrics_exporter::IsolateMetricsExporter;
+pub use isolate_metrics::isolate_runtime_metrics::IsolateRuntimeMetrics;
+pub use isolate_metrics::isolate_worker_metrics::IsolateWorkerMetrics;
+pub use isolate_persistent::IsolatePersistent;
+pub use isolate_persistent::isolate_file_journal::IsolateFileJournal;
+pub use isolate_persistent::isolate_journal::IsolateJournal;
+pub use isolate_persistent::isolate_journal::IsolateJournalEvents;
+pub use isolate_persistent::isolate_journal_error::IsolateJournalError;
+pub use isolate_persistent::isolate_memory_journal::IsolateMemoryJournal;
+pub use isolate_persistent::isolate_persistent_driver::IsolatePersistentDriver;
+pub use isolate_persistent::isolate_record::IsolateRecord;
+pub use isolate_pipeline::IsolatePipeline;
+pub use isolate_pipeline::isolate_pipeline_handle::IsolatePipelineHandle;
+pub use isolate_pipeline::isolate_stream_event::IsolateStreamEvent;
 pub use isolate_runtime::IsolateRuntime;
+pub use isolate_runtime::isolate_broadcast_report::IsolateBroadcastReport;
+pub use isolate_runtime::isolate_cancellation_token::IsolateCancellationToken;
+pub use isolate_runtime::isolate_down::IsolateDown;
+pub use isolate_runtime::isolate_exit_reason::IsolateExitReason;
 pub use isolate_runtime::isolate_identity::IsolateIdentity;
 pub use isolate_runtime::isolate_runtime_error::IsolateRuntimeError;
 pub use isolate_runtime::isolate_runtime_ref::IsolateRuntimeRef;
 pub use isolate_runtime::isolate_runtime_wait::IsolateRuntimeWait;
+pub use isolate_runtime::isolate_worker_info::IsolateWorkerInfo;
 pub use isolate_registry::IsolateRegistry;
 pub use isolate_registry::isolate_registry_ref::IsolateRegistryRef;
 pub use isolate_registry::isolate_registry_error::IsolateRegistryError;
+pub use isolate_router::IsolateRouter;
+pub use isolate_router::isolate_router_error::IsolateRouterError;
+pub use isolate_router::isolate_router_strategy::IsolateRouterStrategy;
+pub use isolate_test::IsolateTestScheduler;
+pub use isolate_test::isolate_test_delivery::IsolateTestDelivery;
+pub use isolate_throttle::isolate_rate_limit::IsolateRateLimit;
+pub use isolate_throttle::isolate_throttle_mode::IsolateThrottleMode;
+pub use isolate_timer::isolate_timer_handle::IsolateTimerHandle;
+pub use isolate_topics::IsolateTopics;
+pub use isolate_topics::isolate_topics_error::IsolateTopicsError;
+pub use isolate_tree::IsolateTree;
+pub use isolate_tree::isolate_tree_entry::IsolateTreeEntry;
+pub use rust_isolate_derive::isolate_handler;
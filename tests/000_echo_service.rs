@@ -1,6 +1,8 @@
+#![allow(clippy::while_let_loop, clippy::comparison_to_empty)]
+
 use rust_isolate::Isolate;
-use rust_isolate::IsolateIdentity;
 use rust_isolate::IsolateChannel;
+use rust_isolate::IsolateContext;
 use rust_isolate::IsolateRuntime;
 use rust_isolate::IsolateRuntimeWait;
 use std::thread;
@@ -8,7 +10,10 @@ use std::thread;
 struct EchoService {}
 
 impl Isolate<String> for EchoService {
-    fn spawn(&self, _: IsolateIdentity, channel: IsolateChannel<String>) -> Box<dyn FnMut() + Send + 'static> {
+    type Args = ();
+
+    fn spawn(&self, context: IsolateContext<String>, _: ()) -> Box<dyn FnMut() + Send + 'static> {
+        let channel = context.channel().clone();
         Box::new(move || {
             loop {
                 match channel.receiver.recv() {
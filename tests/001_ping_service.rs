@@ -1,6 +1,8 @@
+#![allow(clippy::comparison_to_empty)]
+
 use rust_isolate::Isolate;
-use rust_isolate::IsolateIdentity;
 use rust_isolate::IsolateChannel;
+use rust_isolate::IsolateContext;
 use rust_isolate::IsolateRuntime;
 use rust_isolate::IsolateRuntimeWait;
 use std::thread;
@@ -9,7 +11,10 @@ use std::time::Duration;
 struct PingService {}
 
 impl Isolate<String> for PingService {
-    fn spawn(&self, _: IsolateIdentity, channel: IsolateChannel<String>) -> Box<dyn FnMut() + Send + 'static> {
+    type Args = ();
+
+    fn spawn(&self, context: IsolateContext<String>, _: ()) -> Box<dyn FnMut() + Send + 'static> {
+        let channel = context.channel().clone();
         Box::new(move || {
             loop {
                 match channel.receiver.recv_timeout(Duration::from_millis(100)) {
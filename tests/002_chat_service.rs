@@ -1,12 +1,12 @@
 use rust_isolate::Isolate;
 use rust_isolate::IsolateChannel;
+use rust_isolate::IsolateContext;
 use rust_isolate::IsolateIdentity;
 use rust_isolate::IsolateRegistry;
-use rust_isolate::IsolateRegistryRef;
+use rust_isolate::IsolateTestScheduler;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::Mutex;
-use std::thread;
 use std::time::Duration;
 
 #[derive(Debug)]
@@ -37,31 +37,32 @@ impl ChatServer {
 }
 
 struct ChatService {
-    pub registry: IsolateRegistryRef,
     pub server: Arc<Mutex<ChatServer>>,
 }
 
 impl ChatService {
-    pub fn new(registry: IsolateRegistryRef) -> ChatService {
+    pub fn new() -> ChatService {
         ChatService {
-            registry,
             server: Arc::new(Mutex::new(ChatServer::new())),
         }
     }
 }
 
 impl Isolate<ChatMessage> for ChatService {
+    type Args = ();
+
     fn spawn(
         &self,
-        identity: IsolateIdentity,
-        channel: IsolateChannel<ChatMessage>,
+        context: IsolateContext<ChatMessage>,
+        _: (),
     ) -> Box<dyn FnMut() + Send + 'static> {
+        let identity = context.identity();
+        let channel = context.channel().clone();
         let server = self.server.clone();
-        let registry = self.registry.clone();
         Box::new(move || {
             {
                 let mut server_ref = server.lock().unwrap();
-                let runtime = registry.find("Chat").unwrap();
+                let runtime = context.runtime().unwrap();
                 let self_pointing_channel = runtime.find(&identity).unwrap();
                 server_ref
                     .connections
@@ -106,21 +107,28 @@ impl Isolate<ChatMessage> for ChatService {
 
 #[test]
 pub fn main() {
+    let mut scheduler = IsolateTestScheduler::new(1);
     let mut registry = IsolateRegistry::new();
-    let mut runtime = registry
-        .bind("Chat", ChatService::new(registry.as_ref()))
-        .unwrap();
+    let mut runtime = registry.bind("Chat", ChatService::new()).unwrap();
+    scheduler.attach(&runtime).unwrap();
 
     let c1 = runtime.spawn().unwrap();
     let c2 = runtime.spawn().unwrap();
     let c3 = runtime.spawn().unwrap();
 
-    // Wait for all remote threads to startup
-    thread::sleep(Duration::from_millis(100));
+    // Every worker has registered with the server once the scheduler is idle
+    scheduler.run_until_idle();
 
     c1.sender
         .send(ChatMessage::NewMessage("Hello World".to_string()))
         .unwrap();
+    scheduler.run_until_idle();
+
+    let workers = scheduler.workers();
+    scheduler.assert_delivered(&workers[0], "NewMessage(\"Hello World\")");
+    for worker in workers.iter() {
+        scheduler.assert_delivered(worker, "BroadcastMessage(\"Hello World\")");
+    }
 
     match c1.receiver.recv().unwrap() {
         ChatMessage::BroadcastMessage(c) => {
@@ -146,6 +154,7 @@ pub fn main() {
     c1.sender.send(ChatMessage::Halt).unwrap();
     c2.sender.send(ChatMessage::Halt).unwrap();
     c3.sender.send(ChatMessage::Halt).unwrap();
+    scheduler.run_until_idle();
 
     registry.wait();
 }
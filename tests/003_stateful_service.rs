@@ -1,7 +1,9 @@
+#![allow(dead_code, deprecated)]
+
 use crate::errors::StatefulError;
 use rust_isolate::Isolate;
 use rust_isolate::IsolateChannel;
-use rust_isolate::IsolateIdentity;
+use rust_isolate::IsolateContext;
 use rust_isolate::IsolateRuntime;
 use rust_isolate::IsolateRuntimeWait;
 use std::sync::Arc;
@@ -83,11 +85,14 @@ impl StatefulService {
 }
 
 impl Isolate<StatefulEvent> for StatefulService {
+    type Args = ();
+
     fn spawn(
         &self,
-        _: IsolateIdentity,
-        channel: IsolateChannel<StatefulEvent>,
+        context: IsolateContext<StatefulEvent>,
+        _: (),
     ) -> Box<dyn FnMut() + Send + 'static> {
+        let channel = context.channel().clone();
         let mut instance = self.clone();
         Box::new(move || {
             instance.event_loop(&channel);
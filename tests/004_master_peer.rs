@@ -1,3 +1,5 @@
+#![allow(clippy::clone_on_copy, clippy::needless_return)]
+
 use crate::master::MasterEvent;
 use crate::master::MasterIsolate;
 use crate::peer::PeerEvent;
@@ -12,6 +14,7 @@ mod peer {
     use crate::master::MasterEvent;
     use rust_isolate::Isolate;
     use rust_isolate::IsolateChannel;
+    use rust_isolate::IsolateContext;
     use rust_isolate::IsolateIdentity;
     use rust_isolate::IsolateRegistryRef;
 
@@ -125,11 +128,15 @@ mod peer {
     }
 
     impl Isolate<PeerEvent> for PeerIsolate {
+        type Args = ();
+
         fn spawn(
             &self,
-            identity: IsolateIdentity,
-            channel: IsolateChannel<PeerEvent>,
+            context: IsolateContext<PeerEvent>,
+            _: (),
         ) -> Box<dyn FnMut() + Send + 'static> {
+            let identity = context.identity();
+            let channel = context.channel().clone();
             let mut instance = self.clone();
             instance.identity = identity;
             Box::new(move || {
@@ -143,6 +150,7 @@ mod master {
     use crate::peer::PeerEvent;
     use rust_isolate::Isolate;
     use rust_isolate::IsolateChannel;
+    use rust_isolate::IsolateContext;
     use rust_isolate::IsolateIdentity;
     use rust_isolate::IsolateRegistryRef;
     use std::collections::HashMap;
@@ -242,11 +250,15 @@ mod master {
     }
 
     impl Isolate<MasterEvent> for MasterIsolate {
+        type Args = ();
+
         fn spawn(
             &self,
-            identity: IsolateIdentity,
-            channel: IsolateChannel<MasterEvent>,
+            context: IsolateContext<MasterEvent>,
+            _: (),
         ) -> Box<dyn FnMut() + Send + 'static> {
+            let identity = context.identity();
+            let channel = context.channel().clone();
             let mut instance = self.clone();
             Box::new(move || {
                 channel
@@ -0,0 +1,81 @@
+use rust_isolate::Isolate;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateRuntime;
+use rust_isolate::IsolateRuntimeWait;
+use std::sync::mpsc;
+use std::sync::mpsc::Sender;
+
+// In this example each connection is given the user id and the socket it serves when it is
+// spawned, rather than having to be sent an initialize event first.
+
+#[derive(Debug)]
+enum ConnectionEvent {
+    Halt,
+    Message(String),
+}
+
+struct ConnectionArgs {
+    user_id: u32,
+    socket: Sender<String>,
+}
+
+struct ConnectionService {}
+
+impl Isolate<ConnectionEvent> for ConnectionService {
+    type Args = ConnectionArgs;
+
+    fn spawn(
+        &self,
+        context: IsolateContext<ConnectionEvent>,
+        args: ConnectionArgs,
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        let channel = context.channel().clone();
+        Box::new(move || {
+            while let Ok(event) = channel.receiver.recv() {
+                match event {
+                    ConnectionEvent::Halt => break,
+                    ConnectionEvent::Message(s) => {
+                        args.socket.send(format!("{}: {}", args.user_id, s)).unwrap();
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[test]
+pub fn main() {
+    let mut runtime = IsolateRuntime::new(ConnectionService {});
+    let (socket, socket_output) = mpsc::channel();
+
+    let c1 = runtime
+        .spawn_with(ConnectionArgs {
+            user_id: 1,
+            socket: socket.clone(),
+        })
+        .unwrap();
+    let c2 = runtime
+        .spawn_with(ConnectionArgs {
+            user_id: 2,
+            socket: socket.clone(),
+        })
+        .unwrap();
+
+    // Spawning without the connection args doesn't compile: `runtime.spawn()` is only there
+    // for isolates whose Args are ()
+
+    c1.sender
+        .send(ConnectionEvent::Message("Hello".to_string()))
+        .unwrap();
+    assert_eq!(socket_output.recv().unwrap(), "1: Hello");
+
+    c2.sender
+        .send(ConnectionEvent::Message("World".to_string()))
+        .unwrap();
+    assert_eq!(socket_output.recv().unwrap(), "2: World");
+
+    c1.sender.send(ConnectionEvent::Halt).unwrap();
+    c2.sender.send(ConnectionEvent::Halt).unwrap();
+
+    runtime.wait();
+}
@@ -0,0 +1,110 @@
+use rust_isolate::Isolate;
+use rust_isolate::IsolateChannel;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateRuntime;
+use rust_isolate::IsolateRuntimeWait;
+use rust_isolate::IsolateTopics;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+// This is the chat service again, but using topics to broadcast messages rather than holding
+// a shared map of every connection.
+
+#[derive(Clone, Debug)]
+enum ChatMessage {
+    Halt,
+    Joined,
+    BroadcastMessage(String),
+    NewMessage(String),
+}
+
+struct ChatService {
+    pub topics: Arc<Mutex<Option<IsolateTopics<ChatMessage>>>>,
+}
+
+impl Isolate<ChatMessage> for ChatService {
+    type Args = ();
+
+    fn spawn(
+        &self,
+        context: IsolateContext<ChatMessage>,
+        _: (),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        let identity = context.identity();
+        let channel = context.channel().clone();
+        let topics = self.topics.lock().unwrap().clone().unwrap();
+        Box::new(move || {
+            topics.subscribe("Lobby", &identity).unwrap();
+            channel.sender.send(ChatMessage::Joined).unwrap();
+
+            while let Ok(r) = channel.receiver.recv() {
+                match r {
+                    ChatMessage::Halt => break,
+
+                    // A message from the external client to send a new chat message
+                    ChatMessage::NewMessage(s) => {
+                        topics.publish("Lobby", ChatMessage::BroadcastMessage(s));
+                    }
+
+                    // A message back from the broadcaster, to post to the client
+                    ChatMessage::BroadcastMessage(s) => {
+                        channel
+                            .sender
+                            .send(ChatMessage::BroadcastMessage(s))
+                            .unwrap();
+                    }
+
+                    ChatMessage::Joined => {}
+                }
+            }
+        })
+    }
+}
+
+fn expect_message(channel: &IsolateChannel<ChatMessage>, expected: &str) {
+    match channel.receiver.recv().unwrap() {
+        ChatMessage::BroadcastMessage(c) => {
+            assert_eq!(expected, c);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+pub fn main() {
+    let topics = Arc::new(Mutex::new(None));
+    let mut runtime = IsolateRuntime::new(ChatService {
+        topics: topics.clone(),
+    });
+    let lobby = IsolateTopics::new(runtime.as_ref()).unwrap();
+    *topics.lock().unwrap() = Some(lobby.clone());
+
+    let c1 = runtime.spawn().unwrap();
+    let c2 = runtime.spawn().unwrap();
+    let c3 = runtime.spawn().unwrap();
+
+    // Wait for every connection to join the lobby
+    for c in [&c1, &c2, &c3].iter() {
+        match c.receiver.recv().unwrap() {
+            ChatMessage::Joined => {}
+            _ => unreachable!(),
+        }
+    }
+    assert_eq!(lobby.subscribers("Lobby").len(), 3);
+
+    c1.sender
+        .send(ChatMessage::NewMessage("Hello World".to_string()))
+        .unwrap();
+    expect_message(&c1, "Hello World");
+    expect_message(&c2, "Hello World");
+    expect_message(&c3, "Hello World");
+
+    c1.sender.send(ChatMessage::Halt).unwrap();
+    c2.sender.send(ChatMessage::Halt).unwrap();
+    c3.sender.send(ChatMessage::Halt).unwrap();
+
+    runtime.wait();
+
+    // Connections leave the lobby when they exit
+    assert!(lobby.subscribers("Lobby").is_empty());
+}
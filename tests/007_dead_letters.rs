@@ -0,0 +1,122 @@
+use crossbeam::unbounded;
+use crossbeam::Receiver;
+use rust_isolate::Isolate;
+use rust_isolate::IsolateChannelError;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateDeadLetterReason;
+use rust_isolate::IsolateIdentity;
+use rust_isolate::IsolateRegistry;
+use rust_isolate::IsolateRuntimeWait;
+use std::thread;
+
+// In this example a worker halts with messages still in its queue, and more messages are sent
+// to it after it has gone; all of them end up as dead letters that can be redelivered.
+
+#[derive(Debug, PartialEq)]
+enum JobEvent {
+    Halt,
+    Job(u32),
+}
+
+struct JobService {}
+
+impl Isolate<JobEvent> for JobService {
+    type Args = ();
+
+    fn spawn(&self, context: IsolateContext<JobEvent>, _: ()) -> Box<dyn FnMut() + Send + 'static> {
+        let channel = context.channel().clone();
+        Box::new(move || {
+            while let Ok(event) = channel.receiver.recv() {
+                match event {
+                    JobEvent::Halt => break,
+                    JobEvent::Job(n) => channel.sender.send(JobEvent::Job(n)).unwrap(),
+                }
+            }
+        })
+    }
+}
+
+#[test]
+pub fn main() {
+    let mut registry = IsolateRegistry::new();
+    let mut jobs = registry.bind("Jobs", JobService {}).unwrap();
+    let dead_letters = registry.dead_letters().unwrap().subscribe();
+
+    let worker = jobs.spawn().unwrap();
+    let spare = jobs.spawn().unwrap();
+
+    // The worker halts before it gets to the second job
+    worker.send(JobEvent::Job(1)).unwrap();
+    worker.send(JobEvent::Halt).unwrap();
+    worker.send(JobEvent::Job(2)).unwrap();
+    assert_eq!(worker.receiver.recv().unwrap(), JobEvent::Job(1));
+
+    let letter = dead_letters.recv().unwrap();
+    assert_eq!(letter.runtime, "Jobs");
+    assert_eq!(letter.reason, IsolateDeadLetterReason::Unprocessed);
+    assert!(jobs.find(&letter.identity).is_none());
+
+    // Redeliver the job to another worker
+    spare.send(letter.take::<JobEvent>().unwrap()).unwrap();
+    assert_eq!(spare.receiver.recv().unwrap(), JobEvent::Job(2));
+    spare.send(JobEvent::Halt).unwrap();
+    jobs.wait();
+
+    // Sending to the halted worker now also goes to the dead letters
+    match worker.send(JobEvent::Job(3)) {
+        Err(IsolateChannelError::DeadLetter) => {}
+        _ => unreachable!(),
+    }
+    let letter = dead_letters.recv().unwrap();
+    assert_eq!(letter.reason, IsolateDeadLetterReason::WorkerExited);
+    assert_eq!(letter.take::<JobEvent>().unwrap(), JobEvent::Job(3));
+
+    // As does sending to an identity that isn't bound
+    let unknown = IsolateIdentity::new();
+    assert!(jobs.send(&unknown, JobEvent::Job(4)).is_err());
+    let letter = dead_letters.recv().unwrap();
+    assert_eq!(letter.identity, unknown);
+    assert_eq!(letter.reason, IsolateDeadLetterReason::NoMatchingIdentity);
+
+    registry.wait();
+}
+
+/// Halts as soon as its gate opens, with whatever was sent to it left over
+struct GatedService {}
+
+impl Isolate<JobEvent> for GatedService {
+    type Args = Receiver<()>;
+
+    fn spawn(
+        &self,
+        _: IsolateContext<JobEvent>,
+        gate: Receiver<()>,
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        Box::new(move || {
+            let _ = gate.recv();
+        })
+    }
+}
+
+#[test]
+pub fn test_dead_letters_posted_before_wait_returns() {
+    let mut registry = IsolateRegistry::new();
+    let mut jobs = registry.bind("Jobs", GatedService {}).unwrap();
+    let dead_letters = registry.dead_letters().unwrap().subscribe();
+
+    // The worker leaves the runtime as soon as it halts, before its jobs are posted; they are
+    // all posted by the time wait returns
+    let (gate, gate_receiver) = unbounded();
+    let identity = IsolateIdentity::new();
+    let worker = jobs.spawn_as(identity, gate_receiver).unwrap();
+    (1..=10000).for_each(|n| worker.send(JobEvent::Job(n)).unwrap());
+    gate.send(()).unwrap();
+    while jobs.find(&identity).is_some() {
+        thread::yield_now();
+    }
+    jobs.wait();
+    assert_eq!(dead_letters.try_iter().count(), 10000);
+
+    drop(worker);
+    registry.wait();
+}
@@ -0,0 +1,121 @@
+use rust_isolate::Isolate;
+use rust_isolate::IsolateChannel;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateIdentity;
+use rust_isolate::IsolateRateLimit;
+use rust_isolate::IsolateRuntime;
+use rust_isolate::IsolateRuntimeWait;
+use std::time::Duration;
+use std::time::Instant;
+
+// In this example a worker is sent scheduled messages by the runtime rather than polling.
+
+#[derive(Clone, Debug, PartialEq)]
+enum ClockEvent {
+    Halt,
+    Started(IsolateIdentity),
+    Alarm,
+    Tick,
+}
+
+struct ClockService {}
+
+impl Isolate<ClockEvent> for ClockService {
+    type Args = ();
+
+    fn spawn(
+        &self,
+        context: IsolateContext<ClockEvent>,
+        _: (),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        let identity = context.identity();
+        let channel = context.channel().clone();
+        Box::new(move || {
+            channel.sender.send(ClockEvent::Started(identity)).unwrap();
+            while let Ok(event) = channel.receiver.recv() {
+                match event {
+                    ClockEvent::Halt => break,
+                    _ => channel.sender.send(event).unwrap(),
+                }
+            }
+        })
+    }
+}
+
+fn started(channel: &IsolateChannel<ClockEvent>) -> IsolateIdentity {
+    match channel.receiver.recv().unwrap() {
+        ClockEvent::Started(identity) => identity,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+pub fn main() {
+    let mut runtime = IsolateRuntime::new(ClockService {});
+    let clocks = runtime.as_ref();
+
+    let c1 = runtime.spawn().unwrap();
+    let id1 = started(&c1);
+
+    // A single delayed message
+    let start = Instant::now();
+    clocks
+        .send_after(&id1, Duration::from_millis(20), ClockEvent::Alarm)
+        .unwrap();
+    assert_eq!(c1.receiver.recv().unwrap(), ClockEvent::Alarm);
+    assert!(start.elapsed() >= Duration::from_millis(20));
+
+    // A repeating message, until it is cancelled
+    let ticks = clocks
+        .send_interval(&id1, Duration::from_millis(5), ClockEvent::Tick)
+        .unwrap();
+    for _ in 0..3 {
+        assert_eq!(c1.receiver.recv().unwrap(), ClockEvent::Tick);
+    }
+    ticks.cancel();
+
+    // A cancelled alarm never arrives
+    let alarm = clocks
+        .send_after(&id1, Duration::from_millis(20), ClockEvent::Alarm)
+        .unwrap();
+    alarm.cancel();
+    while let Ok(event) = c1.receiver.recv_timeout(Duration::from_millis(50)) {
+        assert_eq!(event, ClockEvent::Tick);
+    }
+
+    // Timers for a worker are cancelled when it exits
+    let ticks = clocks
+        .send_interval(&id1, Duration::from_millis(5), ClockEvent::Tick)
+        .unwrap();
+    c1.sender.send(ClockEvent::Halt).unwrap();
+    runtime.wait();
+    assert!(ticks.is_cancelled());
+
+    // Timers can't be scheduled for workers that aren't bound
+    assert!(clocks
+        .send_after(&id1, Duration::from_millis(5), ClockEvent::Alarm)
+        .is_err());
+}
+
+#[test]
+pub fn test_interval_over_rate_limit() {
+    let limit = IsolateRateLimit::new(1, Duration::from_millis(50));
+    let mut runtime = IsolateRuntime::new(ClockService {}).with_rate_limit(limit);
+    let clocks = runtime.as_ref();
+
+    let c1 = runtime.spawn().unwrap();
+    let id1 = started(&c1);
+
+    // Ticks over the rate limit are dropped, but the timer carries on
+    let ticks = clocks
+        .send_interval(&id1, Duration::from_millis(5), ClockEvent::Tick)
+        .unwrap();
+    for _ in 0..3 {
+        assert_eq!(c1.receiver.recv().unwrap(), ClockEvent::Tick);
+    }
+    assert!(!ticks.is_cancelled());
+    ticks.cancel();
+
+    c1.sender.send(ClockEvent::Halt).unwrap();
+    runtime.wait();
+}
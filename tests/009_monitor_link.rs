@@ -0,0 +1,188 @@
+use rust_isolate::Isolate;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateDown;
+use rust_isolate::IsolateExitReason;
+use rust_isolate::IsolateIdentity;
+use rust_isolate::IsolateRuntime;
+use rust_isolate::IsolateRuntimeWait;
+use rust_isolate::IsolateTestScheduler;
+use std::time::Duration;
+
+// In this example, a supervisor monitors workers in another runtime, and is told why each of
+// them exited. Workers linked to each other fail together.
+
+#[derive(Debug)]
+enum SupervisorEvent {
+    Down(IsolateDown),
+}
+
+impl From<IsolateDown> for SupervisorEvent {
+    fn from(down: IsolateDown) -> Self {
+        SupervisorEvent::Down(down)
+    }
+}
+
+#[derive(Debug)]
+enum WorkerEvent {
+    Halt,
+    Crash,
+    Ping,
+    Pong,
+    Down(IsolateDown),
+}
+
+impl From<IsolateDown> for WorkerEvent {
+    fn from(down: IsolateDown) -> Self {
+        WorkerEvent::Down(down)
+    }
+}
+
+struct SupervisorIsolate {}
+
+impl Isolate<SupervisorEvent> for SupervisorIsolate {
+    type Args = ();
+
+    fn spawn(
+        &self,
+        context: IsolateContext<SupervisorEvent>,
+        _: (),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        let channel = context.channel().clone();
+        Box::new(move || {
+            // Report every down notification
+            while let Ok(event) = channel.receiver.recv() {
+                channel.sender.send(event).unwrap();
+            }
+        })
+    }
+}
+
+struct WorkerIsolate {}
+
+impl Isolate<WorkerEvent> for WorkerIsolate {
+    type Args = ();
+
+    fn spawn(
+        &self,
+        context: IsolateContext<WorkerEvent>,
+        _: (),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        let channel = context.channel().clone();
+        Box::new(move || {
+            while let Ok(event) = channel.receiver.recv() {
+                match event {
+                    WorkerEvent::Halt => break,
+                    WorkerEvent::Crash => panic!("Crashed"),
+                    WorkerEvent::Ping => channel.sender.send(WorkerEvent::Pong).unwrap(),
+                    // A linked worker failed, so this one gives up too
+                    WorkerEvent::Down(down) => {
+                        assert!(down.reason.is_abnormal());
+                        break;
+                    }
+                    WorkerEvent::Pong => {}
+                }
+            }
+        })
+    }
+}
+
+fn expect_down(event: SupervisorEvent, identity: IsolateIdentity, reason: IsolateExitReason) {
+    match event {
+        SupervisorEvent::Down(down) => {
+            assert_eq!(down.identity, identity);
+            assert_eq!(down.reason, reason);
+        }
+    }
+}
+
+#[test]
+pub fn main() {
+    let mut scheduler = IsolateTestScheduler::new(1);
+    let mut supervisors = IsolateRuntime::new(SupervisorIsolate {});
+    let mut workers = IsolateRuntime::new(WorkerIsolate {});
+    scheduler.attach(&supervisors.as_ref()).unwrap();
+    scheduler.attach(&workers.as_ref()).unwrap();
+
+    let supervisor = supervisors.spawn().unwrap();
+    let channels: Vec<_> = (0..6).map(|_| workers.spawn().unwrap()).collect();
+    let ids = scheduler.workers();
+    let (s, w) = (ids[0], &ids[1..]);
+
+    let supervisors = supervisors.as_ref();
+    let workers = workers.as_ref();
+    for worker in w[0..4].iter() {
+        supervisors.monitor(&s, &workers, worker).unwrap();
+    }
+    workers.link(&w[2], &workers, &w[3]).unwrap();
+    workers.link(&w[4], &workers, &w[5]).unwrap();
+    scheduler.run_until_idle();
+
+    // Monitors are told about normal exits
+    channels[0].sender.send(WorkerEvent::Halt).unwrap();
+    scheduler.run_until_idle();
+    let event = supervisor.receiver.try_recv().unwrap();
+    expect_down(event, w[0], IsolateExitReason::Normal);
+
+    // ...and about panics
+    channels[1].sender.send(WorkerEvent::Crash).unwrap();
+    scheduler.run_until_idle();
+    let event = supervisor.receiver.try_recv().unwrap();
+    expect_down(
+        event,
+        w[1],
+        IsolateExitReason::Panicked("Crashed".to_string()),
+    );
+
+    // A crash takes linked workers down with it
+    channels[2].sender.send(WorkerEvent::Crash).unwrap();
+    scheduler.run_until_idle();
+    let event = supervisor.receiver.try_recv().unwrap();
+    expect_down(
+        event,
+        w[2],
+        IsolateExitReason::Panicked("Crashed".to_string()),
+    );
+    let event = supervisor.receiver.try_recv().unwrap();
+    expect_down(event, w[3], IsolateExitReason::Linked(w[2]));
+
+    // A normal exit isn't propagated over a link
+    channels[4].sender.send(WorkerEvent::Halt).unwrap();
+    channels[5].sender.send(WorkerEvent::Ping).unwrap();
+    scheduler.run_until_idle();
+    scheduler.assert_delivered_seq(&w[5], &["Ping"]);
+    assert!(channels[5].receiver.try_recv().is_ok());
+
+    // Watchers of workers that have exited can't be added
+    assert!(supervisors.monitor(&s, &workers, &w[0]).is_err());
+
+    scheduler.shutdown();
+    supervisors.wait();
+    workers.wait();
+}
+
+#[test]
+pub fn test_monitor_reading_channel_directly() {
+    // Without the test scheduler workers have priority queues, but a supervisor that reads its
+    // channel directly still gets its down notifications
+    let mut supervisors = IsolateRuntime::new(SupervisorIsolate {});
+    let mut workers = IsolateRuntime::new(WorkerIsolate {});
+    let (s, w) = (IsolateIdentity::new(), IsolateIdentity::new());
+    let supervisor = supervisors.spawn_as(s, ()).unwrap();
+    let worker = workers.spawn_as(w, ()).unwrap();
+    supervisors
+        .as_ref()
+        .monitor(&s, &workers.as_ref(), &w)
+        .unwrap();
+
+    worker.sender.send(WorkerEvent::Crash).unwrap();
+    let event = supervisor.receiver.recv_timeout(Duration::from_secs(5));
+    expect_down(
+        event.unwrap(),
+        w,
+        IsolateExitReason::Panicked("Crashed".to_string()),
+    );
+
+    drop(supervisor);
+    supervisors.wait();
+    workers.wait();
+}
@@ -0,0 +1,129 @@
+use rust_isolate::isolate_handler;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateDriver;
+use rust_isolate::IsolateFlow;
+use rust_isolate::IsolateRuntime;
+use rust_isolate::IsolateRuntimeWait;
+
+// In this example, a calculator handles each kind of message in its own method, instead of
+// running its own message loop.
+
+#[derive(Debug)]
+enum CalculatorEvent {
+    Halt,
+    Add(isize, isize),
+    Sub(isize, isize),
+    Store { value: isize },
+    Recall,
+    Output(isize),
+}
+
+#[derive(Default)]
+struct Calculator {
+    memory: isize,
+}
+
+#[isolate_handler]
+impl Calculator {
+    #[handler]
+    fn add(&mut self, context: &IsolateContext<CalculatorEvent>, a: isize, b: isize) {
+        context.reply(CalculatorEvent::Output(a + b)).unwrap();
+    }
+
+    #[handler(Sub)]
+    fn subtract(&mut self, context: &IsolateContext<CalculatorEvent>, a: isize, b: isize) {
+        context.reply(CalculatorEvent::Output(a - b)).unwrap();
+    }
+
+    #[handler(Store { value })]
+    fn store(&mut self, _: &IsolateContext<CalculatorEvent>, value: isize) {
+        self.memory = value;
+    }
+
+    #[handler]
+    fn recall(&mut self, context: &IsolateContext<CalculatorEvent>) {
+        context.reply(CalculatorEvent::Output(self.memory)).unwrap();
+    }
+
+    #[handler]
+    fn halt(&mut self, _: &IsolateContext<CalculatorEvent>) -> IsolateFlow {
+        IsolateFlow::Halt
+    }
+
+    #[fallback]
+    fn other(&mut self, _: &IsolateContext<CalculatorEvent>, _: CalculatorEvent) -> IsolateFlow {
+        IsolateFlow::Continue
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum CounterEvent {
+    Increment,
+    Count(usize),
+    Halt,
+}
+
+#[derive(Default)]
+struct Counter {
+    count: usize,
+}
+
+// Without a fallback, every variant needs a handler
+#[isolate_handler]
+impl Counter {
+    #[handler]
+    fn increment(&mut self, context: &IsolateContext<CounterEvent>) {
+        self.count += 1;
+        context.reply(CounterEvent::Count(self.count)).unwrap();
+    }
+
+    #[handler]
+    fn count(&mut self, _: &IsolateContext<CounterEvent>, _: usize) {}
+
+    #[handler]
+    fn halt(&mut self, _: &IsolateContext<CounterEvent>) -> IsolateFlow {
+        IsolateFlow::Halt
+    }
+}
+
+fn output(event: CalculatorEvent) -> isize {
+    match event {
+        CalculatorEvent::Output(value) => value,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+pub fn main() {
+    let mut runtime = IsolateRuntime::new(IsolateDriver::new(|_: ()| Calculator::default()));
+    let channel = runtime.spawn().unwrap();
+
+    channel.sender.send(CalculatorEvent::Add(1, 2)).unwrap();
+    channel.sender.send(CalculatorEvent::Sub(10, 4)).unwrap();
+    channel
+        .sender
+        .send(CalculatorEvent::Store { value: 42 })
+        .unwrap();
+    channel.sender.send(CalculatorEvent::Recall).unwrap();
+
+    assert_eq!(output(channel.receiver.recv().unwrap()), 3);
+    assert_eq!(output(channel.receiver.recv().unwrap()), 6);
+    assert_eq!(output(channel.receiver.recv().unwrap()), 42);
+
+    channel.sender.send(CalculatorEvent::Halt).unwrap();
+    runtime.wait();
+}
+
+#[test]
+pub fn test_every_variant_handled() {
+    let mut runtime = IsolateRuntime::new(IsolateDriver::new(|_: ()| Counter::default()));
+    let channel = runtime.spawn().unwrap();
+
+    channel.sender.send(CounterEvent::Increment).unwrap();
+    channel.sender.send(CounterEvent::Increment).unwrap();
+    assert_eq!(channel.receiver.recv().unwrap(), CounterEvent::Count(1));
+    assert_eq!(channel.receiver.recv().unwrap(), CounterEvent::Count(2));
+
+    channel.sender.send(CounterEvent::Halt).unwrap();
+    runtime.wait();
+}
@@ -0,0 +1,104 @@
+use rust_isolate::Isolate;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateIdentity;
+use rust_isolate::IsolateRegistry;
+
+// In this example, a manager spawns workers in another runtime, and each of those workers
+// spawns a helper of its own. Stopping the manager stops every worker below it.
+
+#[derive(Debug)]
+enum ManagerEvent {
+    Start,
+    Ready(IsolateIdentity),
+}
+
+#[derive(Debug)]
+enum WorkerEvent {
+    Start,
+    Ready,
+}
+
+struct ManagerIsolate {}
+
+impl Isolate<ManagerEvent> for ManagerIsolate {
+    type Args = ();
+
+    fn spawn(
+        &self,
+        context: IsolateContext<ManagerEvent>,
+        _: (),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        let mut workers = Vec::new();
+        Box::new(move || {
+            while let Ok(event) = context.recv() {
+                if let ManagerEvent::Start = event {
+                    for _ in 0..2 {
+                        let worker = context.spawn_in::<WorkerEvent, ()>("Worker", ()).unwrap();
+                        worker.sender.send(WorkerEvent::Start).unwrap();
+                        worker.receiver.recv().unwrap();
+                        workers.push(worker);
+                    }
+                    context
+                        .reply(ManagerEvent::Ready(context.identity()))
+                        .unwrap();
+                }
+            }
+        })
+    }
+}
+
+struct WorkerIsolate {}
+
+impl Isolate<WorkerEvent> for WorkerIsolate {
+    type Args = ();
+
+    fn spawn(
+        &self,
+        context: IsolateContext<WorkerEvent>,
+        _: (),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        let mut helpers = Vec::new();
+        Box::new(move || {
+            while let Ok(event) = context.recv() {
+                if let WorkerEvent::Start = event {
+                    helpers.push(context.spawn().unwrap());
+                    context.reply(WorkerEvent::Ready).unwrap();
+                }
+            }
+        })
+    }
+}
+
+#[test]
+pub fn main() {
+    let mut registry = IsolateRegistry::new();
+    let mut managers = registry.bind("Manager", ManagerIsolate {}).unwrap();
+    registry.bind("Worker", WorkerIsolate {}).unwrap();
+
+    let manager = managers.spawn().unwrap();
+    manager.sender.send(ManagerEvent::Start).unwrap();
+    let identity = match manager.receiver.recv().unwrap() {
+        ManagerEvent::Ready(identity) => identity,
+        _ => unreachable!(),
+    };
+
+    // The tree spans both runtimes
+    let tree = registry.tree().unwrap();
+    let walk = tree.walk(&identity);
+    let depths: Vec<_> = walk.iter().map(|e| e.depth).collect();
+    assert_eq!(depths, vec![0, 1, 2, 1, 2]);
+    assert_eq!(walk[0].runtime.as_deref(), Some("Manager"));
+    assert_eq!(walk[2].runtime.as_deref(), Some("Worker"));
+    assert_eq!(walk[2].parent, Some(walk[1].identity));
+    assert_eq!(
+        tree.children(&identity),
+        vec![walk[1].identity, walk[3].identity]
+    );
+    assert_eq!(tree.roots(), vec![identity]);
+
+    // Stopping the manager stops everything below it
+    assert!(managers.stop(&identity).unwrap());
+    drop(manager);
+    registry.wait();
+    assert!(tree.roots().is_empty());
+}
@@ -0,0 +1,122 @@
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateDriver;
+use rust_isolate::IsolateFlow;
+use rust_isolate::IsolateHandler;
+use rust_isolate::IsolateRegistry;
+use rust_isolate::IsolateTestScheduler;
+
+// In this example, we watch an echo service through its metrics; how many messages each worker
+// has handled, how many are waiting, and how long the handlers took.
+
+#[derive(Debug)]
+enum EchoEvent {
+    Halt,
+    Echo(String),
+}
+
+#[derive(Clone)]
+struct Echo {}
+
+impl IsolateHandler<EchoEvent> for Echo {
+    fn handle(&mut self, context: &IsolateContext<EchoEvent>, message: EchoEvent) -> IsolateFlow {
+        match message {
+            EchoEvent::Halt => IsolateFlow::Halt,
+            EchoEvent::Echo(text) => {
+                context.reply(EchoEvent::Echo(text)).unwrap();
+                IsolateFlow::Continue
+            }
+        }
+    }
+}
+
+#[test]
+pub fn main() {
+    let mut scheduler = IsolateTestScheduler::new(1);
+    let mut registry = IsolateRegistry::new();
+    let mut runtime = registry
+        .bind("Echo", IsolateDriver::from_clone(Echo {}))
+        .unwrap();
+    scheduler.attach(&runtime).unwrap();
+
+    let first = runtime.spawn().unwrap();
+    let second = runtime.spawn().unwrap();
+    for text in ["a", "b", "c"].iter() {
+        first
+            .sender
+            .send(EchoEvent::Echo(text.to_string()))
+            .unwrap();
+    }
+
+    // Nothing has been delivered yet, so the messages are all waiting
+    let metrics = runtime.metrics().unwrap();
+    assert_eq!(metrics.name.as_deref(), Some("Echo"));
+    assert_eq!(metrics.spawned, 2);
+    let mailboxes: Vec<_> = metrics.workers.iter().map(|w| w.mailbox).collect();
+    assert_eq!(mailboxes.iter().sum::<usize>(), 3);
+
+    scheduler.run_until_idle();
+    let metrics = registry.metrics().unwrap();
+    let echo = metrics.runtime("Echo").unwrap();
+    assert_eq!((echo.sent, echo.received), (3, 3));
+    let busy = echo.workers.iter().find(|w| w.received == 3).unwrap();
+    assert_eq!(busy.mailbox, 0);
+    assert_eq!(busy.latency.count(), 3);
+    assert_eq!(first.receiver.try_iter().count(), 3);
+
+    // Counters of workers that exit are kept in the runtime totals
+    second.sender.send(EchoEvent::Halt).unwrap();
+    first.sender.send(EchoEvent::Halt).unwrap();
+    scheduler.run_until_idle();
+    scheduler.shutdown();
+    registry.wait();
+    let metrics = runtime.metrics().unwrap();
+    assert_eq!((metrics.exited, metrics.received), (2, 5));
+    assert!(metrics.workers.is_empty());
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+pub fn prometheus() {
+    use rust_isolate::IsolateMetricsExporter;
+    use std::io::Read;
+    use std::io::Write;
+    use std::net::TcpStream;
+
+    let mut registry = IsolateRegistry::new();
+    let mut runtime = registry
+        .bind("Echo", IsolateDriver::from_clone(Echo {}))
+        .unwrap();
+    let channel = runtime.spawn().unwrap();
+    channel
+        .sender
+        .send(EchoEvent::Echo("a".to_string()))
+        .unwrap();
+    channel.receiver.recv().unwrap();
+
+    let exporter = IsolateMetricsExporter::serve(registry.as_ref(), "127.0.0.1:0").unwrap();
+
+    // A client that connects and sends nothing holds up neither other clients nor the exporter
+    let idle = TcpStream::connect(exporter.local_addr()).unwrap();
+    let mut stream = TcpStream::connect(exporter.local_addr()).unwrap();
+    stream
+        .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.contains("isolate_runtime_received_total{runtime=\"Echo\"} 1\n"));
+    assert!(response.contains("isolate_handler_latency_seconds_count{runtime=\"Echo\""));
+
+    // A request with 8KB of headers that haven't ended is turned away
+    let mut stream = TcpStream::connect(exporter.local_addr()).unwrap();
+    let request = format!("GET /metrics HTTP/1.1\r\nX-Padding: {}", "a".repeat(8192));
+    stream.write_all(&request.as_bytes()[..8192]).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 431"));
+
+    drop(exporter);
+    drop(idle);
+    channel.sender.send(EchoEvent::Halt).unwrap();
+    registry.wait();
+}
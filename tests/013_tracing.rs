@@ -0,0 +1,197 @@
+#![cfg(feature = "tracing")]
+
+use rust_isolate::Isolate;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateIdentity;
+use rust_isolate::IsolateRegistry;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use tracing::span::Attributes;
+use tracing::span::Id;
+use tracing::span::Record;
+use tracing::Event;
+use tracing::Metadata;
+use tracing::Subscriber;
+
+// In this example, a peer asks a master for an answer and passes it back out; with the tracing
+// feature, every hop of the request is part of the same trace.
+
+#[derive(Debug)]
+enum PeerEvent {
+    Ask(IsolateIdentity, usize),
+    Answer(usize),
+}
+
+#[derive(Debug)]
+enum MasterEvent {
+    Started(IsolateIdentity),
+    Request(IsolateIdentity, usize),
+}
+
+struct PeerIsolate {}
+
+impl Isolate<PeerEvent> for PeerIsolate {
+    type Args = ();
+
+    fn spawn(
+        &self,
+        context: IsolateContext<PeerEvent>,
+        _: (),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        Box::new(move || {
+            while let Ok(event) = context.recv() {
+                match event {
+                    PeerEvent::Ask(master, value) => {
+                        let registry = context.registry().unwrap();
+                        let masters = registry.find::<MasterEvent>("Master").unwrap();
+                        let request = MasterEvent::Request(context.identity(), value);
+                        masters.send(&master, request).unwrap();
+                    }
+                    PeerEvent::Answer(value) => {
+                        context.reply(PeerEvent::Answer(value)).unwrap();
+                        break;
+                    }
+                }
+            }
+        })
+    }
+}
+
+struct MasterIsolate {}
+
+impl Isolate<MasterEvent> for MasterIsolate {
+    type Args = ();
+
+    fn spawn(
+        &self,
+        context: IsolateContext<MasterEvent>,
+        _: (),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        Box::new(move || {
+            context
+                .reply(MasterEvent::Started(context.identity()))
+                .unwrap();
+            if let Ok(MasterEvent::Request(peer, value)) = context.recv() {
+                let registry = context.registry().unwrap();
+                let peers = registry.find::<PeerEvent>("Peer").unwrap();
+                peers.send(&peer, PeerEvent::Answer(value * 2)).unwrap();
+            }
+        })
+    }
+}
+
+/// The name and parent of each span, by span id
+type Spans = Arc<Mutex<HashMap<u64, (&'static str, Option<u64>)>>>;
+
+/// Records the name and parent of every span
+struct SpanCollector {
+    next: AtomicU64,
+    spans: Spans,
+    entered: Mutex<HashMap<std::thread::ThreadId, Vec<u64>>>,
+}
+
+impl SpanCollector {
+    fn current(&self) -> Option<u64> {
+        let entered = self.entered.lock().unwrap();
+        let stack = entered.get(&std::thread::current().id())?;
+        stack.last().copied()
+    }
+}
+
+impl Subscriber for SpanCollector {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = self.next.fetch_add(1, Ordering::SeqCst);
+        let parent = match (span.is_root(), span.parent()) {
+            (_, Some(parent)) => Some(parent.into_u64()),
+            (true, None) => None,
+            (false, None) => self.current(),
+        };
+        let name = span.metadata().name();
+        self.spans.lock().unwrap().insert(id, (name, parent));
+        Id::from_u64(id)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, span: &Id) {
+        let mut entered = self.entered.lock().unwrap();
+        let stack = entered.entry(std::thread::current().id()).or_default();
+        stack.push(span.into_u64());
+    }
+
+    fn exit(&self, span: &Id) {
+        let mut entered = self.entered.lock().unwrap();
+        let stack = entered.entry(std::thread::current().id()).or_default();
+        if let Some(index) = stack.iter().rposition(|s| *s == span.into_u64()) {
+            stack.remove(index);
+        }
+    }
+}
+
+#[test]
+pub fn main() {
+    let spans: Spans = Arc::new(Mutex::new(HashMap::new()));
+    let collector = SpanCollector {
+        next: AtomicU64::new(1),
+        spans: spans.clone(),
+        entered: Mutex::new(HashMap::new()),
+    };
+    tracing::subscriber::set_global_default(collector).unwrap();
+
+    let mut registry = IsolateRegistry::new();
+    let mut peers = registry.bind("Peer", PeerIsolate {}).unwrap();
+    let mut masters = registry.bind("Master", MasterIsolate {}).unwrap();
+    let master_channel = masters.spawn().unwrap();
+    let master = match master_channel.receiver.recv().unwrap() {
+        MasterEvent::Started(identity) => identity,
+        _ => unreachable!(),
+    };
+    let peer = peers.spawn().unwrap();
+
+    peer.send(PeerEvent::Ask(master, 21)).unwrap();
+    match peer.receiver.recv().unwrap() {
+        PeerEvent::Answer(value) => assert_eq!(value, 42),
+        _ => unreachable!(),
+    }
+    drop(peer);
+    drop(master_channel);
+    registry.wait();
+
+    // The reply from the peer follows the request through the master and back
+    let spans = spans.lock().unwrap();
+    let (reply, _) = spans
+        .iter()
+        .filter(|(_, (name, _))| *name == "isolate.send")
+        .max_by_key(|(id, _)| **id)
+        .unwrap();
+    let mut trace = Vec::new();
+    let mut next = Some(*reply);
+    while let Some(id) = next {
+        let (name, parent) = spans[&id];
+        trace.push(name);
+        next = parent;
+    }
+    assert_eq!(
+        trace,
+        vec![
+            "isolate.send",
+            "isolate.receive",
+            "isolate.send",
+            "isolate.receive",
+            "isolate.send",
+            "isolate.receive",
+            "isolate.send",
+        ]
+    );
+}
@@ -0,0 +1,102 @@
+use rust_isolate::Isolate;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateEnvelope;
+use rust_isolate::IsolateIdentity;
+use rust_isolate::IsolateRegistry;
+
+// In this example, peers pass requests on to a master in envelopes; the master knows which
+// peer asked without the request saying so, and the answer keeps the id of the request.
+
+#[derive(Debug)]
+enum PeerEvent {
+    Ping,
+    Pong,
+    Add(IsolateIdentity, isize, isize),
+    Output(isize),
+}
+
+#[derive(Debug)]
+enum MasterEvent {
+    Add(isize, isize),
+}
+
+struct PeerIsolate {}
+
+impl Isolate<IsolateEnvelope<PeerEvent>> for PeerIsolate {
+    type Args = ();
+
+    fn spawn(
+        &self,
+        context: IsolateContext<IsolateEnvelope<PeerEvent>>,
+        _: (),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        Box::new(move || {
+            while let Ok(envelope) = context.recv() {
+                match envelope.message {
+                    PeerEvent::Ping => context.respond(&envelope, PeerEvent::Pong).unwrap(),
+                    PeerEvent::Add(master, a, b) => {
+                        let registry = context.registry().unwrap();
+                        let masters = registry.find::<IsolateEnvelope<MasterEvent>>("Master");
+                        let request = context.envelope_reply(&envelope, MasterEvent::Add(a, b));
+                        masters.unwrap().send(&master, request).unwrap();
+                    }
+                    PeerEvent::Output(_) => context.reply(envelope).unwrap(),
+                    PeerEvent::Pong => {}
+                }
+            }
+        })
+    }
+}
+
+struct MasterIsolate {}
+
+impl Isolate<IsolateEnvelope<MasterEvent>> for MasterIsolate {
+    type Args = ();
+
+    fn spawn(
+        &self,
+        context: IsolateContext<IsolateEnvelope<MasterEvent>>,
+        _: (),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        Box::new(move || {
+            while let Ok(request) = context.recv() {
+                let MasterEvent::Add(a, b) = request.message;
+                let registry = context.registry().unwrap();
+                let peers = registry.find::<IsolateEnvelope<PeerEvent>>("Peer").unwrap();
+                let reply = context.envelope_reply(&request, PeerEvent::Output(a + b));
+                peers.send(&request.sender.unwrap(), reply).unwrap();
+            }
+        })
+    }
+}
+
+#[test]
+pub fn main() {
+    let mut registry = IsolateRegistry::new();
+    let mut peers = registry.bind("Peer", PeerIsolate {}).unwrap();
+    let mut masters = registry.bind("Master", MasterIsolate {}).unwrap();
+    let master = masters.spawn().unwrap();
+    let peer = peers.spawn().unwrap();
+
+    // A plain message has no sender, so the answer comes back out of the peer
+    peer.send_message(PeerEvent::Ping).unwrap();
+    let pong = peer.receiver.recv().unwrap();
+    assert!(matches!(pong.message, PeerEvent::Pong));
+    assert!(pong.sender.is_some());
+
+    // The master answers whichever peer asked, and the answer keeps the request id
+    let tree = registry.tree().unwrap();
+    let roots = tree.roots();
+    let master_identity = roots.iter().find(|id| masters.find(id).is_some()).unwrap();
+    let request =
+        IsolateEnvelope::new(PeerEvent::Add(*master_identity, 1, 2)).with_header("user", "42");
+    let correlation = request.correlation;
+    peer.send(request).unwrap();
+    let output = peer.receiver.recv().unwrap();
+    assert_eq!(output.correlation, correlation);
+    assert!(matches!(output.message, PeerEvent::Output(3)));
+
+    drop(peer);
+    drop(master);
+    registry.wait();
+}
@@ -0,0 +1,78 @@
+use crossbeam::unbounded;
+use crossbeam::Receiver;
+use rust_isolate::Isolate;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolatePriority;
+use rust_isolate::IsolateRuntime;
+use rust_isolate::IsolateRuntimeWait;
+
+// In this example, a worker is kept busy while messages queue up behind it; urgent messages
+// are handled before everything that was already waiting.
+
+#[derive(Debug, PartialEq)]
+enum JobEvent {
+    Busy,
+    Job(&'static str),
+}
+
+struct JobIsolate {}
+
+impl Isolate<JobEvent> for JobIsolate {
+    type Args = Receiver<()>;
+
+    fn spawn(
+        &self,
+        context: IsolateContext<JobEvent>,
+        gate: Receiver<()>,
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        Box::new(move || {
+            while let Ok(event) = context.recv() {
+                if event == JobEvent::Busy {
+                    // Stay busy until the test has queued up its messages
+                    context.reply(JobEvent::Busy).unwrap();
+                    gate.recv().unwrap();
+                } else {
+                    context.reply(event).unwrap();
+                }
+            }
+        })
+    }
+}
+
+#[test]
+pub fn main() {
+    let mut runtime = IsolateRuntime::new(JobIsolate {});
+    let (gate, gate_receiver) = unbounded();
+    let channel = runtime.spawn_with(gate_receiver).unwrap();
+
+    channel.send(JobEvent::Busy).unwrap();
+    assert_eq!(channel.receiver.recv().unwrap(), JobEvent::Busy);
+
+    channel.send(JobEvent::Job("first")).unwrap();
+    channel.send(JobEvent::Job("second")).unwrap();
+    channel
+        .send_priority(JobEvent::Job("high"), IsolatePriority::High)
+        .unwrap();
+    channel
+        .send_priority(JobEvent::Job("system"), IsolatePriority::System)
+        .unwrap();
+    channel
+        .send_priority(JobEvent::Job("normal"), IsolatePriority::Normal)
+        .unwrap();
+    gate.send(()).unwrap();
+
+    let handled: Vec<_> = (0..5).map(|_| channel.receiver.recv().unwrap()).collect();
+    assert_eq!(
+        handled,
+        vec![
+            JobEvent::Job("system"),
+            JobEvent::Job("high"),
+            JobEvent::Job("first"),
+            JobEvent::Job("second"),
+            JobEvent::Job("normal"),
+        ]
+    );
+
+    drop(channel);
+    runtime.wait();
+}
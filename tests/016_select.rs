@@ -0,0 +1,87 @@
+use crossbeam::unbounded;
+use crossbeam::Receiver;
+use rust_isolate::Isolate;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateRuntime;
+use rust_isolate::IsolateRuntimeWait;
+use rust_isolate::IsolateSelected;
+
+// In this example, a worker waits on its own messages and a feed from elsewhere at once, and
+// holds back unrelated messages while it waits for an acknowledgement.
+
+#[derive(Debug, PartialEq)]
+enum WorkerEvent {
+    Job(&'static str),
+    Wait,
+    Ack,
+    Output(String),
+}
+
+struct WorkerIsolate {}
+
+impl Isolate<WorkerEvent> for WorkerIsolate {
+    type Args = Receiver<&'static str>;
+
+    fn spawn(
+        &self,
+        context: IsolateContext<WorkerEvent>,
+        feed: Receiver<&'static str>,
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        let mut feed_open = true;
+        Box::new(move || loop {
+            let select = context.select::<String>();
+            let select = match feed_open {
+                true => select.recv(&feed, |item| format!("feed:{}", item)),
+                false => select,
+            };
+            let output = match select.wait() {
+                Ok(IsolateSelected::Message(WorkerEvent::Job(job))) => format!("inbox:{}", job),
+                Ok(IsolateSelected::Message(WorkerEvent::Wait)) => {
+                    context.recv_matching(|m| *m == WorkerEvent::Ack).unwrap();
+                    format!("acked:{}", context.stashed())
+                }
+                Ok(IsolateSelected::Message(_)) => continue,
+                Ok(IsolateSelected::Channel(output)) => output,
+                Ok(IsolateSelected::Closed(_)) => {
+                    feed_open = false;
+                    "feed:closed".to_string()
+                }
+                Err(_) => break,
+            };
+            context.reply(WorkerEvent::Output(output)).unwrap();
+        })
+    }
+}
+
+#[test]
+pub fn main() {
+    let mut runtime = IsolateRuntime::new(WorkerIsolate {});
+    let (feed, feed_receiver) = unbounded();
+    let channel = runtime.spawn_with(feed_receiver).unwrap();
+    let output = || match channel.receiver.recv().unwrap() {
+        WorkerEvent::Output(output) => output,
+        _ => unreachable!(),
+    };
+
+    feed.send("x").unwrap();
+    assert_eq!(output(), "feed:x");
+    channel.send(WorkerEvent::Job("a")).unwrap();
+    assert_eq!(output(), "inbox:a");
+
+    // Jobs that arrive while waiting for the acknowledgement are handled after it, in order
+    channel.send(WorkerEvent::Wait).unwrap();
+    channel.send(WorkerEvent::Job("b")).unwrap();
+    channel.send(WorkerEvent::Job("c")).unwrap();
+    channel.send(WorkerEvent::Ack).unwrap();
+    assert_eq!(output(), "acked:2");
+    assert_eq!(output(), "inbox:b");
+    assert_eq!(output(), "inbox:c");
+
+    drop(feed);
+    assert_eq!(output(), "feed:closed");
+    channel.send(WorkerEvent::Job("d")).unwrap();
+    assert_eq!(output(), "inbox:d");
+
+    drop(channel);
+    runtime.wait();
+}
@@ -0,0 +1,164 @@
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateFileJournal;
+use rust_isolate::IsolateFlow;
+use rust_isolate::IsolateIdentity;
+use rust_isolate::IsolateJournal;
+use rust_isolate::IsolateMemoryJournal;
+use rust_isolate::IsolatePersistent;
+use rust_isolate::IsolatePersistentDriver;
+use rust_isolate::IsolateRecord;
+use rust_isolate::IsolateRuntime;
+use rust_isolate::IsolateRuntimeError;
+use rust_isolate::IsolateRuntimeWait;
+use std::fs;
+
+// In this example, an account keeps its balance as a series of deposits and withdrawals; when
+// the worker comes back under the same identity, its balance is recovered from the journal.
+
+#[derive(Debug, PartialEq)]
+enum AccountEvent {
+    Deposit(i64),
+    Withdraw(i64),
+    Balance,
+    Halt,
+    Output(i64),
+    Rejected,
+}
+
+enum AccountChange {
+    Deposited(i64),
+    Withdrew(i64),
+}
+
+impl IsolateRecord for AccountChange {
+    fn to_record(&self) -> Vec<u8> {
+        let (tag, amount) = match self {
+            AccountChange::Deposited(amount) => (b'+', amount),
+            AccountChange::Withdrew(amount) => (b'-', amount),
+        };
+        let mut record = vec![tag];
+        record.extend(amount.to_record());
+        record
+    }
+
+    fn from_record(record: &[u8]) -> Option<Self> {
+        let amount = i64::from_record(record.get(1..)?)?;
+        match record.first()? {
+            b'+' => Some(AccountChange::Deposited(amount)),
+            b'-' => Some(AccountChange::Withdrew(amount)),
+            _ => None,
+        }
+    }
+}
+
+struct Account {
+    balance: i64,
+}
+
+impl IsolateRecord for Account {
+    fn to_record(&self) -> Vec<u8> {
+        self.balance.to_record()
+    }
+
+    fn from_record(record: &[u8]) -> Option<Self> {
+        i64::from_record(record).map(|balance| Account { balance })
+    }
+}
+
+impl IsolatePersistent<AccountEvent> for Account {
+    type Event = AccountChange;
+
+    fn handle(
+        &self,
+        context: &IsolateContext<AccountEvent>,
+        message: AccountEvent,
+        events: &mut Vec<AccountChange>,
+    ) -> IsolateFlow {
+        match message {
+            AccountEvent::Deposit(amount) => events.push(AccountChange::Deposited(amount)),
+            AccountEvent::Withdraw(amount) if amount <= self.balance => {
+                events.push(AccountChange::Withdrew(amount))
+            }
+            AccountEvent::Withdraw(_) => context.reply(AccountEvent::Rejected).unwrap(),
+            AccountEvent::Balance => context.reply(AccountEvent::Output(self.balance)).unwrap(),
+            AccountEvent::Halt => return IsolateFlow::Halt,
+            _ => {}
+        }
+        IsolateFlow::Continue
+    }
+
+    fn apply(&mut self, event: &AccountChange) {
+        match event {
+            AccountChange::Deposited(amount) => self.balance += amount,
+            AccountChange::Withdrew(amount) => self.balance -= amount,
+        }
+    }
+}
+
+fn run_account(runtime: &mut IsolateRuntime<AccountEvent, i64>, identity: IsolateIdentity) -> i64 {
+    let channel = runtime.spawn_as(identity, 0i64).unwrap();
+    channel.sender.send(AccountEvent::Deposit(50)).unwrap();
+    channel.sender.send(AccountEvent::Withdraw(500)).unwrap();
+    channel.sender.send(AccountEvent::Withdraw(20)).unwrap();
+    channel.sender.send(AccountEvent::Balance).unwrap();
+    channel.sender.send(AccountEvent::Halt).unwrap();
+    assert_eq!(channel.receiver.recv().unwrap(), AccountEvent::Rejected);
+    match channel.receiver.recv().unwrap() {
+        AccountEvent::Output(balance) => balance,
+        event => panic!("Unexpected {:?}", event),
+    }
+}
+
+#[test]
+pub fn test_recover_from_memory_journal() {
+    let journal = IsolateMemoryJournal::new();
+    let driver = IsolatePersistentDriver::new(journal.clone(), |balance| Account { balance })
+        .snapshot_every(2);
+    let mut runtime = IsolateRuntime::new(driver);
+    let identity = IsolateIdentity::new();
+
+    // A running worker's identity can't be taken
+    let channel = runtime.spawn_as(identity, 0i64).unwrap();
+    match runtime.spawn_as(identity, 0i64) {
+        Err(IsolateRuntimeError::IdentityInUse(taken)) => assert_eq!(taken, identity),
+        _ => panic!("Spawned a second worker as {}", identity),
+    }
+    channel.sender.send(AccountEvent::Halt).unwrap();
+    runtime.wait();
+
+    assert_eq!(run_account(&mut runtime, identity), 30);
+    runtime.wait();
+    assert_eq!(
+        journal.snapshot(&identity).unwrap(),
+        Some((2, 30i64.to_record()))
+    );
+
+    // Coming back, the balance starts where it left off rather than from the spawn-time args
+    assert_eq!(run_account(&mut runtime, identity), 60);
+    runtime.wait();
+    assert_eq!(journal.events(&identity, 0).unwrap().len(), 4);
+}
+
+#[test]
+pub fn test_recover_from_file_journal() {
+    let directory =
+        std::env::temp_dir().join(format!("isolate-accounts-{}", IsolateIdentity::new()));
+    let identity = IsolateIdentity::new();
+
+    for expected in &[30, 60, 90] {
+        // A fresh journal and runtime each time, as after a restart
+        let driver = IsolatePersistentDriver::open(&directory, |balance| Account { balance })
+            .unwrap()
+            .snapshot_every(3);
+        let mut runtime = IsolateRuntime::new(driver);
+        assert_eq!(run_account(&mut runtime, identity), *expected);
+        runtime.wait();
+    }
+
+    let journal = IsolateFileJournal::new(&directory).unwrap();
+    assert_eq!(
+        journal.snapshot(&identity).unwrap(),
+        Some((6, 90i64.to_record()))
+    );
+    fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,166 @@
+use crossbeam::unbounded;
+use crossbeam::Receiver;
+use rust_isolate::Isolate;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateDurableMailbox;
+use rust_isolate::IsolateIdentity;
+use rust_isolate::IsolateMailboxSync;
+use rust_isolate::IsolateRecord;
+use rust_isolate::IsolateRuntime;
+use rust_isolate::IsolateRuntimeWait;
+use std::fs;
+
+// In this example, a job worker crashes part way through its jobs; after a restart, a worker
+// with the same identity picks up the jobs that weren't done from the durable mailbox.
+
+#[derive(Debug, PartialEq)]
+enum JobEvent {
+    Run(String),
+    Done(String),
+}
+
+impl IsolateRecord for JobEvent {
+    fn to_record(&self) -> Vec<u8> {
+        match self {
+            JobEvent::Run(name) => format!("R{}", name).into_bytes(),
+            JobEvent::Done(name) => format!("D{}", name).into_bytes(),
+        }
+    }
+
+    fn from_record(record: &[u8]) -> Option<Self> {
+        let record = String::from_record(record)?;
+        match record.split_at(1) {
+            ("R", name) => Some(JobEvent::Run(name.to_string())),
+            ("D", name) => Some(JobEvent::Done(name.to_string())),
+            _ => None,
+        }
+    }
+}
+
+struct JobIsolate {}
+
+impl Isolate<JobEvent> for JobIsolate {
+    /// The name of a job the worker crashes on
+    type Args = Option<String>;
+
+    fn spawn(
+        &self,
+        context: IsolateContext<JobEvent>,
+        crash_on: Option<String>,
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        Box::new(move || {
+            while let Ok(event) = context.recv() {
+                if let JobEvent::Run(name) = event {
+                    if crash_on.as_ref() == Some(&name) {
+                        panic!("Crashed on {}", name);
+                    }
+                    context.reply(JobEvent::Done(name)).unwrap();
+                }
+            }
+        })
+    }
+}
+
+struct OneJobIsolate {}
+
+impl Isolate<JobEvent> for OneJobIsolate {
+    /// The name of the one job the worker handles before it exits, and a gate it waits on
+    /// before it starts
+    type Args = (String, Receiver<()>);
+
+    fn spawn(
+        &self,
+        context: IsolateContext<JobEvent>,
+        (name, gate): (String, Receiver<()>),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        Box::new(move || {
+            gate.recv().unwrap();
+            let event = context.recv_matching(|event| *event == job(&name)).unwrap();
+            if let JobEvent::Run(name) = event {
+                context.reply(JobEvent::Done(name)).unwrap();
+            }
+        })
+    }
+}
+
+fn job(name: &str) -> JobEvent {
+    JobEvent::Run(name.to_string())
+}
+
+fn done(name: &str) -> JobEvent {
+    JobEvent::Done(name.to_string())
+}
+
+#[test]
+pub fn test_redeliver_after_restart() {
+    let directory = std::env::temp_dir().join(format!("isolate-jobs-{}", IsolateIdentity::new()));
+    let identity = IsolateIdentity::new();
+    let open = || {
+        IsolateDurableMailbox::new(&directory)
+            .unwrap()
+            .with_sync(IsolateMailboxSync::Every(2))
+            .with_segment_size(16)
+    };
+
+    let mut runtime = IsolateRuntime::new(JobIsolate {}).with_mailbox(open());
+    let channel = runtime.spawn_as(identity, Some("b".to_string())).unwrap();
+    channel.send(job("a")).unwrap();
+    channel.send(job("b")).unwrap();
+    channel.send(job("c")).unwrap();
+    assert_eq!(channel.receiver.recv().unwrap(), done("a"));
+    runtime.wait();
+
+    // After the restart, the jobs the crashed worker didn't finish come first
+    let mailbox = open();
+    assert_eq!(mailbox.pending().unwrap(), vec![identity]);
+    let mut runtime = IsolateRuntime::new(JobIsolate {}).with_mailbox(mailbox);
+    let channel = runtime.spawn_as(identity, None::<String>).unwrap();
+    channel.send(job("d")).unwrap();
+    assert_eq!(channel.receiver.recv().unwrap(), done("b"));
+    assert_eq!(channel.receiver.recv().unwrap(), done("c"));
+    assert_eq!(channel.receiver.recv().unwrap(), done("d"));
+    runtime.as_ref().stop(&identity).unwrap();
+    runtime.wait();
+
+    // Everything was handled, so the worker's mailbox is gone
+    assert!(open().pending().unwrap().is_empty());
+    assert!(!directory.join(identity.to_string()).exists());
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+pub fn test_raw_and_durable_sends() {
+    let directory = std::env::temp_dir().join(format!("isolate-jobs-{}", IsolateIdentity::new()));
+    let identity = IsolateIdentity::new();
+    let open = || IsolateDurableMailbox::new(&directory).unwrap();
+
+    let mut runtime = IsolateRuntime::new(OneJobIsolate {}).with_mailbox(open());
+
+    // A message sent straight on the sender skips the mailbox; the worker handles it and exits
+    // with the durable message still waiting
+    let (gate, gate_receiver) = unbounded();
+    let channel = runtime
+        .spawn_as(identity, ("raw".to_string(), gate_receiver))
+        .unwrap();
+    channel.sender.send(job("raw")).unwrap();
+    channel.send(job("durable")).unwrap();
+    gate.send(()).unwrap();
+    assert_eq!(channel.receiver.recv().unwrap(), done("raw"));
+    runtime.wait();
+
+    // The durable message wasn't acknowledged along with the raw one, so it is still there
+    let mailbox = open();
+    assert_eq!(mailbox.pending().unwrap(), vec![identity]);
+    let mut runtime = IsolateRuntime::new(OneJobIsolate {}).with_mailbox(mailbox);
+    let (gate, gate_receiver) = unbounded();
+    let channel = runtime
+        .spawn_as(identity, ("durable".to_string(), gate_receiver))
+        .unwrap();
+    gate.send(()).unwrap();
+    assert_eq!(channel.receiver.recv().unwrap(), done("durable"));
+    runtime.wait();
+    assert!(open().pending().unwrap().is_empty());
+
+    fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,199 @@
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateDeadLetterReason;
+use rust_isolate::IsolateFsm;
+use rust_isolate::IsolateFsmState;
+use rust_isolate::IsolateIdentity;
+use rust_isolate::IsolateRegistry;
+use rust_isolate::IsolateTransition;
+use rust_isolate::IsolateUnhandled;
+use std::thread;
+use std::time::Duration;
+
+// In this example, a connection worker is a state machine; data that arrives before the login
+// waits for it, a connection that never logs in is closed, and a closing connection turns
+// everything away.
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ConnectionState {
+    Connecting,
+    Authenticated,
+    Closing,
+}
+
+#[derive(Debug, PartialEq)]
+enum ConnectionEvent {
+    Login(String),
+    Data(String),
+    Close,
+    Output(String),
+}
+
+struct Connection {
+    user: String,
+}
+
+type Transition = IsolateTransition<ConnectionState, ConnectionEvent>;
+
+fn connecting(
+    _: &IsolateContext<ConnectionEvent>,
+    connection: &mut Connection,
+    event: ConnectionEvent,
+) -> Transition {
+    match event {
+        ConnectionEvent::Login(user) => {
+            connection.user = user;
+            IsolateTransition::Goto(ConnectionState::Authenticated)
+        }
+        event => IsolateTransition::Unhandled(event),
+    }
+}
+
+fn authenticated(
+    context: &IsolateContext<ConnectionEvent>,
+    connection: &mut Connection,
+    event: ConnectionEvent,
+) -> Transition {
+    match event {
+        ConnectionEvent::Data(data) => {
+            let output = format!("{}: {}", connection.user, data);
+            context.reply(ConnectionEvent::Output(output)).unwrap();
+            IsolateTransition::Stay
+        }
+        ConnectionEvent::Close => IsolateTransition::Goto(ConnectionState::Closing),
+        event => IsolateTransition::Unhandled(event),
+    }
+}
+
+fn closing(
+    _: &IsolateContext<ConnectionEvent>,
+    _: &mut Connection,
+    event: ConnectionEvent,
+) -> Transition {
+    match event {
+        ConnectionEvent::Close => IsolateTransition::Halt,
+        event => IsolateTransition::Unhandled(event),
+    }
+}
+
+fn connection_machine(
+    login_timeout: Duration,
+) -> IsolateFsm<(), ConnectionState, Connection, ConnectionEvent> {
+    let timed_out = |context: &IsolateContext<ConnectionEvent>, _: &mut Connection| {
+        context
+            .reply(ConnectionEvent::Output("timed out".to_string()))
+            .unwrap();
+        IsolateTransition::Goto(ConnectionState::Closing)
+    };
+    IsolateFsm::new(|_| {
+        let connection = Connection {
+            user: String::new(),
+        };
+        (ConnectionState::Connecting, connection)
+    })
+    .state(
+        ConnectionState::Connecting,
+        IsolateFsmState::new(connecting)
+            .unhandled(IsolateUnhandled::Defer)
+            .timeout(login_timeout, timed_out),
+    )
+    .state(
+        ConnectionState::Authenticated,
+        IsolateFsmState::new(authenticated),
+    )
+    .state(ConnectionState::Closing, IsolateFsmState::new(closing))
+    .transition(ConnectionState::Connecting, ConnectionState::Authenticated)
+    .transition(ConnectionState::Connecting, ConnectionState::Closing)
+    .transition(ConnectionState::Authenticated, ConnectionState::Closing)
+}
+
+#[test]
+pub fn test_defer_and_reject() {
+    let mut registry = IsolateRegistry::new();
+    let mut connections = registry
+        .bind("Connections", connection_machine(Duration::from_secs(60)))
+        .unwrap();
+    let dead_letters = registry.dead_letters().unwrap().subscribe();
+    let identity = IsolateIdentity::new();
+    let connection = connections.spawn_as(identity, ()).unwrap();
+
+    // Data sent before the login is handled once the connection is authenticated
+    connection
+        .send(ConnectionEvent::Data("early".to_string()))
+        .unwrap();
+    connection
+        .send(ConnectionEvent::Login("ann".to_string()))
+        .unwrap();
+    let output = ConnectionEvent::Output("ann: early".to_string());
+    assert_eq!(connection.receiver.recv().unwrap(), output);
+    let state = connections.current_state(&identity).unwrap();
+    assert_eq!(state, Some(ConnectionState::Authenticated));
+
+    // A second login isn't handled once authenticated
+    connection
+        .send(ConnectionEvent::Login("bob".to_string()))
+        .unwrap();
+    let letter = dead_letters.recv().unwrap();
+    assert_eq!(letter.reason, IsolateDeadLetterReason::Rejected);
+    let login = ConnectionEvent::Login("bob".to_string());
+    assert_eq!(letter.take::<ConnectionEvent>().unwrap(), login);
+
+    // Nor is data once closing
+    connection.send(ConnectionEvent::Close).unwrap();
+    connection
+        .send(ConnectionEvent::Data("late".to_string()))
+        .unwrap();
+    let letter = dead_letters.recv().unwrap();
+    assert_eq!(letter.reason, IsolateDeadLetterReason::Rejected);
+    let state = connections.current_state(&identity).unwrap();
+    assert_eq!(state, Some(ConnectionState::Closing));
+
+    connection.send(ConnectionEvent::Close).unwrap();
+    registry.wait();
+}
+
+#[test]
+pub fn test_state_timeout() {
+    let mut registry = IsolateRegistry::new();
+    let mut connections = registry
+        .bind("Connections", connection_machine(Duration::from_millis(20)))
+        .unwrap();
+    let identity = IsolateIdentity::new();
+    let connection = connections.spawn_as(identity, ()).unwrap();
+
+    // Without a login, the connection closes by itself
+    let output = ConnectionEvent::Output("timed out".to_string());
+    assert_eq!(connection.receiver.recv().unwrap(), output);
+    let state = connections.current_state(&identity).unwrap();
+    assert_eq!(state, Some(ConnectionState::Closing));
+    assert_eq!(
+        connections.current_state::<String>(&identity).unwrap(),
+        None
+    );
+
+    connection.send(ConnectionEvent::Close).unwrap();
+    registry.wait();
+}
+
+#[test]
+pub fn test_deferred_on_exit() {
+    let mut registry = IsolateRegistry::new();
+    let mut connections = registry
+        .bind("Connections", connection_machine(Duration::from_secs(60)))
+        .unwrap();
+    let dead_letters = registry.dead_letters().unwrap().subscribe();
+    let identity = IsolateIdentity::new();
+    let connection = connections.spawn_as(identity, ()).unwrap();
+
+    // Data waiting for a login that never comes isn't lost when the connection stops
+    connection
+        .send(ConnectionEvent::Data("early".to_string()))
+        .unwrap();
+    thread::sleep(Duration::from_millis(20));
+    connections.stop(&identity).unwrap();
+    let letter = dead_letters.recv().unwrap();
+    assert_eq!(letter.reason, IsolateDeadLetterReason::Unprocessed);
+    let data = ConnectionEvent::Data("early".to_string());
+    assert_eq!(letter.take::<ConnectionEvent>().unwrap(), data);
+
+    registry.wait();
+}
@@ -0,0 +1,201 @@
+use rust_isolate::Isolate;
+use rust_isolate::IsolateChannel;
+use rust_isolate::IsolateChannelError;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateDeadLetterReason;
+use rust_isolate::IsolateRateLimit;
+use rust_isolate::IsolateRegistry;
+use rust_isolate::IsolateRuntime;
+use rust_isolate::IsolateRuntimeWait;
+use rust_isolate::IsolateThrottleMode;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+// In this example, a chat room limits how fast messages reach it, so a chatty client can't
+// flood everyone else; peers pass on what their clients say, and find out when they were cut
+// off.
+
+#[derive(Debug, Clone, PartialEq)]
+enum ChatEvent {
+    Say(String),
+    Post(String),
+    Posted(bool),
+}
+
+struct RoomIsolate {}
+
+impl Isolate<ChatEvent> for RoomIsolate {
+    type Args = ();
+
+    fn spawn(
+        &self,
+        context: IsolateContext<ChatEvent>,
+        _: (),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        Box::new(move || {
+            while let Ok(event) = context.recv() {
+                if let ChatEvent::Post(text) = event {
+                    context.reply(ChatEvent::Post(text)).unwrap();
+                }
+            }
+        })
+    }
+}
+
+struct PeerIsolate {}
+
+impl Isolate<ChatEvent> for PeerIsolate {
+    /// The room the peer posts to
+    type Args = IsolateChannel<ChatEvent>;
+
+    fn spawn(
+        &self,
+        context: IsolateContext<ChatEvent>,
+        room: IsolateChannel<ChatEvent>,
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        Box::new(move || {
+            while let Ok(event) = context.recv() {
+                if let ChatEvent::Say(text) = event {
+                    let posted = room.send(ChatEvent::Post(text)).is_ok();
+                    context.reply(ChatEvent::Posted(posted)).unwrap();
+                }
+            }
+        })
+    }
+}
+
+fn say(text: &str) -> ChatEvent {
+    ChatEvent::Say(text.to_string())
+}
+
+#[test]
+pub fn test_reject_over_limit() {
+    let mut registry = IsolateRegistry::new();
+    let mut rooms = registry.bind("Rooms", RoomIsolate {}).unwrap();
+    let dead_letters = registry.dead_letters().unwrap().subscribe();
+    rooms
+        .set_rate_limit(IsolateRateLimit::new(3, Duration::from_secs(60)))
+        .unwrap();
+    let room = rooms.spawn().unwrap();
+
+    for text in &["one", "two", "three"] {
+        room.send(ChatEvent::Post(text.to_string())).unwrap();
+    }
+    match room.send(ChatEvent::Post("four".to_string())) {
+        Err(IsolateChannelError::Throttled) => {}
+        result => panic!("Sent over the limit: {:?}", result),
+    }
+    let letter = dead_letters.recv().unwrap();
+    assert_eq!(letter.reason, IsolateDeadLetterReason::Throttled);
+    let post = ChatEvent::Post("four".to_string());
+    assert_eq!(letter.take::<ChatEvent>().unwrap(), post);
+
+    let metrics = rooms.metrics().unwrap();
+    assert_eq!(metrics.throttled, 1);
+    assert_eq!(metrics.workers[0].throttled, 1);
+
+    drop(room);
+    registry.wait();
+}
+
+#[test]
+pub fn test_limit_per_sender() {
+    let limit = IsolateRateLimit::new(2, Duration::from_secs(60)).per_sender();
+    let mut rooms = IsolateRuntime::new(RoomIsolate {}).with_rate_limit(limit);
+    let mut peers = IsolateRuntime::new(PeerIsolate {});
+    let room = rooms.spawn().unwrap();
+    let chatty = peers.spawn_with(room.clone()).unwrap();
+    let quiet = peers.spawn_with(room.clone()).unwrap();
+
+    // Each peer has its own allowance, so the chatty one doesn't use up the quiet one's
+    for text in &["a", "b", "c"] {
+        chatty.send(say(text)).unwrap();
+    }
+    let posted: Vec<_> = (0..3).map(|_| chatty.receiver.recv().unwrap()).collect();
+    let expected = [true, true, false];
+    assert_eq!(
+        posted,
+        expected
+            .iter()
+            .map(|p| ChatEvent::Posted(*p))
+            .collect::<Vec<_>>()
+    );
+    quiet.send(say("hello")).unwrap();
+    assert_eq!(quiet.receiver.recv().unwrap(), ChatEvent::Posted(true));
+    assert_eq!(rooms.as_ref().metrics().unwrap().throttled, 1);
+
+    drop((room, chatty, quiet));
+    peers.wait();
+    rooms.wait();
+}
+
+#[test]
+pub fn test_delay_over_limit() {
+    let mut rooms = IsolateRuntime::new(RoomIsolate {});
+    let room = rooms.spawn().unwrap();
+
+    // A client's handle to the room holds it to 100 messages a second, one at a time
+    let limit = IsolateRateLimit::new(100, Duration::from_secs(1))
+        .burst(1)
+        .mode(IsolateThrottleMode::Delay);
+    let client = room.throttled(limit);
+    let started = Instant::now();
+    for text in &["a", "b", "c", "d", "e"] {
+        client.send(ChatEvent::Post(text.to_string())).unwrap();
+    }
+    assert!(started.elapsed() >= Duration::from_millis(30));
+    for text in &["a", "b", "c", "d", "e"] {
+        assert_eq!(
+            room.receiver.recv().unwrap(),
+            ChatEvent::Post(text.to_string())
+        );
+    }
+    assert_eq!(rooms.as_ref().metrics().unwrap().throttled, 4);
+
+    drop((room, client));
+    rooms.wait();
+}
+
+#[test]
+pub fn test_fan_out_does_not_wait() {
+    let limit = IsolateRateLimit::new(1, Duration::from_secs(60)).mode(IsolateThrottleMode::Delay);
+    let mut rooms = IsolateRuntime::new(RoomIsolate {}).with_rate_limit(limit);
+    let room = rooms.spawn().unwrap();
+
+    // A broadcast rejects a copy that would have to wait, rather than hold up other workers
+    let started = Instant::now();
+    let report = rooms
+        .as_ref()
+        .broadcast(ChatEvent::Post("a".to_string()))
+        .unwrap();
+    assert!(report.is_complete());
+    let report = rooms
+        .as_ref()
+        .broadcast(ChatEvent::Post("b".to_string()))
+        .unwrap();
+    match report.failed.as_slice() {
+        [(_, IsolateChannelError::Throttled)] => {}
+        failed => panic!("Broadcast over the limit: {:?}", failed),
+    }
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert_eq!(
+        room.receiver.recv().unwrap(),
+        ChatEvent::Post("a".to_string())
+    );
+
+    // A message to a worker that has exited isn't held to the limit
+    let identity = report.failed[0].0;
+    rooms.as_ref().stop(&identity).unwrap();
+    while rooms.as_ref().find(&identity).is_some() {
+        thread::sleep(Duration::from_millis(1));
+    }
+    match room.send(ChatEvent::Post("c".to_string())) {
+        Err(IsolateChannelError::Disconnected) => {}
+        result => panic!("Sent to an exited worker: {:?}", result),
+    }
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    drop(room);
+    rooms.wait();
+}
@@ -0,0 +1,235 @@
+use rust_isolate::Isolate;
+use rust_isolate::IsolateBreakerError;
+use rust_isolate::IsolateBreakerState;
+use rust_isolate::IsolateCircuitBreaker;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateEnvelope;
+use rust_isolate::IsolateIdentity;
+use rust_isolate::IsolateRuntime;
+use rust_isolate::IsolateRuntimeWait;
+use rust_isolate::IsolateTestScheduler;
+use std::thread;
+use std::time::Duration;
+
+// In this example, peers ask a master for answers through a shared circuit breaker; when the
+// master stalls or crashes the breaker opens, so peers fail fast instead of piling on more
+// requests, and it closes again once the master is answering.
+
+#[derive(Debug, PartialEq)]
+enum MasterEvent {
+    Query(u32),
+    Answer(u32),
+    Refused,
+    Stall(Duration),
+    Crash,
+}
+
+struct MasterIsolate {}
+
+impl Isolate<IsolateEnvelope<MasterEvent>> for MasterIsolate {
+    type Args = ();
+
+    fn spawn(
+        &self,
+        context: IsolateContext<IsolateEnvelope<MasterEvent>>,
+        _: (),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        Box::new(move || {
+            while let Ok(request) = context.recv() {
+                let answer = match request.message {
+                    MasterEvent::Query(0) => MasterEvent::Refused,
+                    MasterEvent::Query(n) => MasterEvent::Answer(n * 2),
+                    MasterEvent::Stall(duration) => {
+                        thread::sleep(duration);
+                        continue;
+                    }
+                    MasterEvent::Crash => panic!("Master crashed"),
+                    _ => continue,
+                };
+                let reply = context.envelope_reply(&request, answer);
+                context.reply(reply).unwrap();
+            }
+        })
+    }
+}
+
+const TIMEOUT: Duration = Duration::from_millis(20);
+
+fn ask(
+    breaker: &IsolateCircuitBreaker<IsolateEnvelope<MasterEvent>>,
+    event: MasterEvent,
+) -> Result<MasterEvent, IsolateBreakerError> {
+    breaker
+        .ask(event, TIMEOUT)
+        .map(|reply| reply.into_message())
+}
+
+#[test]
+pub fn test_open_on_timeouts() {
+    let mut masters = IsolateRuntime::new(MasterIsolate {});
+    let master = masters.spawn().unwrap();
+    let breaker = IsolateCircuitBreaker::new(master.clone())
+        .with_threshold(2)
+        .with_reset_after(Duration::from_millis(100));
+    let peer = breaker.clone();
+    let changes = breaker.subscribe();
+
+    assert_eq!(
+        ask(&peer, MasterEvent::Query(1)).unwrap(),
+        MasterEvent::Answer(2)
+    );
+
+    // The master stalls, so two asks in a row time out and the breaker opens
+    master
+        .send_message(MasterEvent::Stall(Duration::from_millis(80)))
+        .unwrap();
+    for n in 2..4 {
+        match ask(&peer, MasterEvent::Query(n)) {
+            Err(IsolateBreakerError::Timeout) => {}
+            result => panic!("Expected a timeout: {:?}", result),
+        }
+    }
+    assert_eq!(breaker.state(), IsolateBreakerState::Open);
+    assert_eq!(changes.recv().unwrap(), IsolateBreakerState::Open);
+    match ask(&peer, MasterEvent::Query(4)) {
+        Err(IsolateBreakerError::Open) => {}
+        result => panic!("Expected to fail fast: {:?}", result),
+    }
+
+    // The reset timer runs out, so a trial ask goes through and closes the breaker; it gets its
+    // own answer, rather than the late answers to the asks that timed out
+    thread::sleep(Duration::from_millis(120));
+    assert_eq!(breaker.state(), IsolateBreakerState::HalfOpen);
+    assert_eq!(
+        ask(&peer, MasterEvent::Query(5)).unwrap(),
+        MasterEvent::Answer(10)
+    );
+    assert_eq!(breaker.state(), IsolateBreakerState::Closed);
+    let changes: Vec<_> = changes.try_iter().collect();
+    assert_eq!(
+        changes,
+        vec![IsolateBreakerState::HalfOpen, IsolateBreakerState::Closed]
+    );
+
+    drop((master, breaker, peer));
+    masters.wait();
+}
+
+#[test]
+pub fn test_concurrent_asks() {
+    let mut masters = IsolateRuntime::new(MasterIsolate {});
+    let master = masters.spawn().unwrap();
+    let breaker = IsolateCircuitBreaker::new(master.clone());
+
+    // Peers sharing the breaker each get the answers to their own queries
+    let peers: Vec<_> = (1..5)
+        .map(|peer| {
+            let breaker = breaker.clone();
+            thread::spawn(move || {
+                for n in 0..20 {
+                    let query = peer * 100 + n + 1;
+                    let reply = breaker.ask(MasterEvent::Query(query), Duration::from_secs(5));
+                    assert_eq!(reply.unwrap().message, MasterEvent::Answer(query * 2));
+                }
+            })
+        })
+        .collect();
+    peers.into_iter().for_each(|peer| peer.join().unwrap());
+    assert_eq!(breaker.state(), IsolateBreakerState::Closed);
+
+    drop((master, breaker));
+    masters.wait();
+}
+
+#[test]
+pub fn test_failure_replies() {
+    let mut masters = IsolateRuntime::new(MasterIsolate {});
+    let master = masters.spawn().unwrap();
+    let breaker = IsolateCircuitBreaker::new(master.clone())
+        .with_threshold(2)
+        .with_failure(|reply| reply.message == MasterEvent::Refused);
+
+    // A refusal is handed back, but counts towards opening the breaker
+    assert_eq!(
+        ask(&breaker, MasterEvent::Query(0)).unwrap(),
+        MasterEvent::Refused
+    );
+    assert_eq!(breaker.state(), IsolateBreakerState::Closed);
+    assert_eq!(
+        ask(&breaker, MasterEvent::Query(0)).unwrap(),
+        MasterEvent::Refused
+    );
+    assert_eq!(breaker.state(), IsolateBreakerState::Open);
+    match breaker.send(MasterEvent::Query(1).into()) {
+        Err(IsolateBreakerError::Open) => {}
+        result => panic!("Expected to fail fast: {:?}", result),
+    }
+
+    breaker.reset().unwrap();
+    assert_eq!(
+        ask(&breaker, MasterEvent::Query(1)).unwrap(),
+        MasterEvent::Answer(2)
+    );
+
+    drop((master, breaker));
+    masters.wait();
+}
+
+#[test]
+pub fn test_open_on_crash() {
+    let mut masters = IsolateRuntime::new(MasterIsolate {});
+    let identity = IsolateIdentity::new();
+    masters.spawn_as(identity, ()).unwrap();
+    let breaker = IsolateCircuitBreaker::for_worker(masters.as_ref(), identity)
+        .with_threshold(2)
+        .with_reset_after(Duration::from_millis(50));
+
+    // The master crashes, and is gone from the runtime until it is restarted
+    match ask(&breaker, MasterEvent::Crash) {
+        Err(IsolateBreakerError::Disconnected) | Err(IsolateBreakerError::Timeout) => {}
+        result => panic!("Expected the master to crash: {:?}", result),
+    }
+    while masters.as_ref().find(&identity).is_some() {
+        thread::sleep(Duration::from_millis(1));
+    }
+    match ask(&breaker, MasterEvent::Query(1)) {
+        Err(IsolateBreakerError::NoMatchingIdentity) => {}
+        result => panic!("Expected no master: {:?}", result),
+    }
+    assert_eq!(breaker.state(), IsolateBreakerState::Open);
+
+    // The restarted master is found by identity once the breaker half opens
+    masters.spawn_as(identity, ()).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(
+        ask(&breaker, MasterEvent::Query(3)).unwrap(),
+        MasterEvent::Answer(6)
+    );
+    assert_eq!(breaker.state(), IsolateBreakerState::Closed);
+
+    masters.as_ref().stop(&identity).unwrap();
+    drop(breaker);
+    masters.wait();
+}
+
+#[test]
+pub fn test_reset_on_virtual_time() {
+    let mut scheduler = IsolateTestScheduler::new(1);
+    let masters = IsolateRuntime::new(MasterIsolate {});
+    scheduler.attach(&masters.as_ref()).unwrap();
+    let breaker = IsolateCircuitBreaker::for_worker(masters.as_ref(), IsolateIdentity::new())
+        .with_threshold(1)
+        .with_reset_after(Duration::from_secs(60));
+
+    // With no master to ask the breaker opens, and only half opens once the scheduler's clock
+    // has moved on, however long the test itself takes
+    match ask(&breaker, MasterEvent::Query(1)) {
+        Err(IsolateBreakerError::NoMatchingIdentity) => {}
+        result => panic!("Expected no master: {:?}", result),
+    }
+    assert_eq!(breaker.state(), IsolateBreakerState::Open);
+    scheduler.advance(Duration::from_secs(59));
+    assert_eq!(breaker.state(), IsolateBreakerState::Open);
+    scheduler.advance(Duration::from_secs(1));
+    assert_eq!(breaker.state(), IsolateBreakerState::HalfOpen);
+}
@@ -0,0 +1,175 @@
+use crossbeam::unbounded;
+use rust_isolate::Isolate;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolatePipeline;
+use rust_isolate::IsolateRuntimeWait;
+use rust_isolate::IsolateStreamEvent;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// In this example, readings flow from a sensor through stages that scale them, drop the odd
+// ones out and gather them into batches for storage; a slow store holds back the sensor
+// instead of letting readings pile up in memory.
+
+#[test]
+pub fn test_map_filter_batch() {
+    let (stored, batches) = unbounded();
+    let pipeline = IsolatePipeline::source(1..=10)
+        .map(|reading: u32| reading * reading)
+        .filter(|reading| reading % 2 == 0)
+        .batch(2)
+        .sink(move |batch| stored.send(batch).unwrap())
+        .unwrap();
+
+    // The stream ends when the source runs out, and the last batch is flushed
+    pipeline.wait();
+    let batches: Vec<_> = batches.try_iter().collect();
+    assert_eq!(batches, vec![vec![4, 16], vec![36, 64], vec![100]]);
+}
+
+#[test]
+pub fn test_backpressure_and_stop() {
+    let read = Arc::new(AtomicUsize::new(0));
+    let sensor = read.clone();
+    let (stored, readings) = unbounded();
+    let pipeline = IsolatePipeline::source(0..)
+        .with_buffer(2)
+        .map(move |reading: usize| {
+            sensor.fetch_add(1, Ordering::SeqCst);
+            reading
+        })
+        .sink(move |reading| {
+            thread::sleep(Duration::from_millis(5));
+            stored.send(reading).unwrap();
+        })
+        .unwrap();
+
+    // The sensor only runs a few readings ahead of the store
+    thread::sleep(Duration::from_millis(100));
+    let ahead = read.load(Ordering::SeqCst) - readings.len();
+    assert!(ahead <= 4, "The source ran {} readings ahead", ahead);
+
+    // Stopping the source ends the stream once the readings in flight are stored
+    assert!(pipeline.stop().unwrap());
+    pipeline.wait();
+    let readings: Vec<_> = readings.try_iter().collect();
+    assert_eq!(readings, (0..readings.len()).collect::<Vec<_>>());
+    assert_eq!(readings.len(), read.load(Ordering::SeqCst));
+}
+
+#[test]
+pub fn test_failed_stage_ends_stream() {
+    let (stored, readings) = unbounded();
+    let pipeline = IsolatePipeline::source(0..)
+        .map(|reading: u32| {
+            if reading == 3 {
+                panic!("Sensor fault");
+            }
+            reading
+        })
+        .sink(move |reading| stored.send(reading).unwrap())
+        .unwrap();
+
+    // The stages after the failed one end the stream, and those before it stop sending
+    pipeline.wait();
+    let readings: Vec<_> = readings.try_iter().collect();
+    assert_eq!(readings, vec![0, 1, 2]);
+}
+
+/// A stage of our own, which passes on the running total of the readings
+struct RunningTotal {}
+
+impl Isolate<IsolateStreamEvent<u32>> for RunningTotal {
+    type Args = ();
+
+    fn spawn(
+        &self,
+        context: IsolateContext<IsolateStreamEvent<u32>>,
+        _: (),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        let mut total = 0;
+        Box::new(move || {
+            while let Ok(event) = context.recv() {
+                match event {
+                    IsolateStreamEvent::Item(reading) => {
+                        total += reading;
+                        context.reply(IsolateStreamEvent::Item(total)).unwrap();
+                    }
+                    IsolateStreamEvent::End => {
+                        let _ = context.reply(IsolateStreamEvent::End);
+                        return;
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[test]
+pub fn test_worker_stage() {
+    let (stored, totals) = unbounded();
+    let pipeline = IsolatePipeline::source(1..=5)
+        .with_buffer(2)
+        .through(RunningTotal {})
+        .map(|total| total * 10)
+        .sink(move |total| stored.send(total).unwrap())
+        .unwrap();
+
+    pipeline.wait();
+    let totals: Vec<_> = totals.try_iter().collect();
+    assert_eq!(totals, vec![10, 30, 60, 100, 150]);
+}
+
+/// A stage of our own that takes its time over each reading
+struct SlowStage {}
+
+impl Isolate<IsolateStreamEvent<usize>> for SlowStage {
+    type Args = ();
+
+    fn spawn(
+        &self,
+        context: IsolateContext<IsolateStreamEvent<usize>>,
+        _: (),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        Box::new(move || {
+            while let Ok(event) = context.recv() {
+                let end = event == IsolateStreamEvent::End;
+                thread::sleep(Duration::from_millis(5));
+                let _ = context.reply(event);
+                if end {
+                    return;
+                }
+            }
+        })
+    }
+}
+
+#[test]
+pub fn test_worker_stage_backpressure() {
+    let read = Arc::new(AtomicUsize::new(0));
+    let sensor = read.clone();
+    let (stored, readings) = unbounded();
+    let pipeline = IsolatePipeline::source(0..)
+        .with_buffer(2)
+        .map(move |reading: usize| {
+            sensor.fetch_add(1, Ordering::SeqCst);
+            reading
+        })
+        .through(SlowStage {})
+        .sink(move |reading| stored.send(reading).unwrap())
+        .unwrap();
+
+    // The worker is only given more readings as it takes those it has
+    thread::sleep(Duration::from_millis(100));
+    let ahead = read.load(Ordering::SeqCst) - readings.len();
+    assert!(ahead <= 8, "The source ran {} readings ahead", ahead);
+
+    assert!(pipeline.stop().unwrap());
+    pipeline.wait();
+    let readings: Vec<_> = readings.try_iter().collect();
+    assert_eq!(readings, (0..readings.len()).collect::<Vec<_>>());
+    assert_eq!(readings.len(), read.load(Ordering::SeqCst));
+}
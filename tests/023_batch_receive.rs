@@ -0,0 +1,184 @@
+use rust_isolate::Isolate;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateDurableMailbox;
+use rust_isolate::IsolateIdentity;
+use rust_isolate::IsolateRecord;
+use rust_isolate::IsolateRuntime;
+use rust_isolate::IsolateRuntimeWait;
+use std::fs;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+// In this example, an adder keeps a running total shared between workers; rather than take
+// the lock for every update, it receives updates in batches and applies each batch under one
+// lock.
+
+#[derive(Debug, PartialEq)]
+enum AdderEvent {
+    Start,
+    Add(u32),
+    Applied(Vec<u32>),
+}
+
+impl IsolateRecord for AdderEvent {
+    fn to_record(&self) -> Vec<u8> {
+        match self {
+            AdderEvent::Add(n) => (*n as u64).to_record(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn from_record(record: &[u8]) -> Option<Self> {
+        match record {
+            [] => Some(AdderEvent::Start),
+            _ => u64::from_record(record).map(|n| AdderEvent::Add(n as u32)),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Total {
+    value: u32,
+    locks: u32,
+}
+
+struct AdderIsolate {
+    total: Arc<Mutex<Total>>,
+}
+
+impl Isolate<AdderEvent> for AdderIsolate {
+    /// The largest batch to take, and whether to crash after the first one
+    type Args = (usize, bool);
+
+    fn spawn(
+        &self,
+        context: IsolateContext<AdderEvent>,
+        (max, crash): (usize, bool),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        let total = self.total.clone();
+        Box::new(move || {
+            if context.recv_matching(|e| *e == AdderEvent::Start).is_err() {
+                return;
+            }
+            while let Ok(batch) = context.recv_batch(max, Duration::from_secs(5)) {
+                let updates: Vec<_> = batch
+                    .into_iter()
+                    .filter_map(|event| match event {
+                        AdderEvent::Add(n) => Some(n),
+                        _ => None,
+                    })
+                    .collect();
+                {
+                    let mut total = total.lock().unwrap();
+                    total.value += updates.iter().sum::<u32>();
+                    total.locks += 1;
+                }
+                context.reply(AdderEvent::Applied(updates)).unwrap();
+                if crash {
+                    panic!("Crashed after a batch");
+                }
+            }
+        })
+    }
+}
+
+#[test]
+pub fn test_batches_up_to_max() {
+    let total = Arc::new(Mutex::new(Total::default()));
+    let mut adders = IsolateRuntime::new(AdderIsolate {
+        total: total.clone(),
+    });
+    let adder = adders.spawn_with((4usize, false)).unwrap();
+
+    // The updates are waiting before the adder starts, so they come in full batches
+    (1..=10).for_each(|n| adder.send(AdderEvent::Add(n)).unwrap());
+    adder.send(AdderEvent::Start).unwrap();
+    assert_eq!(
+        adder.receiver.recv().unwrap(),
+        AdderEvent::Applied(vec![1, 2, 3, 4])
+    );
+    assert_eq!(
+        adder.receiver.recv().unwrap(),
+        AdderEvent::Applied(vec![5, 6, 7, 8])
+    );
+    assert_eq!(
+        adder.receiver.recv().unwrap(),
+        AdderEvent::Applied(vec![9, 10])
+    );
+    assert_eq!(total.lock().unwrap().value, 55);
+    assert_eq!(total.lock().unwrap().locks, 3);
+    assert_eq!(adders.as_ref().metrics().unwrap().workers[0].received, 11);
+
+    drop(adder);
+    adders.wait();
+}
+
+#[test]
+pub fn test_coalescing_window() {
+    let total = Arc::new(Mutex::new(Total::default()));
+    let mut adders = IsolateRuntime::new(AdderIsolate {
+        total: total.clone(),
+    })
+    .with_coalescing(Duration::from_millis(200));
+    let adder = adders.spawn_with((64usize, false)).unwrap();
+    adder.send(AdderEvent::Start).unwrap();
+
+    // Updates trickling in within the window of the first are applied together
+    thread::sleep(Duration::from_millis(20));
+    for n in 1..=5 {
+        adder.send(AdderEvent::Add(n)).unwrap();
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(
+        adder.receiver.recv().unwrap(),
+        AdderEvent::Applied(vec![1, 2, 3, 4, 5])
+    );
+    assert_eq!(total.lock().unwrap().locks, 1);
+
+    drop(adder);
+    adders.wait();
+}
+
+#[test]
+pub fn test_batch_acknowledged_together() {
+    let directory = std::env::temp_dir().join(format!("isolate-adder-{}", IsolateIdentity::new()));
+    let identity = IsolateIdentity::new();
+    let total = Arc::new(Mutex::new(Total::default()));
+    let open = || IsolateDurableMailbox::new(&directory).unwrap();
+
+    // The adder crashes after applying a batch, before asking for the next one
+    let mut adders = IsolateRuntime::new(AdderIsolate {
+        total: total.clone(),
+    })
+    .with_mailbox(open());
+    let adder = adders.spawn_as(identity, (4usize, true)).unwrap();
+    (1..=3).for_each(|n| adder.send(AdderEvent::Add(n)).unwrap());
+    adder.send(AdderEvent::Start).unwrap();
+    assert_eq!(
+        adder.receiver.recv().unwrap(),
+        AdderEvent::Applied(vec![1, 2, 3])
+    );
+    adders.wait();
+
+    // None of the batch was acknowledged, so all of it is delivered again
+    let mut adders = IsolateRuntime::new(AdderIsolate {
+        total: total.clone(),
+    })
+    .with_mailbox(open());
+    let adder = adders.spawn_as(identity, (4usize, false)).unwrap();
+    adder.send(AdderEvent::Start).unwrap();
+    assert_eq!(
+        adder.receiver.recv().unwrap(),
+        AdderEvent::Applied(vec![1, 2, 3])
+    );
+    // Let the adder drain what is left, such as the start message of the first run, which may
+    // come again since only acknowledgements in order are kept on disk
+    drop(adder);
+    adders.wait();
+    assert!(open().pending().unwrap().is_empty());
+    assert_eq!(total.lock().unwrap().value, 12);
+
+    fs::remove_dir_all(directory).unwrap();
+}
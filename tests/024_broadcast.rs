@@ -0,0 +1,105 @@
+use rust_isolate::Isolate;
+use rust_isolate::IsolateChannelError;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateRateLimit;
+use rust_isolate::IsolateRegistry;
+use std::time::Duration;
+
+// In this example, a chat server announces to every connected client without keeping a map of
+// connections of its own; clients can open side channels, which only hear what is meant for
+// them.
+
+#[derive(Debug, Clone, PartialEq)]
+enum ChatEvent {
+    Announce(String),
+    OpenSideChannel,
+    Opened,
+}
+
+struct ClientIsolate {}
+
+impl Isolate<ChatEvent> for ClientIsolate {
+    type Args = ();
+
+    fn spawn(
+        &self,
+        context: IsolateContext<ChatEvent>,
+        _: (),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        Box::new(move || {
+            while let Ok(event) = context.recv() {
+                match event {
+                    ChatEvent::OpenSideChannel => {
+                        context.spawn().unwrap();
+                        context.reply(ChatEvent::Opened).unwrap();
+                    }
+                    event => context.reply(event).unwrap(),
+                }
+            }
+        })
+    }
+}
+
+fn announce(text: &str) -> ChatEvent {
+    ChatEvent::Announce(text.to_string())
+}
+
+#[test]
+pub fn test_broadcast_to_all() {
+    let mut registry = IsolateRegistry::new();
+    let mut clients = registry.bind("Clients", ClientIsolate {}).unwrap();
+    let alice = clients.spawn().unwrap();
+    let bob = clients.spawn().unwrap();
+
+    let report = clients.broadcast(announce("Welcome")).unwrap();
+    assert!(report.is_complete());
+    assert_eq!(report.delivered.len(), 2);
+    assert_eq!(alice.receiver.recv().unwrap(), announce("Welcome"));
+    assert_eq!(bob.receiver.recv().unwrap(), announce("Welcome"));
+
+    // Side channels are spawned by clients, so leaving out workers with a parent skips them
+    alice.send(ChatEvent::OpenSideChannel).unwrap();
+    assert_eq!(alice.receiver.recv().unwrap(), ChatEvent::Opened);
+    assert_eq!(clients.metrics().unwrap().workers.len(), 3);
+    let report = clients
+        .broadcast_where(announce("Clients only"), |worker| {
+            worker.parent.is_none() && worker.runtime.as_deref() == Some("Clients")
+        })
+        .unwrap();
+    assert_eq!(report.delivered.len(), 2);
+    assert_eq!(alice.receiver.recv().unwrap(), announce("Clients only"));
+    assert_eq!(bob.receiver.recv().unwrap(), announce("Clients only"));
+
+    drop((alice, bob));
+    registry.wait();
+}
+
+#[test]
+pub fn test_report_failed_deliveries() {
+    let mut registry = IsolateRegistry::new();
+    let mut clients = registry.bind("Clients", ClientIsolate {}).unwrap();
+    let alice = clients.spawn().unwrap();
+    let alice_identity = clients.metrics().unwrap().workers[0].identity;
+
+    // Bob joins after the server starts limiting messages, and can take only one a minute
+    clients
+        .set_rate_limit(IsolateRateLimit::new(1, Duration::from_secs(60)))
+        .unwrap();
+    let bob = clients.spawn().unwrap();
+    assert!(clients.broadcast(announce("First")).unwrap().is_complete());
+
+    let report = clients.broadcast(announce("Second")).unwrap();
+    assert_eq!(report.delivered, vec![alice_identity]);
+    assert_eq!(report.failed.len(), 1);
+    assert_ne!(report.failed[0].0, alice_identity);
+    match &report.failed[0].1 {
+        IsolateChannelError::Throttled => {}
+        err => panic!("Expected a throttled delivery: {:?}", err),
+    }
+    assert_eq!(alice.receiver.recv().unwrap(), announce("First"));
+    assert_eq!(alice.receiver.recv().unwrap(), announce("Second"));
+    assert_eq!(bob.receiver.recv().unwrap(), announce("First"));
+
+    drop((alice, bob));
+    registry.wait();
+}
@@ -0,0 +1,116 @@
+use rust_isolate::Isolate;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateRuntime;
+use rust_isolate::IsolateRuntimeWait;
+use std::thread;
+
+// In this example, each connection to a chat server is a worker tagged with the user it
+// belongs to, so the server can reach every connection of a user without keeping a map of its
+// own; connections tag themselves with the room they join.
+
+#[derive(Debug, Clone, PartialEq)]
+enum ConnectionEvent {
+    Join(String),
+    Leave,
+    Close,
+    Post(String),
+    Joined(Option<String>),
+    Left(Option<String>),
+}
+
+struct ConnectionIsolate {}
+
+impl Isolate<ConnectionEvent> for ConnectionIsolate {
+    type Args = ();
+
+    fn spawn(
+        &self,
+        context: IsolateContext<ConnectionEvent>,
+        _: (),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        Box::new(move || {
+            while let Ok(event) = context.recv() {
+                match event {
+                    ConnectionEvent::Join(room) => {
+                        context.set_tag("room", &room);
+                        let user = context.tag("user");
+                        context.reply(ConnectionEvent::Joined(user)).unwrap();
+                    }
+                    ConnectionEvent::Leave => {
+                        let room = context.remove_tag("room");
+                        context.reply(ConnectionEvent::Left(room)).unwrap();
+                    }
+                    ConnectionEvent::Close => return,
+                    event => context.reply(event).unwrap(),
+                }
+            }
+        })
+    }
+}
+
+fn post(text: &str) -> ConnectionEvent {
+    ConnectionEvent::Post(text.to_string())
+}
+
+#[test]
+pub fn test_find_by_tag() {
+    let mut connections = IsolateRuntime::new(ConnectionIsolate {});
+    let phone = connections.spawn_tagged((), &[("user", "42")]).unwrap();
+    let laptop = connections.spawn_tagged((), &[("user", "42")]).unwrap();
+    let other = connections.spawn_tagged((), &[("user", "7")]).unwrap();
+
+    // Every connection of user 42 gets the message, and no one else's
+    let found = connections.as_ref().find_tagged("user", "42");
+    assert_eq!(found.len(), 2);
+    found
+        .iter()
+        .for_each(|connection| connection.send(post("For 42")).unwrap());
+    assert_eq!(phone.receiver.recv().unwrap(), post("For 42"));
+    assert_eq!(laptop.receiver.recv().unwrap(), post("For 42"));
+    assert!(other.receiver.try_recv().is_err());
+    assert!(connections.as_ref().find_tagged("user", "1").is_empty());
+
+    // A connection that has closed is no longer found
+    laptop.send(ConnectionEvent::Close).unwrap();
+    while connections.as_ref().find_tagged("user", "42").len() > 1 {
+        thread::yield_now();
+    }
+    assert_eq!(connections.as_ref().find_tagged("user", "7").len(), 1);
+
+    drop((found, phone, laptop, other));
+    connections.wait();
+}
+
+#[test]
+pub fn test_tags_set_by_worker() {
+    let mut connections = IsolateRuntime::new(ConnectionIsolate {});
+    let phone = connections.spawn_tagged((), &[("user", "42")]).unwrap();
+    let other = connections.spawn_tagged((), &[("user", "7")]).unwrap();
+
+    phone
+        .send(ConnectionEvent::Join("rust".to_string()))
+        .unwrap();
+    let joined = ConnectionEvent::Joined(Some("42".to_string()));
+    assert_eq!(phone.receiver.recv().unwrap(), joined);
+    other.send(ConnectionEvent::Join("go".to_string())).unwrap();
+    other.receiver.recv().unwrap();
+
+    // Tags are metadata for broadcasts too
+    let report = connections
+        .as_ref()
+        .broadcast_where(post("For rust"), |worker| {
+            worker.tags.get("room").map(|room| room.as_str()) == Some("rust")
+        })
+        .unwrap();
+    assert_eq!(report.delivered.len(), 1);
+    assert_eq!(phone.receiver.recv().unwrap(), post("For rust"));
+
+    phone.send(ConnectionEvent::Leave).unwrap();
+    let left = ConnectionEvent::Left(Some("rust".to_string()));
+    assert_eq!(phone.receiver.recv().unwrap(), left);
+    assert!(connections.as_ref().find_tagged("room", "rust").is_empty());
+    assert_eq!(connections.as_ref().find_tagged("room", "go").len(), 1);
+
+    drop((phone, other));
+    connections.wait();
+}
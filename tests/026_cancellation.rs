@@ -0,0 +1,128 @@
+use rust_isolate::Isolate;
+use rust_isolate::IsolateContext;
+use rust_isolate::IsolateIdentity;
+use rust_isolate::IsolateRegistry;
+use rust_isolate::IsolateRegistryError;
+use rust_isolate::IsolateRuntime;
+use rust_isolate::IsolateRuntimeWait;
+use std::thread;
+use std::time::Duration;
+
+// In this example, workers crunch numbers for as long as they are allowed to; they check their
+// cancellation token as they go, so the runtime can stop them part way through a job without a
+// message protocol of their own.
+
+#[derive(Debug, PartialEq)]
+enum CrunchEvent {
+    Crunch,
+    Started,
+    Cancelled(u64),
+    Watched(bool),
+}
+
+struct CruncherIsolate {}
+
+impl Isolate<CrunchEvent> for CruncherIsolate {
+    type Args = ();
+
+    fn spawn(
+        &self,
+        context: IsolateContext<CrunchEvent>,
+        _: (),
+    ) -> Box<dyn FnMut() + Send + 'static> {
+        Box::new(move || {
+            // A helper thread sleeps until the worker is cancelled
+            let token = context.cancellation();
+            let watcher = thread::spawn(move || token.wait_timeout(Duration::from_secs(10)));
+
+            while let Ok(CrunchEvent::Crunch) = context.recv() {
+                context.reply(CrunchEvent::Started).unwrap();
+                let token = context.cancellation();
+                let mut rounds = 0;
+                while token.check().is_ok() {
+                    rounds += 1;
+                    thread::sleep(Duration::from_millis(1));
+                }
+                context.reply(CrunchEvent::Cancelled(rounds)).unwrap();
+            }
+            let watched = watcher.join().unwrap();
+            let _ = context.reply(CrunchEvent::Watched(watched));
+        })
+    }
+}
+
+fn cancelled(event: CrunchEvent) -> bool {
+    match event {
+        CrunchEvent::Cancelled(rounds) => rounds > 0,
+        _ => false,
+    }
+}
+
+#[test]
+pub fn test_kill_long_running_worker() {
+    let mut crunchers = IsolateRuntime::new(CruncherIsolate {});
+    let identity = IsolateIdentity::new();
+    let cruncher = crunchers.spawn_as(identity, ()).unwrap();
+    cruncher.send(CrunchEvent::Crunch).unwrap();
+    assert_eq!(cruncher.receiver.recv().unwrap(), CrunchEvent::Started);
+
+    // The worker is gone from the runtime at once, and stops at its next check
+    assert!(crunchers.as_ref().kill(&identity).unwrap());
+    assert!(crunchers.as_ref().find(&identity).is_none());
+    assert!(!crunchers.as_ref().kill(&identity).unwrap());
+    assert!(cancelled(cruncher.receiver.recv().unwrap()));
+    assert_eq!(
+        cruncher.receiver.recv().unwrap(),
+        CrunchEvent::Watched(true)
+    );
+
+    crunchers.wait();
+}
+
+#[test]
+pub fn test_shutdown_runtime() {
+    let mut crunchers = IsolateRuntime::new(CruncherIsolate {});
+    let busy = crunchers.spawn().unwrap();
+    let idle = crunchers.spawn().unwrap();
+    busy.send(CrunchEvent::Crunch).unwrap();
+    assert_eq!(busy.receiver.recv().unwrap(), CrunchEvent::Started);
+
+    // Both the worker crunching and the one waiting for a message are stopped
+    crunchers.shutdown();
+    assert!(cancelled(busy.receiver.recv().unwrap()));
+    assert_eq!(busy.receiver.recv().unwrap(), CrunchEvent::Watched(true));
+    assert_eq!(idle.receiver.recv().unwrap(), CrunchEvent::Watched(true));
+}
+
+#[test]
+pub fn test_unbind_from_registry() {
+    let mut registry = IsolateRegistry::new();
+    let mut crunchers = registry.bind("Crunchers", CruncherIsolate {}).unwrap();
+    let cruncher = crunchers.spawn().unwrap();
+    cruncher.send(CrunchEvent::Crunch).unwrap();
+    assert_eq!(cruncher.receiver.recv().unwrap(), CrunchEvent::Started);
+
+    registry.unbind("Crunchers").unwrap();
+    assert!(cancelled(cruncher.receiver.recv().unwrap()));
+    assert_eq!(
+        cruncher.receiver.recv().unwrap(),
+        CrunchEvent::Watched(true)
+    );
+    match registry.find::<CrunchEvent>("Crunchers") {
+        Err(IsolateRegistryError::NoMatchingIdentity) => {}
+        result => panic!("Expected the runtime to be unbound: {:?}", result.is_ok()),
+    }
+    match registry.unbind("Crunchers") {
+        Err(IsolateRegistryError::NoMatchingIdentity) => {}
+        result => panic!("Expected nothing to unbind: {:?}", result),
+    }
+
+    // The name is free to bind again
+    let mut crunchers = registry.bind("Crunchers", CruncherIsolate {}).unwrap();
+    let cruncher = crunchers.spawn().unwrap();
+    registry.shutdown();
+    assert_eq!(
+        cruncher.receiver.recv().unwrap(),
+        CrunchEvent::Watched(true)
+    );
+}